@@ -1,14 +1,36 @@
 use dioxus::prelude::*;
 use dioxus_heroicons::outline::Shape;
-use emojis::{Group, UnicodeVersion};
+use emojis::Group;
 use sir::css;
+use state::{emoji::SkinTone, Actions, PersistedState, STATE};
 use ui_kit::{
     button::{self, Button},
     outside::OutsideClick,
 };
-use utils::extensions::{BasicExtension, ExtensionInfo, ExtensionType};
+use utils::{
+    extensions::{BasicExtension, ExtensionInfo, ExtensionType},
+    Storage,
+};
+
+pub mod packs;
+pub mod shortcodes;
 
-static MAX_UNICODE_VER: UnicodeVersion = UnicodeVersion::new(11, 0);
+// the number of emojis in each row. used to move up and down with the arrow keys
+const COLUMNS: usize = 12;
+// the number of columns taken by a sticker
+const STICKER_SPAN: usize = 3;
+
+// an item in the selector
+#[derive(Clone, PartialEq)]
+enum Entry {
+    Emoji(String),
+    // an emoji or sticker from a custom pack, inserted as `:name:`
+    Custom {
+        name: String,
+        src: String,
+        sticker: bool,
+    },
+}
 
 fn get_group_name(group: Group) -> String {
     match group {
@@ -53,10 +75,43 @@ impl BasicExtension for EmojiSelector {
             right: 20px;
             max-width: calc(100% - 20px); // -20px is based on the 'right' attribute above
             overflow-y: scroll;
+            outline: none;
             &::-webkit-scrollbar-thumb {
                 background: var(--theme-primary) !important;
                 opacity: 1;
             }
+            .controls {
+                display: flex;
+                align-items: center;
+                gap: 0.5rem;
+                padding: 0.25rem;
+
+                .search {
+                    flex: 1;
+                    padding: 0.4rem 0.75rem;
+                    border: 1px solid var(--theme-borders);
+                    border-radius: 20px;
+                    background: var(--theme-foreground);
+                    color: var(--theme-text);
+                    outline: none;
+                }
+
+                .import-error {
+                    color: var(--theme-red);
+                    font-size: var(--text-small);
+                }
+
+                .skin-tone {
+                    cursor: pointer;
+                    border-radius: 4px;
+                    padding: 0.125rem;
+                    opacity: 0.6;
+                    &.selected {
+                        opacity: 1;
+                        background: var(--theme-secondary);
+                    }
+                }
+            }
             .category {
                 display: inline-flex;
                 flex-direction: column;
@@ -66,37 +121,201 @@ impl BasicExtension for EmojiSelector {
 
                 .items {
                     display: grid;
-                    grid-template-columns: repeat(auto-fit, minmax(30px, 1fr));
+                    grid-template-columns: repeat(12, 1fr);
                     .item {
                         cursor: pointer;
+                        border-radius: 4px;
+                        &.focused {
+                            background: var(--theme-secondary);
+                        }
+                        img {
+                            height: 1.5em;
+                            width: auto;
+                        }
+                    }
+                    .item.sticker {
+                        grid-column: span 3;
+                        img {
+                            height: 5rem;
+                        }
                     }
                 }
             }
         "
         );
 
+        let state = use_atom_ref(&cx, STATE);
         let eval = use_eval(&cx);
-        let insert = move |val: &str| {
+        let search_value = use_state(&cx, String::new);
+        // the emoji selected with the arrow keys, as (category, index in the category)
+        let focused: &UseState<Option<(usize, usize)>> = use_state(&cx, || None);
+        let skin_tone = state.read().emoji_skin_tone;
+        let import_error = use_state(&cx, String::new);
+        let storage = use_state(&cx, || cx.consume_context::<Storage>());
+
+        // packs imported on another device are installed when the selector is opened
+        let storage2 = storage.get().clone();
+        use_future(&cx, (), |_| async move {
+            if let Some(mut storage) = storage2 {
+                packs::sync(&mut storage).await;
+            }
+        });
+
+        let import = move |bytes: Result<Vec<u8>, anyhow::Error>| {
+            let result = bytes.and_then(|bytes| Ok((packs::install_zip(&bytes)?, bytes)));
+            match result {
+                Ok((pack, bytes)) => {
+                    import_error.set(String::new());
+                    if let Some(mut storage) = storage.get().clone() {
+                        cx.spawn(async move {
+                            if let Err(e) =
+                                packs::upload(&mut storage, &pack.manifest.id, &bytes).await
+                            {
+                                log::error!(
+                                    "failed to store emoji pack {}: {}",
+                                    pack.manifest.id,
+                                    e
+                                );
+                            }
+                        });
+                    }
+                }
+                Err(e) => import_error.set(format!("Import failed: {}", e)),
+            }
+        };
+
+        // the cursor position is lost when the composer is blurred, so it is saved on every change
+        let insert = move |val: String| {
             eval(format!(
                 "
-                document.querySelector('#TODO-textarea-input .dynamic-input').focus();
-                document.execCommand('insertText', false, '{}');
+                const input = document.querySelector('#TODO-textarea-input .dynamic-input');
+                input.focus();
+                const range = window.composerRange;
+                if (range && input.contains(range.startContainer)) {{
+                    const selection = window.getSelection();
+                    selection.removeAllRanges();
+                    selection.addRange(range);
+                }}
+                document.execCommand('insertText', false, {});
             ",
-                val
+                js_string(&val)
             ));
         };
+        let select = move |entry: Entry| match entry {
+            Entry::Emoji(emoji) => {
+                insert(emoji.clone());
+                state.write().dispatch(Actions::RecordEmojiUsage(emoji));
+            }
+            Entry::Custom { name, .. } => insert(format!(":{}: ", name)),
+        };
+
+        let categories = get_categories(&state.read(), search_value.get(), skin_tone);
+        let grid: Vec<(usize, usize)> = categories
+            .iter()
+            .map(|(_, emojis)| (emojis.len(), span(emojis)))
+            .collect();
+        let entries: Vec<Vec<Entry>> = categories
+            .iter()
+            .map(|(_, emojis)| emojis.clone())
+            .collect();
+        // shows each skin tone in the picker
+        let hand = emojis::get("\u{270b}");
 
-        let groups = Group::iter();
         cx.render(rsx! {
                 div {
                     class: "ext-emoji-selector",
+                    script {
+                        "
+                        if (!window.composerRangeListener) {{
+                            window.composerRangeListener = true;
+                            document.addEventListener('selectionchange', () => {{
+                                const input = document.querySelector('#TODO-textarea-input .dynamic-input');
+                                const selection = window.getSelection();
+                                if (input && selection.rangeCount > 0 && input.contains(selection.anchorNode)) {{
+                                    window.composerRange = selection.getRangeAt(0).cloneRange();
+                                }}
+                            }});
+                        }}
+                        "
+                    },
                     rsx! {
                         OutsideClick {
                         div {
                             onblur: |_| println!("blur"),
                             class: "{styles}",
-                            groups.map(|group| {
-                                let name = get_group_name(group);
+                            tabindex: "0",
+                            onkeydown: move |e| {
+                                if let ("Enter", Some((category, idx))) = (e.key.as_str(), **focused) {
+                                    if let Some(entry) = entries.get(category).and_then(|emojis| emojis.get(idx)) {
+                                        select(entry.clone());
+                                    }
+                                    return;
+                                }
+                                if let Some(next) = move_focus(&grid, **focused, e.key.as_str()) {
+                                    focused.set(Some(next));
+                                    eval(String::from("setTimeout(() => document.querySelector('.ext-emoji-selector .item.focused')?.scrollIntoView({ block: 'nearest' }), 0);"));
+                                }
+                            },
+                            div {
+                                class: "controls",
+                                input {
+                                    class: "search",
+                                    placeholder: "Search emojis",
+                                    value: "{search_value}",
+                                    oninput: move |e| {
+                                        search_value.set(e.value.clone());
+                                        focused.set(None);
+                                    },
+                                    // left and right move the caret. up, down and enter still reach the grid
+                                    onkeydown: move |e| {
+                                        if matches!(e.key.as_str(), "ArrowLeft" | "ArrowRight") {
+                                            e.cancel_bubble();
+                                        }
+                                    },
+                                },
+                                SkinTone::all().into_iter().map(|tone| {
+                                    let selected = if tone == skin_tone { "selected" } else { "" };
+                                    let preview = hand
+                                        .map(|hand| shortcodes::with_skin_tone(hand, tone).as_str())
+                                        .unwrap_or_default();
+                                    let label = tone.label();
+                                    rsx!(
+                                        span {
+                                            class: "skin-tone {selected}",
+                                            title: "{label}",
+                                            onclick: move |_| state.write().dispatch(Actions::SetSkinTone(tone)),
+                                            "{preview}"
+                                        }
+                                    )
+                                }),
+                                button {
+                                    class: "import",
+                                    title: "Import an emoji pack from a folder containing a manifest.json",
+                                    onclick: move |_| {
+                                        if let Some(dir) = rfd::FileDialog::new().pick_folder() {
+                                            import(packs::zip_directory(&dir));
+                                        }
+                                    },
+                                    "Import Folder"
+                                },
+                                button {
+                                    class: "import",
+                                    title: "Import an emoji pack from a zip containing a manifest.json",
+                                    onclick: move |_| {
+                                        if let Some(file) = rfd::FileDialog::new().add_filter("zip", &["zip"]).pick_file() {
+                                            import(std::fs::read(file).map_err(anyhow::Error::from));
+                                        }
+                                    },
+                                    "Import Zip"
+                                },
+                            },
+                            (!import_error.is_empty()).then(|| rsx!(
+                                div {
+                                    class: "import-error",
+                                    "{import_error}"
+                                }
+                            )),
+                            categories.into_iter().enumerate().map(|(category, (name, emojis))| {
                                 rsx!(
                                     div {
                                         class: "category",
@@ -106,18 +325,30 @@ impl BasicExtension for EmojiSelector {
                                         },
                                         div {
                                             class: "items",
-                                            group.emojis()
-                                                .filter(|v| v.unicode_version() <= MAX_UNICODE_VER)
-                                                .map(|v| {
-                                                    let name = v.name();
-                                                    let emoji = v.as_str();
-                                                    rsx!(button {
-                                                        onclick: move |_| insert(emoji),
-                                                        class: "item",
-                                                        title: "{name}",
-                                                        "{v}"
-                                                    })
-                                                })
+                                            emojis.into_iter().enumerate().map(|(idx, entry)| {
+                                                let focused_class = if **focused == Some((category, idx)) { "focused" } else { "" };
+                                                let entry2 = entry.clone();
+                                                match entry {
+                                                    Entry::Emoji(emoji) => {
+                                                        let title = emojis::get(&emoji).map(|e| e.name()).unwrap_or_default();
+                                                        rsx!(button {
+                                                            onclick: move |_| select(entry2.clone()),
+                                                            class: "item {focused_class}",
+                                                            title: "{title}",
+                                                            "{emoji}"
+                                                        })
+                                                    }
+                                                    Entry::Custom { name, src, sticker } => {
+                                                        let sticker_class = if sticker { "sticker" } else { "" };
+                                                        rsx!(button {
+                                                            onclick: move |_| select(entry2.clone()),
+                                                            class: "item {sticker_class} {focused_class}",
+                                                            title: ":{name}:",
+                                                            img { src: "{src}", alt: ":{name}:" }
+                                                        })
+                                                    }
+                                                }
+                                            })
                                         },
                                     }
                                 )
@@ -128,12 +359,158 @@ impl BasicExtension for EmojiSelector {
                             on_pressed: move |_| {}
                         }
                     }
-                } 
+                }
             }
         })
     }
 }
 
+// the categories shown in the selector and the emojis in each, with the skin tone applied
+fn get_categories(
+    state: &PersistedState,
+    search: &str,
+    skin_tone: SkinTone,
+) -> Vec<(String, Vec<Entry>)> {
+    let packs = packs::installed();
+    if !search.trim().is_empty() {
+        let query = search.trim().to_lowercase();
+        let matching = |stickers: bool| -> Vec<Entry> {
+            packs
+                .iter()
+                .flat_map(|pack| custom_entries(pack, stickers))
+                .filter(|entry| matches!(entry, Entry::Custom { name, .. } if name.to_lowercase().contains(&query)))
+                .collect()
+        };
+        let mut results = matching(false);
+        results.extend(
+            shortcodes::search(search, skin_tone)
+                .into_iter()
+                .map(|emoji| Entry::Emoji(emoji.as_str().to_string())),
+        );
+        // stickers are wider, so they get their own grid
+        let mut categories = vec![(String::from("Search Results"), results)];
+        let stickers = matching(true);
+        if !stickers.is_empty() {
+            categories.push((String::from("Sticker Results"), stickers));
+        }
+        return categories;
+    }
+
+    let mut categories = vec![];
+    let recent = state.emoji_usage.recent(COLUMNS);
+    if !recent.is_empty() {
+        let entries = |emojis: Vec<String>| emojis.into_iter().map(Entry::Emoji).collect();
+        categories.push((String::from("Recently Used"), entries(recent)));
+        categories.push((
+            String::from("Frequently Used"),
+            entries(state.emoji_usage.frequent(COLUMNS)),
+        ));
+    }
+    for pack in &packs {
+        let emojis = custom_entries(pack, false);
+        if !emojis.is_empty() {
+            categories.push((pack.manifest.name.clone(), emojis));
+        }
+    }
+    categories.extend(Group::iter().map(|group| {
+        let emojis = shortcodes::base_emojis()
+            .filter(|emoji| emoji.group() == group)
+            .map(|emoji| {
+                Entry::Emoji(
+                    shortcodes::with_skin_tone(emoji, skin_tone)
+                        .as_str()
+                        .to_string(),
+                )
+            })
+            .collect();
+        (get_group_name(group), emojis)
+    }));
+    for pack in &packs {
+        let stickers = custom_entries(pack, true);
+        if !stickers.is_empty() {
+            categories.push((format!("{} Stickers", pack.manifest.name), stickers));
+        }
+    }
+    categories
+}
+
+// the number of columns taken by each item of a category. a category holds either stickers or emojis
+fn span(entries: &[Entry]) -> usize {
+    match entries.first() {
+        Some(Entry::Custom { sticker: true, .. }) => STICKER_SPAN,
+        _ => 1,
+    }
+}
+
+// where the arrow `key` moves the focus. `grid` has the number of items of each category and the number of columns
+// each item spans. up and down keep the column, and move between categories at their first and last rows.
+fn move_focus(
+    grid: &[(usize, usize)],
+    focused: Option<(usize, usize)>,
+    key: &str,
+) -> Option<(usize, usize)> {
+    let next_category = |from: usize| (from + 1..grid.len()).find(|c| grid[*c].0 > 0);
+    let previous_category = |from: usize| (0..from).rev().find(|c| grid[*c].0 > 0);
+    let (category, idx) = match focused {
+        Some(focused) => focused,
+        None => {
+            return match key {
+                "ArrowRight" | "ArrowDown" => {
+                    grid.iter().position(|(len, _)| *len > 0).map(|c| (c, 0))
+                }
+                _ => None,
+            }
+        }
+    };
+    let (len, span) = grid[category];
+    let columns = COLUMNS / span;
+    // the position of the focused item within the row, in grid columns
+    let x = (idx % columns) * span;
+    match key {
+        "ArrowRight" if idx + 1 < len => Some((category, idx + 1)),
+        "ArrowRight" => next_category(category).map(|c| (c, 0)),
+        "ArrowLeft" if idx > 0 => Some((category, idx - 1)),
+        "ArrowLeft" => previous_category(category).map(|c| (c, grid[c].0 - 1)),
+        "ArrowDown" if idx + columns < len => Some((category, idx + columns)),
+        // the last row is shorter
+        "ArrowDown" if idx / columns < (len - 1) / columns => Some((category, len - 1)),
+        "ArrowDown" => next_category(category).map(|c| {
+            let (len, span) = grid[c];
+            (c, (x / span).min(len - 1))
+        }),
+        "ArrowUp" if idx >= columns => Some((category, idx - columns)),
+        "ArrowUp" => previous_category(category).map(|c| {
+            let (len, span) = grid[c];
+            let columns = COLUMNS / span;
+            let last_row = (len - 1) / columns * columns;
+            (c, (last_row + x / span).min(len - 1))
+        }),
+        _ => None,
+    }
+}
+
+// `value` as a JavaScript string literal
+fn js_string(value: &str) -> String {
+    // JSON strings are JavaScript strings, except that older engines end lines at these separators
+    serde_json::to_string(value)
+        .unwrap_or_else(|_| String::from("\"\""))
+        .replace('\u{2028}', "\\u2028")
+        .replace('\u{2029}', "\\u2029")
+}
+
+fn custom_entries(pack: &packs::Pack, stickers: bool) -> Vec<Entry> {
+    pack.items()
+        .filter(|(_, sticker)| *sticker == stickers)
+        .filter_map(|(item, sticker)| {
+            Some(Entry::Custom {
+                name: item.name.clone(),
+                src: packs::data_uri(pack, item)?,
+                sticker,
+            })
+        })
+        .collect()
+}
+
 #[no_mangle]
 pub extern "C" fn ret_rend() -> Box<fn(Scope) -> Element> {
     Box::new(EmojiSelector::render)
@@ -143,3 +520,58 @@ pub extern "C" fn ret_rend() -> Box<fn(Scope) -> Element> {
 pub extern "C" fn ret_info() -> Box<ExtensionInfo> {
     Box::new(EmojiSelector::info())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // recently used, then a short category, then stickers
+    const GRID: [(usize, usize); 4] = [(12, 1), (0, 1), (14, 1), (5, STICKER_SPAN)];
+
+    #[test]
+    fn the_first_key_focuses_the_first_item() {
+        assert_eq!(move_focus(&GRID, None, "ArrowDown"), Some((0, 0)));
+        assert_eq!(move_focus(&GRID, None, "ArrowRight"), Some((0, 0)));
+        assert_eq!(move_focus(&GRID, None, "ArrowUp"), None);
+        assert_eq!(move_focus(&[(0, 1)], None, "ArrowDown"), None);
+    }
+
+    #[test]
+    fn left_and_right_cross_categories() {
+        assert_eq!(move_focus(&GRID, Some((0, 11)), "ArrowRight"), Some((2, 0)));
+        assert_eq!(move_focus(&GRID, Some((2, 0)), "ArrowLeft"), Some((0, 11)));
+        assert_eq!(move_focus(&GRID, Some((3, 4)), "ArrowRight"), None);
+        assert_eq!(move_focus(&GRID, Some((0, 0)), "ArrowLeft"), None);
+    }
+
+    #[test]
+    fn up_and_down_keep_the_column() {
+        // skips the empty category
+        assert_eq!(move_focus(&GRID, Some((0, 5)), "ArrowDown"), Some((2, 5)));
+        assert_eq!(move_focus(&GRID, Some((2, 5)), "ArrowUp"), Some((0, 5)));
+        // within the category
+        assert_eq!(move_focus(&GRID, Some((2, 1)), "ArrowDown"), Some((2, 13)));
+        assert_eq!(move_focus(&GRID, Some((2, 13)), "ArrowUp"), Some((2, 1)));
+        // to the end of a shorter last row
+        assert_eq!(move_focus(&GRID, Some((2, 5)), "ArrowDown"), Some((2, 13)));
+    }
+
+    #[test]
+    fn stickers_span_columns() {
+        let grid = [(12, 1), (5, STICKER_SPAN)];
+        // the seventh column is covered by the third sticker
+        assert_eq!(move_focus(&grid, Some((0, 7)), "ArrowDown"), Some((1, 2)));
+        assert_eq!(move_focus(&grid, Some((1, 2)), "ArrowUp"), Some((0, 6)));
+        // four stickers in a row
+        assert_eq!(move_focus(&grid, Some((1, 1)), "ArrowDown"), Some((1, 4)));
+        assert_eq!(move_focus(&grid, Some((1, 4)), "ArrowUp"), Some((1, 0)));
+    }
+
+    #[test]
+    fn inserted_text_is_a_string_literal() {
+        assert_eq!(js_string("🎉"), "\"🎉\"");
+        assert_eq!(js_string("'); alert(1); ('"), "\"'); alert(1); ('\"");
+        assert_eq!(js_string("a\"b\\c\n"), "\"a\\\"b\\\\c\\n\"");
+        assert_eq!(js_string("a\u{2028}b"), "\"a\\u2028b\"");
+    }
+}
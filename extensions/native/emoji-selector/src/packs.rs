@@ -0,0 +1,624 @@
+//! custom emoji and sticker packs.
+//!
+//! a pack is a zip file (or a directory) containing a `manifest.json` and the images it lists. installed packs are
+//! extracted to `DEFAULT_PATH/emoji-packs/<pack id>` and the zip is stored in Constellation, under the app's hidden
+//! directory, so the packs roam with the account. messages reference a custom emoji as `:name@pack_id:`, and the image is attached as well so that
+//! recipients who don't have the pack still see it.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{Cursor, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Context};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use utils::{
+    upload_queue::{in_directory, APP_DIRECTORY},
+    Storage, DEFAULT_PATH,
+};
+use warp::sync::RwLock;
+
+pub const MANIFEST_NAME: &str = "manifest.json";
+/// the prefix of the pack archives stored in Constellation
+pub const STORAGE_PREFIX: &str = "uplink-emoji-pack-";
+// archives come from other users, so what is extracted from them is limited
+const MAX_MANIFEST_SIZE: u64 = 1024 * 1024;
+const MAX_IMAGE_SIZE: u64 = 5 * 1024 * 1024;
+const MAX_PACK_SIZE: u64 = 50 * 1024 * 1024;
+
+static INSTALLED_PACKS: Lazy<RwLock<Vec<Pack>>> = Lazy::new(|| RwLock::new(load_installed()));
+// images are rendered on every message, so their data URIs are kept until a pack changes
+static DATA_URIS: Lazy<RwLock<HashMap<PathBuf, String>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PackItem {
+    /// referenced as `:name:` while typing
+    pub name: String,
+    /// the image, relative to the manifest
+    pub file: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PackManifest {
+    /// shared by everyone who has the pack. used to resolve references in messages
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub emojis: Vec<PackItem>,
+    #[serde(default)]
+    pub stickers: Vec<PackItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pack {
+    pub manifest: PackManifest,
+    pub dir: PathBuf,
+}
+
+impl Pack {
+    pub fn items(&self) -> impl Iterator<Item = (&PackItem, bool)> {
+        self.manifest
+            .emojis
+            .iter()
+            .map(|item| (item, false))
+            .chain(self.manifest.stickers.iter().map(|item| (item, true)))
+    }
+
+    pub fn find(&self, name: &str) -> Option<(&PackItem, bool)> {
+        self.items().find(|(item, _)| item.name == name)
+    }
+
+    pub fn path(&self, item: &PackItem) -> PathBuf {
+        self.dir.join(&item.file)
+    }
+
+    /// the name used when the image is attached to a message. includes the pack id so that the attachment can be
+    /// hidden by recipients who have the pack.
+    pub fn attachment_name(&self, item: &PackItem) -> String {
+        let extension = Path::new(&item.file)
+            .extension()
+            .map(|ext| format!(".{}", ext.to_string_lossy()))
+            .unwrap_or_default();
+        format!("{}@{}{}", item.name, self.manifest.id, extension)
+    }
+}
+
+fn packs_dir() -> PathBuf {
+    DEFAULT_PATH.read().join("emoji-packs")
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '+'
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(is_name_char)
+}
+
+// file names may not point outside the pack
+fn is_valid_file(file: &str) -> bool {
+    let path = Path::new(file);
+    path.components().count() == 1 && path.file_name().is_some()
+}
+
+impl PackManifest {
+    fn validate(&self) -> Result<(), anyhow::Error> {
+        if !is_valid_name(&self.id) {
+            bail!("invalid pack id: {}", self.id);
+        }
+        let mut names = HashSet::new();
+        for item in self.emojis.iter().chain(self.stickers.iter()) {
+            if !is_valid_name(&item.name) {
+                bail!("invalid emoji name: {}", item.name);
+            }
+            if !is_valid_file(&item.file) {
+                bail!("invalid file name: {}", item.file);
+            }
+            if !names.insert(&item.name) {
+                bail!("duplicate emoji name: {}", item.name);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// the installed packs, in the order they were installed
+pub fn installed() -> Vec<Pack> {
+    INSTALLED_PACKS.read().clone()
+}
+
+pub fn get(pack_id: &str) -> Option<Pack> {
+    get_in(&INSTALLED_PACKS.read(), pack_id).cloned()
+}
+
+fn get_in<'a>(packs: &'a [Pack], pack_id: &str) -> Option<&'a Pack> {
+    packs.iter().find(|pack| pack.manifest.id == pack_id)
+}
+
+pub fn is_installed(pack_id: &str) -> bool {
+    get(pack_id).is_some()
+}
+
+/// finds a custom emoji by the name typed in the composer. earlier packs take precedence.
+pub fn find_by_name(name: &str) -> Option<(Pack, PackItem)> {
+    find_in(&INSTALLED_PACKS.read(), name)
+}
+
+fn find_in(packs: &[Pack], name: &str) -> Option<(Pack, PackItem)> {
+    packs.iter().find_map(|pack| {
+        pack.find(name)
+            .map(|(item, _)| (pack.clone(), item.clone()))
+    })
+}
+
+fn load_installed() -> Vec<Pack> {
+    let entries = match fs::read_dir(packs_dir()) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+    let mut packs: Vec<(std::time::SystemTime, Pack)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry.path().is_dir() && !entry.file_name().to_string_lossy().starts_with('.')
+        })
+        .filter_map(|entry| {
+            let dir = entry.path();
+            match read_manifest(&dir) {
+                Ok(manifest) => {
+                    let installed_at = entry
+                        .metadata()
+                        .and_then(|meta| meta.modified())
+                        .unwrap_or(std::time::UNIX_EPOCH);
+                    Some((installed_at, Pack { manifest, dir }))
+                }
+                Err(e) => {
+                    log::error!("failed to load emoji pack {}: {}", dir.to_string_lossy(), e);
+                    None
+                }
+            }
+        })
+        .collect();
+    packs.sort_by_key(|(installed_at, _)| *installed_at);
+    packs.into_iter().map(|(_, pack)| pack).collect()
+}
+
+fn read_manifest(dir: &Path) -> Result<PackManifest, anyhow::Error> {
+    let contents = fs::read_to_string(dir.join(MANIFEST_NAME))?;
+    let manifest: PackManifest = serde_json::from_str(&contents)?;
+    manifest.validate()?;
+    Ok(manifest)
+}
+
+/// packs a directory containing a manifest into a zip, in the format stored in Constellation
+pub fn zip_directory(dir: &Path) -> Result<Vec<u8>, anyhow::Error> {
+    let manifest = read_manifest(dir)?;
+    let mut writer = zip::ZipWriter::new(Cursor::new(vec![]));
+    let options = zip::write::FileOptions::default();
+
+    writer.start_file(MANIFEST_NAME, options)?;
+    writer.write_all(&fs::read(dir.join(MANIFEST_NAME))?)?;
+    for item in manifest.emojis.iter().chain(manifest.stickers.iter()) {
+        let contents = fs::read(dir.join(&item.file))
+            .with_context(|| format!("failed to read {}", item.file))?;
+        writer.start_file(item.file.as_str(), options)?;
+        writer.write_all(&contents)?;
+    }
+    Ok(writer.finish()?.into_inner())
+}
+
+/// extracts a pack archive and makes it available. an installed pack with the same id is replaced.
+pub fn install_zip(bytes: &[u8]) -> Result<Pack, anyhow::Error> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))?;
+    let manifest: PackManifest = {
+        let mut file = archive
+            .by_name(MANIFEST_NAME)
+            .map_err(|_| anyhow!("the pack has no {}", MANIFEST_NAME))?;
+        let size = file.size();
+        let contents = read_limited(&mut file, size, MAX_MANIFEST_SIZE)
+            .with_context(|| format!("failed to read {}", MANIFEST_NAME))?;
+        serde_json::from_slice(&contents)?
+    };
+    manifest.validate()?;
+
+    // read before anything is written, so that a pack which is too large doesn't replace the installed one
+    let mut remaining = MAX_PACK_SIZE;
+    let mut images = vec![];
+    for item in manifest.emojis.iter().chain(manifest.stickers.iter()) {
+        let mut file = archive
+            .by_name(&item.file)
+            .map_err(|_| anyhow!("the pack is missing {}", item.file))?;
+        let size = file.size();
+        let contents = read_limited(&mut file, size, MAX_IMAGE_SIZE.min(remaining))
+            .with_context(|| format!("failed to read {}", item.file))?;
+        remaining -= contents.len() as u64;
+        images.push((&item.file, contents));
+    }
+
+    let dir = packs_dir().join(&manifest.id);
+    if dir.exists() {
+        fs::remove_dir_all(&dir)?;
+    }
+    fs::create_dir_all(&dir)?;
+    fs::write(
+        dir.join(MANIFEST_NAME),
+        serde_json::to_vec_pretty(&manifest)?,
+    )?;
+    for (file, contents) in images {
+        fs::write(dir.join(file), contents)?;
+    }
+
+    remove_attachments(&manifest.id);
+    let pack = Pack { manifest, dir };
+    let mut packs = INSTALLED_PACKS.write();
+    packs.retain(|p| p.manifest.id != pack.manifest.id);
+    packs.push(pack.clone());
+    Ok(pack)
+}
+
+// reads at most `limit` bytes. the size in the archive is checked first, but it is only what the archive claims
+fn read_limited(file: &mut impl Read, size: u64, limit: u64) -> Result<Vec<u8>, anyhow::Error> {
+    if size > limit {
+        bail!("the file is too large ({} bytes)", size);
+    }
+    let mut contents = vec![];
+    file.take(limit + 1).read_to_end(&mut contents)?;
+    if contents.len() as u64 > limit {
+        bail!("the file is larger than the archive says");
+    }
+    Ok(contents)
+}
+
+pub fn remove(pack_id: &str) -> Result<(), anyhow::Error> {
+    INSTALLED_PACKS
+        .write()
+        .retain(|pack| pack.manifest.id != pack_id);
+    remove_attachments(pack_id);
+    let dir = packs_dir().join(pack_id);
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+    }
+    Ok(())
+}
+
+// the copies made by `attachment_path` and the cached images are stale once a pack changes
+fn remove_attachments(pack_id: &str) {
+    DATA_URIS.write().clear();
+    let entries = match fs::read_dir(packs_dir().join(".attachments")) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let file_name = entry.file_name();
+        let matches = parse_attachment_name(&file_name.to_string_lossy())
+            .map(|(_, id)| id == pack_id)
+            .unwrap_or(false);
+        if matches {
+            if let Err(e) = fs::remove_file(entry.path()) {
+                log::error!("failed to remove {}: {}", entry.path().to_string_lossy(), e);
+            }
+        }
+    }
+}
+
+/// the name of the archive in Constellation
+pub fn storage_name(pack_id: &str) -> String {
+    format!("{}{}.zip", STORAGE_PREFIX, pack_id)
+}
+
+/// the pack id of an archive stored in Constellation
+pub fn pack_id_from_storage_name(name: &str) -> Option<&str> {
+    name.strip_prefix(STORAGE_PREFIX)?.strip_suffix(".zip")
+}
+
+/// the image as a data URI, so that it can be displayed without a file server
+pub fn data_uri(pack: &Pack, item: &PackItem) -> Option<String> {
+    let path = pack.path(item);
+    if let Some(uri) = DATA_URIS.read().get(&path) {
+        return Some(uri.clone());
+    }
+    let bytes = fs::read(&path).ok()?;
+    let mime = match path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .as_deref()
+    {
+        Some("gif") => "image/gif",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("webp") => "image/webp",
+        Some("svg") => "image/svg+xml",
+        _ => "image/png",
+    };
+    let uri = format!("data:{};base64,{}", mime, base64::encode(bytes));
+    DATA_URIS.write().insert(path, uri.clone());
+    Some(uri)
+}
+
+// attachments keep their file name, so the image is copied to a file named after the reference
+fn attachment_path(pack: &Pack, item: &PackItem) -> Result<PathBuf, anyhow::Error> {
+    let dir = packs_dir().join(".attachments");
+    let path = dir.join(pack.attachment_name(item));
+    if !path.exists() {
+        fs::create_dir_all(&dir)?;
+        fs::copy(pack.path(item), &path)?;
+    }
+    Ok(path)
+}
+
+/// the reference a fallback attachment stands in for, as (name, pack id)
+pub fn parse_attachment_name(file_name: &str) -> Option<(&str, &str)> {
+    let stem = Path::new(file_name).file_stem()?.to_str()?;
+    let (name, pack_id) = stem.split_once('@')?;
+    (is_valid_name(name) && is_valid_name(pack_id)).then(|| (name, pack_id))
+}
+
+/// whether the attachment is the fallback image of a custom emoji used in the message, rather than a file which
+/// was shared. a file which only happens to be named `name@pack.ext` is a shared file.
+pub fn is_fallback_attachment(file_name: &str, text: &str) -> bool {
+    parse_attachment_name(file_name)
+        .map(|(name, pack_id)| text.contains(&format!(":{}@{}:", name, pack_id)))
+        .unwrap_or(false)
+}
+
+/// replaces every `:name:` which refers to a custom emoji with `:name@pack_id:`. standard shortcodes are left alone.
+/// returns the images which should be attached for recipients who don't have the pack.
+pub fn encode_references(text: &str) -> (String, Vec<PathBuf>) {
+    encode_in(&INSTALLED_PACKS.read(), text, attachment_path)
+}
+
+// the packs and the way attachments are made are passed in, so that tests don't depend on the installed packs
+fn encode_in<F>(packs: &[Pack], text: &str, mut attach: F) -> (String, Vec<PathBuf>)
+where
+    F: FnMut(&Pack, &PackItem) -> Result<PathBuf, anyhow::Error>,
+{
+    let mut attachments: Vec<PathBuf> = vec![];
+    let output = map_references(text, |name, pack_id| {
+        if pack_id.is_some() || emojis::get_by_shortcode(name).is_some() {
+            return None;
+        }
+        let (pack, item) = find_in(packs, name)?;
+        match attach(&pack, &item) {
+            Ok(path) => {
+                if !attachments.contains(&path) {
+                    attachments.push(path);
+                }
+            }
+            Err(e) => log::error!("failed to attach custom emoji {}: {}", item.name, e),
+        }
+        Some(format!(":{}@{}:", item.name, pack.manifest.id))
+    });
+    (output, attachments)
+}
+
+/// the custom emojis referenced in `text` which can be displayed, as (pack, item)
+pub fn resolve_references(text: &str) -> Vec<(Pack, PackItem)> {
+    let mut found = vec![];
+    map_references(text, |name, pack_id| {
+        let pack = get(pack_id?)?;
+        let item = pack.find(name)?.0.clone();
+        found.push((pack, item));
+        None
+    });
+    found
+}
+
+/// replaces every `:name@pack_id:` with an image, if the pack is installed
+pub fn render_references(text: &str) -> String {
+    render_in(&INSTALLED_PACKS.read(), text)
+}
+
+fn render_in(packs: &[Pack], text: &str) -> String {
+    map_references(text, |name, pack_id| {
+        let pack = get_in(packs, pack_id?)?;
+        let (item, is_sticker) = pack.find(name)?;
+        let src = data_uri(pack, item)?;
+        let class = if is_sticker {
+            "custom-emoji sticker"
+        } else {
+            "custom-emoji"
+        };
+        Some(format!(
+            "<img class=\"{}\" src=\"{}\" alt=\":{}:\" title=\":{}:\"/>",
+            class, src, item.name, item.name
+        ))
+    })
+}
+
+/// removes every reference to an installed pack. used to check whether a message only contains emojis.
+pub fn strip_references(text: &str) -> String {
+    map_references(text, |name, pack_id| {
+        get(pack_id?)?.find(name)?;
+        Some(String::new())
+    })
+}
+
+// calls `f` with the name and optional pack id of each `:name:` or `:name@pack_id:` in `text`.
+// if `f` returns a string, the reference is replaced with it.
+fn map_references<F>(text: &str, mut f: F) -> String
+where
+    F: FnMut(&str, Option<&str>) -> Option<String>,
+{
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(':') {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let end = after
+            .find(|c: char| !(is_name_char(c) || c == '@'))
+            .unwrap_or(after.len());
+        let candidate = &after[..end];
+        if end > 0 && after[end..].starts_with(':') {
+            let (name, pack_id) = match candidate.split_once('@') {
+                Some((name, pack_id)) => (name, Some(pack_id)),
+                None => (candidate, None),
+            };
+            if let Some(replacement) = f(name, pack_id) {
+                output.push_str(&replacement);
+                rest = &after[end + 1..];
+                continue;
+            }
+        }
+        output.push(':');
+        rest = after;
+    }
+    output.push_str(rest);
+    output
+}
+
+// where the pack archives are stored in Constellation, out of the way of the user's files
+fn storage_directory() -> PathBuf {
+    Path::new(APP_DIRECTORY).join("emoji-packs")
+}
+
+/// stores the pack archive in Constellation so that it roams with the account
+pub async fn upload(
+    storage: &mut Storage,
+    pack_id: &str,
+    bytes: &[u8],
+) -> Result<(), anyhow::Error> {
+    let name = storage_name(pack_id);
+    let bytes = bytes.to_vec();
+    let result = in_directory(storage, &storage_directory(), |mut storage| async move {
+        if storage.current_directory()?.has_item(&name) {
+            storage.remove(&name, true).await?;
+        }
+        storage.put_buffer(&name, &bytes).await
+    })
+    .await
+    .map_err(|e| anyhow!(e))?;
+    Ok(result?)
+}
+
+/// installs the packs which were imported on another device. returns the number of packs installed.
+pub async fn sync(storage: &mut Storage) -> usize {
+    let pack_ids: Vec<String> = storage
+        .root_directory()
+        .get_item_by_path(&storage_directory().to_string_lossy())
+        .and_then(|item| item.get_directory())
+        .map(|dir| dir.get_items())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|item| pack_id_from_storage_name(&item.name()).map(str::to_string))
+        .filter(|pack_id| !is_installed(pack_id))
+        .collect();
+    if pack_ids.is_empty() {
+        return 0;
+    }
+
+    let result = in_directory(storage, &storage_directory(), |storage| async move {
+        let mut installed = 0;
+        for pack_id in pack_ids {
+            let result = match storage.get_buffer(&storage_name(&pack_id)).await {
+                Ok(bytes) => install_zip(&bytes),
+                Err(e) => Err(e.into()),
+            };
+            match result {
+                Ok(_) => installed += 1,
+                Err(e) => log::error!("failed to sync emoji pack {}: {}", pack_id, e),
+            }
+        }
+        installed
+    })
+    .await;
+    result.unwrap_or_else(|e| {
+        log::error!("failed to open the emoji pack directory: {}", e);
+        0
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(name: &str, file: &str) -> PackItem {
+        PackItem {
+            name: name.to_string(),
+            file: file.to_string(),
+        }
+    }
+
+    fn pack(dir: PathBuf) -> Pack {
+        Pack {
+            manifest: PackManifest {
+                id: String::from("cats"),
+                name: String::from("Cats"),
+                emojis: vec![item("wave", "wave.png"), item("smile", "smile.png")],
+                stickers: vec![item("party", "party.gif")],
+            },
+            dir,
+        }
+    }
+
+    #[test]
+    fn attachment_names_are_parsed() {
+        assert_eq!(
+            parse_attachment_name("wave@cats.png"),
+            Some(("wave", "cats"))
+        );
+        assert_eq!(parse_attachment_name("wave@cats"), Some(("wave", "cats")));
+        assert_eq!(parse_attachment_name("wave.png"), None);
+        assert_eq!(parse_attachment_name("@cats.png"), None);
+        assert_eq!(parse_attachment_name("wave@.png"), None);
+        assert_eq!(parse_attachment_name("my wave@cats.png"), None);
+    }
+
+    #[test]
+    fn custom_emojis_are_encoded_with_their_pack() {
+        let packs = vec![pack(PathBuf::new())];
+        let mut attached = vec![];
+        let (text, attachments) = encode_in(
+            &packs,
+            ":wave: at 12:30 :wave: :smile: :wave@dogs: :nope:",
+            |pack, item| {
+                attached.push(item.name.clone());
+                Ok(PathBuf::from(pack.attachment_name(item)))
+            },
+        );
+        // standard shortcodes win over packs, and references which already have a pack are kept
+        assert_eq!(
+            text,
+            ":wave@cats: at 12:30 :wave@cats: :smile: :wave@dogs: :nope:"
+        );
+        assert_eq!(attachments, vec![PathBuf::from("wave@cats.png")]);
+        assert_eq!(attached, vec!["wave", "wave"]);
+    }
+
+    #[test]
+    fn references_to_installed_packs_are_rendered() {
+        let dir = std::env::temp_dir().join(format!("uplink-pack-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("wave.png"), [1, 2, 3]).unwrap();
+        fs::write(dir.join("party.gif"), [4, 5, 6]).unwrap();
+        let packs = vec![pack(dir.clone())];
+
+        let html = render_in(&packs, "hi :wave@cats: :party@cats: :wave@dogs: :wave:");
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            html,
+            "hi <img class=\"custom-emoji\" src=\"data:image/png;base64,AQID\" alt=\":wave:\" title=\":wave:\"/> \
+             <img class=\"custom-emoji sticker\" src=\"data:image/gif;base64,BAUG\" alt=\":party:\" title=\":party:\"/> \
+             :wave@dogs: :wave:"
+        );
+    }
+
+    #[test]
+    fn reading_stops_at_the_limit() {
+        let bytes = [0_u8; 10];
+        assert_eq!(read_limited(&mut &bytes[..], 10, 10).unwrap().len(), 10);
+        // the archive claims too much
+        assert!(read_limited(&mut &bytes[..], 11, 10).is_err());
+        // the archive claims too little
+        assert!(read_limited(&mut &bytes[..], 5, 9).is_err());
+    }
+
+    #[test]
+    fn fallback_attachments_are_referenced_by_the_message() {
+        assert!(is_fallback_attachment("wave@cats.png", "hi :wave@cats:"));
+        assert!(!is_fallback_attachment("wave@cats.png", "hi"));
+        assert!(!is_fallback_attachment("wave@cats.png", "hi :wave@dogs:"));
+        assert!(!is_fallback_attachment("photo.png", "hi :wave@cats:"));
+    }
+}
@@ -0,0 +1,260 @@
+//! the shortcode table shared by the emoji selector, the composer's autocomplete and message rendering.
+//! shortcodes follow GitHub's gemoji names, e.g. `:tada:` or `:thumbsup:`.
+
+use emojis::{Emoji, UnicodeVersion};
+use state::emoji::SkinTone;
+
+/// newer emojis may not be supported by the system font
+pub static MAX_UNICODE_VER: UnicodeVersion = UnicodeVersion::new(11, 0);
+
+// the longest emoji sequence is well below this many bytes
+const MAX_EMOJI_LEN: usize = 40;
+
+/// returns the variant of `emoji` with the given skin tone, or `emoji` itself if it doesn't support skin tones
+pub fn with_skin_tone(emoji: &'static Emoji, skin_tone: SkinTone) -> &'static Emoji {
+    let tone = match skin_tone {
+        SkinTone::Default => return emoji,
+        SkinTone::Light => emojis::SkinTone::Light,
+        SkinTone::MediumLight => emojis::SkinTone::MediumLight,
+        SkinTone::Medium => emojis::SkinTone::Medium,
+        SkinTone::MediumDark => emojis::SkinTone::MediumDark,
+        SkinTone::Dark => emojis::SkinTone::Dark,
+    };
+    emoji.with_skin_tone(tone).unwrap_or(emoji)
+}
+
+/// every supported emoji, skin tone variants excluded
+pub fn base_emojis() -> impl Iterator<Item = &'static Emoji> {
+    emojis::iter()
+        .filter(|emoji| emoji.unicode_version() <= MAX_UNICODE_VER)
+        .filter(|emoji| {
+            emoji
+                .skin_tone()
+                .map_or(true, |tone| tone == emojis::SkinTone::Default)
+        })
+}
+
+/// every supported emoji which has a shortcode
+pub fn all() -> impl Iterator<Item = (&'static str, &'static Emoji)> {
+    base_emojis().filter_map(|emoji| emoji.shortcode().map(|shortcode| (shortcode, emoji)))
+}
+
+/// emojis whose name or shortcode contains every word of `query`
+pub fn search(query: &str, skin_tone: SkinTone) -> Vec<&'static Emoji> {
+    let query = query.to_lowercase();
+    let words: Vec<&str> = query.split_whitespace().collect();
+    base_emojis()
+        .filter(|emoji| {
+            let name = emoji.name().to_lowercase();
+            let shortcode = emoji.shortcode().unwrap_or_default();
+            words
+                .iter()
+                .all(|word| name.contains(word) || shortcode.contains(word))
+        })
+        .map(|emoji| with_skin_tone(emoji, skin_tone))
+        .collect()
+}
+
+/// emojis whose shortcode contains `query`. shortcodes starting with `query` are listed first.
+pub fn suggest(query: &str, skin_tone: SkinTone, limit: usize) -> Vec<(String, String)> {
+    let query = query.to_lowercase();
+    let (mut prefixed, contained): (Vec<_>, Vec<_>) = all()
+        .filter(|(shortcode, _)| shortcode.contains(&query))
+        .partition(|(shortcode, _)| shortcode.starts_with(&query));
+    prefixed.extend(contained);
+    prefixed
+        .into_iter()
+        .take(limit)
+        .map(|(shortcode, emoji)| {
+            (
+                shortcode.to_string(),
+                with_skin_tone(emoji, skin_tone).as_str().to_string(),
+            )
+        })
+        .collect()
+}
+
+/// the shortcode being typed at the end of `text`, without the leading colon. the user has to type at least two
+/// characters so that a colon in a sentence doesn't open the autocomplete.
+pub fn trailing_query(text: &str) -> Option<&str> {
+    let start = text.rfind(':')?;
+    if start > 0 && !text[..start].ends_with(char::is_whitespace) {
+        return None;
+    }
+    let query = &text[start + 1..];
+    if query.chars().count() >= 2 && query.chars().all(is_shortcode_char) {
+        Some(query)
+    } else {
+        None
+    }
+}
+
+fn is_shortcode_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '+'
+}
+
+/// replaces every known `:shortcode:` in `text` with its emoji. unknown shortcodes, and those inside code spans or
+/// code blocks, are left as they are.
+pub fn replace_shortcodes(text: &str, skin_tone: SkinTone) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('`') {
+        output.push_str(&replace_in_prose(&rest[..start], skin_tone));
+        let ticks = rest[start..].len() - rest[start..].trim_start_matches('`').len();
+        let after = &rest[start + ticks..];
+        // a code span is closed by a run of as many backticks. unclosed runs are plain text
+        match find_backtick_run(after, ticks) {
+            Some(end) => {
+                output.push_str(&rest[start..start + ticks + end + ticks]);
+                rest = &after[end + ticks..];
+            }
+            None => {
+                output.push_str(&rest[start..start + ticks]);
+                rest = after;
+            }
+        }
+    }
+    output.push_str(&replace_in_prose(rest, skin_tone));
+    output
+}
+
+// the start of the first run of exactly `len` backticks in `text`
+fn find_backtick_run(text: &str, len: usize) -> Option<usize> {
+    let mut offset = 0;
+    while let Some(start) = text[offset..].find('`') {
+        let start = offset + start;
+        let run = text[start..].len() - text[start..].trim_start_matches('`').len();
+        if run == len {
+            return Some(start);
+        }
+        offset = start + run;
+    }
+    None
+}
+
+fn replace_in_prose(text: &str, skin_tone: SkinTone) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(':') {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let end = after.find(|c: char| !is_shortcode_char(c));
+        match end {
+            Some(end) if end > 0 && after[end..].starts_with(':') => {
+                match emojis::get_by_shortcode(&after[..end]) {
+                    Some(emoji) => {
+                        output.push_str(with_skin_tone(emoji, skin_tone).as_str());
+                        rest = &after[end + 1..];
+                    }
+                    None => {
+                        // the closing colon may start the next shortcode
+                        output.push(':');
+                        output.push_str(&after[..end]);
+                        rest = &after[end..];
+                    }
+                }
+            }
+            _ => {
+                output.push(':');
+                rest = after;
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+/// true if `text` contains nothing but emojis and whitespace
+pub fn is_emoji_only(text: &str) -> bool {
+    let mut rest = text.trim_start();
+    if rest.is_empty() {
+        return false;
+    }
+    while !rest.is_empty() {
+        // match the longest emoji at the start of the text, so that sequences joined by ZWJ are found as a whole
+        let longest = rest
+            .char_indices()
+            .map(|(idx, c)| idx + c.len_utf8())
+            .take_while(|end| *end <= MAX_EMOJI_LEN)
+            .filter(|end| emojis::get(&rest[..*end]).is_some())
+            .last();
+        match longest {
+            Some(end) => rest = rest[end..].trim_start(),
+            None => return false,
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trailing_query_needs_two_characters_after_a_space() {
+        assert_eq!(trailing_query(":ta"), Some("ta"));
+        assert_eq!(trailing_query("party :tada"), Some("tada"));
+        assert_eq!(trailing_query("party :t"), None);
+        assert_eq!(trailing_query("time 10:30"), None);
+        assert_eq!(trailing_query("party :tada:"), None);
+        assert_eq!(trailing_query("party :ta da"), None);
+        assert_eq!(trailing_query("no colon"), None);
+    }
+
+    #[test]
+    fn known_shortcodes_are_replaced() {
+        assert_eq!(
+            replace_shortcodes("yay :tada:", SkinTone::Default),
+            "yay 🎉"
+        );
+        assert_eq!(
+            replace_shortcodes(":tada::tada:", SkinTone::Default),
+            "🎉🎉"
+        );
+        assert_eq!(
+            replace_shortcodes(":nope: :tada:", SkinTone::Default),
+            ":nope: 🎉"
+        );
+        assert_eq!(
+            replace_shortcodes("at 10:30:tada:", SkinTone::Default),
+            "at 10:30🎉"
+        );
+    }
+
+    #[test]
+    fn shortcodes_get_the_skin_tone() {
+        assert_eq!(replace_shortcodes(":wave:", SkinTone::Default), "👋");
+        assert_eq!(replace_shortcodes(":wave:", SkinTone::Dark), "👋🏿");
+        // emojis without skin tones are unchanged
+        assert_eq!(replace_shortcodes(":tada:", SkinTone::Dark), "🎉");
+    }
+
+    #[test]
+    fn code_is_left_alone() {
+        assert_eq!(
+            replace_shortcodes("`:tada:` :tada:", SkinTone::Default),
+            "`:tada:` 🎉"
+        );
+        assert_eq!(
+            replace_shortcodes("``a ` :tada:`` :tada:", SkinTone::Default),
+            "``a ` :tada:`` 🎉"
+        );
+        assert_eq!(
+            replace_shortcodes("```\n:tada:\n```\n:tada:", SkinTone::Default),
+            "```\n:tada:\n```\n🎉"
+        );
+        // an unclosed backtick doesn't start a code span
+        assert_eq!(replace_shortcodes("` :tada:", SkinTone::Default), "` 🎉");
+    }
+
+    #[test]
+    fn emoji_only_text() {
+        assert!(is_emoji_only("🎉"));
+        assert!(is_emoji_only(" 🎉 👋🏿 "));
+        // joined by ZWJ
+        assert!(is_emoji_only("👩‍💻"));
+        assert!(!is_emoji_only("🎉 yay"));
+        assert!(!is_emoji_only(""));
+        assert!(!is_emoji_only("   "));
+    }
+}
@@ -1,6 +1,9 @@
 use dioxus::prelude::*;
 use dioxus_heroicons::outline::Shape;
-use state::STATE;
+use state::{
+    palette::{PaletteCommand, PaletteCommands},
+    Actions, STATE,
+};
 use ui_kit::button::{self, Button};
 use utils::extensions::{BasicExtension, ExtensionInfo, ExtensionType};
 
@@ -23,7 +26,7 @@ impl BasicExtension for ExtIncognitoTyping {
         let send_typing = state.read().send_typing;
 
         let handle_click = move |_| {
-            state.write().send_typing = !send_typing;
+            state.write().dispatch(Actions::SetSendTyping(!send_typing));
         };
 
         cx.render(rsx! {
@@ -41,3 +44,13 @@ impl BasicExtension for ExtIncognitoTyping {
         })
     }
 }
+
+impl PaletteCommands for ExtIncognitoTyping {
+    fn commands() -> Vec<PaletteCommand> {
+        vec![PaletteCommand {
+            name: String::from("Toggle Incognito Typing"),
+            description: String::from("Show or hide your typing indicator"),
+            run: |state| Actions::SetSendTyping(!state.send_typing),
+        }]
+    }
+}
@@ -0,0 +1,326 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use dioxus::{events::FormEvent, prelude::*};
+use dioxus_heroicons::{outline::Shape, Icon};
+use dioxus_toast::{Position, ToastInfo};
+use futures::StreamExt;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use ui_kit::{button::Button, input::Input, profile_picture::PFP};
+use uuid::Uuid;
+use warp::{constellation::Progression, raygun::Message};
+
+use crate::{iutils, state::ConversationInfo, Account, Messaging, STATE, TOAST_MANAGER};
+
+#[derive(Props)]
+pub struct Props<'a> {
+    account: Account,
+    messaging: Messaging,
+    // the messages to forward, in the order they should be sent
+    messages: Vec<Message>,
+    on_dismiss: EventHandler<'a, ()>,
+}
+
+#[allow(non_snake_case)]
+pub fn ForwardPicker<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
+    log::debug!("rendering compose/ForwardPicker");
+    let state = use_atom_ref(&cx, STATE);
+    let search_value = use_state(&cx, String::new);
+    let selected: &UseState<HashSet<Uuid>> = use_state(&cx, HashSet::new);
+    let toast = use_atom_ref(&cx, TOAST_MANAGER);
+    let mp = cx.props.account.clone();
+
+    // most recent conversations first
+    let mut chats: Vec<ConversationInfo> = state.read().all_chats.values().cloned().collect();
+    chats.sort();
+    chats.reverse();
+
+    let matcher = SkimMatcherV2::default();
+    let search = search_value.get().to_lowercase();
+    let filtered_chats: Vec<(ConversationInfo, String)> = chats
+        .into_iter()
+        .map(|info| {
+            let (_, username) = iutils::get_username_from_conversation(&info, &mp);
+            (info, username)
+        })
+        .filter(|(_, username)| {
+            search.is_empty() || matcher.fuzzy_match(username, &search).is_some()
+        })
+        .collect();
+
+    let num_selected = selected.len();
+    let num_messages = cx.props.messages.len();
+    let title = match num_messages {
+        1 => String::from("Forward message"),
+        _ => format!("Forward {} messages", num_messages),
+    };
+
+    cx.render(rsx! {
+        div {
+            id: "forward-picker",
+            onclick: move |_| cx.props.on_dismiss.call(()),
+            div {
+                class: "forward-picker-body",
+                onclick: move |e| {
+                    e.cancel_bubble();
+                },
+                div {
+                    class: "forward-picker-header",
+                    h3 { "{title}" },
+                    Button {
+                        icon: Shape::XMark,
+                        state: ui_kit::button::State::Secondary,
+                        on_pressed: move |_| cx.props.on_dismiss.call(()),
+                    },
+                },
+                Input {
+                    icon: Shape::MagnifyingGlass,
+                    placeholder: String::from("Search conversations"),
+                    value: search_value.to_string(),
+                    on_change: move |e: FormEvent| {
+                        search_value.set(e.value.clone());
+                    },
+                    on_enter: move |_| {},
+                },
+                div {
+                    class: "forward-picker-chats",
+                    filtered_chats.iter().map(|(info, username)| {
+                        let id = info.conversation.id();
+                        let is_selected = selected.contains(&id);
+                        let selected_class = if is_selected { "selected" } else { "" };
+                        let (display_did, _) = iutils::get_username_from_conversation(info, &mp);
+                        let profile_picture = iutils::get_pfp_from_did(display_did, &mp);
+                        rsx!(
+                            div {
+                                key: "{id}",
+                                class: "forward-picker-chat {selected_class}",
+                                onclick: move |_| {
+                                    selected.with_mut(|selected| {
+                                        if !selected.remove(&id) {
+                                            selected.insert(id);
+                                        }
+                                    });
+                                },
+                                PFP {
+                                    src: profile_picture,
+                                    size: ui_kit::profile_picture::Size::Small
+                                },
+                                p {
+                                    class: "ellipsis",
+                                    "{username}"
+                                },
+                                is_selected.then(|| rsx!(
+                                    Icon {
+                                        icon: Shape::CheckCircle,
+                                    }
+                                ))
+                            }
+                        )
+                    })
+                },
+                Button {
+                    icon: Shape::ArrowUturnRight,
+                    text: format!("Forward to {}", num_selected),
+                    disabled: num_selected == 0,
+                    on_pressed: move |_| {
+                        let targets: Vec<Uuid> = selected.iter().cloned().collect();
+                        let messages = cx.props.messages.clone();
+                        let rg = cx.props.messaging.clone();
+                        let mp = cx.props.account.clone();
+                        let toast = toast.clone();
+                        cx.spawn(async move {
+                            for message in messages {
+                                let sender_name = iutils::get_username_from_did(message.sender(), &mp);
+                                if let Err(e) = forward_message(rg.clone(), message, sender_name, &targets).await {
+                                    let single_toast = ToastInfo {
+                                        position: Position::TopRight,
+                                        ..ToastInfo::simple(&e)
+                                    };
+                                    let _id = toast.write().popup(single_toast);
+                                }
+                            }
+                        });
+                        cx.props.on_dismiss.call(());
+                    },
+                },
+            }
+        }
+    })
+}
+
+/// re-sends the text of `message` to every conversation in `targets`, prefixed with a header naming
+/// the original sender. attachments are downloaded to a temporary directory, attached again and deleted.
+/// nothing is sent if an attachment can't be downloaded.
+pub async fn forward_message(
+    mut rg: Messaging,
+    message: Message,
+    sender_name: String,
+    targets: &[Uuid],
+) -> Result<(), String> {
+    let mut text = vec![format!("Forwarded from {}", sender_name)];
+    text.extend(message.value());
+
+    // a directory for each forward, so that forwarding the same message twice at once doesn't mix the copies
+    let dir = std::env::temp_dir()
+        .join("uplink-forward")
+        .join(Uuid::new_v4().to_string());
+    let result = match download_attachments(&rg, &message, &dir).await {
+        Ok(attachments) => {
+            let mut failed = vec![];
+            for target in targets {
+                let result = if attachments.is_empty() {
+                    rg.send(*target, None, text.clone()).await
+                } else {
+                    rg.attach(*target, attachments.clone(), text.clone()).await
+                };
+                if let Err(e) = result {
+                    log::error!("failed to forward message to {}: {}", target, e);
+                    failed.push(e.to_string());
+                }
+            }
+            match failed.first() {
+                Some(e) => Err(format!("Couldn't forward a message: {}", e)),
+                None => Ok(()),
+            }
+        }
+        Err(e) => Err(e),
+    };
+
+    // the copies are no longer needed once they were sent, or failed to send
+    match tokio::fs::remove_dir_all(&dir).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            log::error!("failed to delete forwarded attachments: {}", e)
+        }
+        _ => {}
+    }
+    result
+}
+
+// the name an attachment is saved under. the sender chooses the name, so only its last component is kept, and
+// names which are already `taken` get a number
+fn local_name(name: &str, taken: &mut HashSet<String>) -> Option<String> {
+    let path = Path::new(name);
+    let file_name = path.file_name()?.to_str()?;
+    let mut local = file_name.to_string();
+    let mut n = 1;
+    while taken.contains(&local) {
+        let stem = Path::new(file_name)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or(file_name);
+        local = match Path::new(file_name)
+            .extension()
+            .and_then(|ext| ext.to_str())
+        {
+            Some(ext) => format!("{} ({}).{}", stem, n, ext),
+            None => format!("{} ({})", stem, n),
+        };
+        n += 1;
+    }
+    taken.insert(local.clone());
+    Some(local)
+}
+
+async fn download_attachments(
+    rg: &Messaging,
+    message: &Message,
+    dir: &Path,
+) -> Result<Vec<PathBuf>, String> {
+    let attachments = message.attachments();
+    if attachments.is_empty() {
+        return Ok(vec![]);
+    }
+
+    if let Err(e) = tokio::fs::create_dir_all(dir).await {
+        log::error!(
+            "failed to create directory for forwarded attachments: {}",
+            e
+        );
+        return Err(String::from("Couldn't forward the attachments"));
+    }
+
+    let mut paths = vec![];
+    let mut taken = HashSet::new();
+    let mut failed = vec![];
+    for file in attachments {
+        let path = match local_name(&file.name(), &mut taken) {
+            Some(name) => dir.join(name),
+            None => {
+                log::error!("refusing to download attachment named {}", file.name());
+                failed.push(file.name());
+                continue;
+            }
+        };
+        match rg
+            .download(
+                message.conversation_id(),
+                message.id(),
+                file.name(),
+                path.clone(),
+            )
+            .await
+        {
+            Ok(mut stream) => {
+                let mut downloaded = true;
+                while let Some(progress) = stream.next().await {
+                    if let Progression::ProgressFailed { name, error, .. } = progress {
+                        log::error!("{name} failed to download with error: {error:?}");
+                        downloaded = false;
+                    }
+                }
+                if downloaded {
+                    paths.push(path);
+                } else {
+                    failed.push(file.name());
+                }
+            }
+            Err(e) => {
+                log::error!("failed to download attachment {}: {}", file.name(), e);
+                failed.push(file.name());
+            }
+        }
+    }
+    if failed.is_empty() {
+        Ok(paths)
+    } else {
+        Err(format!(
+            "Couldn't download {} to forward it",
+            failed.join(", ")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attachments_stay_in_the_directory() {
+        let mut taken = HashSet::new();
+        assert_eq!(
+            local_name("../../.bashrc", &mut taken),
+            Some(String::from(".bashrc"))
+        );
+        assert_eq!(
+            local_name("/etc/passwd", &mut taken),
+            Some(String::from("passwd"))
+        );
+        assert_eq!(local_name("..", &mut taken), None);
+        assert_eq!(local_name("/", &mut taken), None);
+        assert_eq!(local_name("", &mut taken), None);
+    }
+
+    #[test]
+    fn duplicate_names_are_numbered() {
+        let mut taken = HashSet::new();
+        let mut name = |name: &str| local_name(name, &mut taken).unwrap();
+        assert_eq!(name("photo.png"), "photo.png");
+        assert_eq!(name("photo.png"), "photo (1).png");
+        assert_eq!(name("a/photo.png"), "photo (2).png");
+        assert_eq!(name("notes"), "notes");
+        assert_eq!(name("notes"), "notes (1)");
+    }
+}
@@ -1,12 +1,17 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     time::{Duration, Instant},
 };
 
 use crate::{
-    components::main::compose::{divider::Divider, msg::Msg, reply::Reply},
+    components::main::compose::{
+        divider::Divider,
+        msg::Msg,
+        reply::Reply,
+        selection_bar::{update_selection, SelectionBar},
+    },
     iutils,
-    state::{Actions, LastMsgSent},
+    state::{retention::ControlMessage, Actions, LastMsgSent},
     Account, Messaging, STATE,
 };
 use dioxus::prelude::*;
@@ -61,8 +66,7 @@ pub fn Messages(cx: Scope<Props>) -> Element {
     let my_did = ident.did_key();
     // this one has a special name because of the other variable names within the use_future
     let list: UseRef<Vec<Message>> = use_ref(&cx, Vec::new).clone();
-    // this one is for the rsx! macro. it is reversed for display purposes and defined here because `list` gets moved into the use_future
-    let messages: Vec<Message> = list.read().iter().cloned().collect();
+    let list2 = list.clone();
 
     // this is used for reading the event stream.
     let current_chat = state
@@ -70,6 +74,46 @@ pub fn Messages(cx: Scope<Props>) -> Element {
         .selected_chat
         .and_then(|x| state.read().active_chats.get(&x).cloned());
 
+    let retention = current_chat
+        .as_ref()
+        .map(|info| info.retention)
+        .unwrap_or_default();
+
+    // this one is for the rsx! macro. it is reversed for display purposes and defined here because `list` gets moved into the use_future
+    // control messages and messages which have expired are hidden. expired messages are deleted by the sweeper in `Main`
+    let messages: Vec<Message> = list
+        .read()
+        .iter()
+        .filter(|message| !ControlMessage::is_control_message(&message.value()))
+        .filter(|message| !retention.is_expired(message.date()))
+        .cloned()
+        .collect();
+
+    // multi-select mode. `displayed_ids` is used to resolve shift-click ranges
+    let selecting = use_state(&cx, || false);
+    let selected: &UseRef<HashSet<Uuid>> = use_ref(&cx, HashSet::new);
+    let last_clicked: &UseRef<Option<usize>> = use_ref(&cx, || None);
+    let displayed_ids: &UseRef<Vec<Uuid>> = use_ref(&cx, Vec::new);
+    let selection_chat: &UseRef<Option<Uuid>> = use_ref(&cx, || None);
+    *displayed_ids.write_silent() = messages.iter().map(|message| message.id()).collect();
+
+    // the selection doesn't carry over to another conversation
+    let selected_chat = state.read().selected_chat;
+    if *selection_chat.read() != selected_chat {
+        *selection_chat.write_silent() = selected_chat;
+        selected.write_silent().clear();
+        *last_clicked.write_silent() = None;
+        if **selecting {
+            selecting.set(false);
+        }
+    }
+
+    let selected_messages: Vec<Message> = messages
+        .iter()
+        .filter(|message| selected.read().contains(&message.id()))
+        .cloned()
+        .collect();
+
     let first_unread_message_id = current_chat
         .clone()
         .unwrap_or_default()
@@ -234,6 +278,21 @@ pub fn Messages(cx: Scope<Props>) -> Element {
                 .await
                 .unwrap_or_default();
 
+            // the most recent control message holds the current settings. this catches settings which were
+            // changed while Uplink wasn't running
+            if let Some(ControlMessage::SetRetention(retention)) = messages
+                .iter()
+                .rev()
+                .find_map(|message| ControlMessage::decode(&message.value()))
+            {
+                if retention != current_chat.retention {
+                    state.write().dispatch(Actions::SetRetention(
+                        current_chat.conversation.id(),
+                        retention,
+                    ));
+                }
+            }
+
             //This is to prevent the future updating the state and causing a rerender
             if *list.read() != messages {
                 log::debug!("updating messages list ");
@@ -254,6 +313,15 @@ pub fn Messages(cx: Scope<Props>) -> Element {
                             match rg.get_message(conversation_id, message_id).await {
                                 Ok(message) => {
                                     log::debug!("compose/messages streamed a new message ");
+                                    if let Some(ControlMessage::SetRetention(retention)) =
+                                        ControlMessage::decode(&message.value())
+                                    {
+                                        state.write().dispatch(Actions::SetRetention(
+                                            conversation_id,
+                                            retention,
+                                        ));
+                                        continue;
+                                    }
                                     // remove typing indicator
                                     let username =
                                         iutils::get_username_from_did(message.sender(), &mp);
@@ -363,6 +431,7 @@ pub fn Messages(cx: Scope<Props>) -> Element {
                     let conversation_id = message.conversation_id();
                     let msg_sender = message.sender();
                     let is_remote = ident.did_key() != msg_sender;
+                    let is_selected = selected.read().contains(&message_id);
                     let mut rg = rg.clone();
                     let sender_picture = profile_pictures.get(&msg_sender).and_then(|pbp| pbp.clone()).unwrap_or_default();
 
@@ -429,13 +498,50 @@ pub fn Messages(cx: Scope<Props>) -> Element {
                                     if let Err(_e) = warp::async_block_in_place_uncheck(rg.reply(conversation_id, message_id, vec![reply])) {
                                         //TODO: Display error?
                                     }
-                                }
+                                },
+                                selecting: **selecting,
+                                selected: is_selected,
+                                on_select: move |shift| {
+                                    update_selection(
+                                        idx,
+                                        shift,
+                                        &displayed_ids.read(),
+                                        &mut selected.write(),
+                                        &mut last_clicked.write_silent(),
+                                    );
+                                    if !**selecting {
+                                        selecting.set(true);
+                                    }
+                                },
                             }
                         }
                     }
                 }),
                 script { "{msg_script}" 
             }
+            (**selecting).then(|| rsx!(
+                SelectionBar {
+                    account: cx.props.account.clone(),
+                    messaging: cx.props.messaging.clone(),
+                    messages: selected_messages,
+                    on_delete: move |ids: Vec<Uuid>| {
+                        let mut rg = cx.props.messaging.clone();
+                        for message in list2.read().iter().filter(|message| ids.contains(&message.id())) {
+                            if let Err(e) = warp::async_block_in_place_uncheck(rg.delete(message.conversation_id(), Some(message.id()))) {
+                                log::error!("failed to delete message: {}", e);
+                            }
+                        }
+                        list2.write().retain(|message| !ids.contains(&message.id()));
+                        selected.write().clear();
+                        selecting.set(false);
+                    },
+                    on_cancel: move |_| {
+                        selected.write().clear();
+                        *last_clicked.write_silent() = None;
+                        selecting.set(false);
+                    },
+                }
+            ))
         }
     })
 }
@@ -1,7 +1,9 @@
 pub mod divider;
+pub mod forward;
 pub mod messages;
 pub mod msg;
 pub mod reply;
+pub mod selection_bar;
 pub mod topbar;
 pub mod write;
 
@@ -9,6 +11,7 @@ use std::collections::HashMap;
 
 use dioxus::prelude::*;
 use dioxus_heroicons::outline::Shape;
+use emoji_selector::{packs, shortcodes};
 use ui_kit::{button::Button, typing_indicator::TypingIndicator};
 
 use rfd::FileDialog;
@@ -61,6 +64,7 @@ pub fn Compose(cx: Scope<Props>) -> Element {
                 rsx!(
                     TopBar {
                         account: cx.props.account.clone(),
+                        messaging: cx.props.messaging.clone(),
                         on_call: move |_| {
                             show_media.set(!show_media);
                         },
@@ -101,10 +105,22 @@ pub fn Compose(cx: Scope<Props>) -> Element {
                             text.set(String::from(""));
                             let mut rg = cx.props.messaging.clone();
 
+                            // shortcodes are sent as emojis, in the sender's skin tone
+                            let message = shortcodes::replace_shortcodes(&message, state.read().emoji_skin_tone);
+                            // custom emojis are sent with their pack id, and attached for recipients who don't have the pack
+                            let mut pack_attachments: Vec<PathBuf> = vec![];
                             let text_as_vec = message
                                 .split('\n')
                                 .filter(|&s| !s.is_empty())
-                                .map(|s| s.to_string())
+                                .map(|s| {
+                                    let (line, paths) = packs::encode_references(s);
+                                    for path in paths {
+                                        if !pack_attachments.contains(&path) {
+                                            pack_attachments.push(path);
+                                        }
+                                    }
+                                    line
+                                })
                                 .collect::<Vec<_>>();
 
                             if text_as_vec.is_empty() && selected_file.is_none() {
@@ -122,8 +138,9 @@ pub fn Compose(cx: Scope<Props>) -> Element {
                                         .dispatch(Actions::UpdateConversation(conversation_info));
                                 }
 
-                                if selected_file.is_some() {
-                                    let attachments = selected_file.as_ref().unwrap().to_vec();
+                                if selected_file.is_some() || !pack_attachments.is_empty() {
+                                    let mut attachments = selected_file.as_ref().cloned().unwrap_or_default();
+                                    attachments.extend(pack_attachments);
                                     if let Err(_e) =
                                         warp::async_block_in_place_uncheck(rg.attach(id, attachments, text_as_vec))
                                     {
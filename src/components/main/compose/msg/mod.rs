@@ -1,10 +1,11 @@
 use dioxus::prelude::*;
 use dioxus_heroicons::outline::Shape;
 use embeds::LinkEmbed;
+use emoji_selector::{packs, shortcodes};
 use linkify::LinkFinder;
 use pulldown_cmark::{html, Options, Parser};
 
-use state::{Actions, STATE};
+use state::{emoji::SkinTone, Actions, STATE};
 use ui_kit::{
     button::Button,
     context_menu::{ContextItem, ContextMenu},
@@ -14,7 +15,10 @@ use utils::Account;
 use warp::{crypto::DID, raygun::Message};
 
 use crate::{
-    components::reusable::{popout::Popout, textarea::TextArea},
+    components::{
+        main::compose::forward::ForwardPicker,
+        reusable::{popout::Popout, profile::FriendProfile, textarea::TextArea},
+    },
     iutils::{
         self,
         get_meta::{get_meta, SiteMeta},
@@ -38,6 +42,11 @@ pub struct Props<'a> {
     last: bool,
     profile_picture: Option<String>,
     on_reply: EventHandler<'a, String>,
+    // true while the conversation is in multi-select mode
+    selecting: bool,
+    selected: bool,
+    // called with `true` if shift was held, to select a range of messages
+    on_select: EventHandler<'a, bool>,
 }
 
 #[allow(non_snake_case)]
@@ -46,8 +55,21 @@ pub fn Msg<'a>(cx: Scope<'a, Props>) -> Element<'a> {
     let state = use_atom_ref(&cx, STATE).clone();
     let finder = LinkFinder::new();
     let content = cx.props.message.value();
-    let attachments = cx.props.message.attachments();
     let joined_a = content.join("\n");
+    // images attached for recipients without the emoji pack are hidden when the pack is installed
+    let attachments: Vec<_> = cx
+        .props
+        .message
+        .attachments()
+        .into_iter()
+        .filter(|file| {
+            let name = file.name();
+            !(packs::is_fallback_attachment(&name, &joined_a)
+                && packs::parse_attachment_name(&name)
+                    .and_then(|(name, pack_id)| packs::get(pack_id)?.find(name).map(|_| ()))
+                    .is_some())
+        })
+        .collect();
     let joined_b = joined_a.clone();
     let has_links = finder.links(&joined_b).next().is_some();
 
@@ -76,10 +98,24 @@ pub fn Msg<'a>(cx: Scope<'a, Props>) -> Element<'a> {
     let meta2 = meta.clone();
 
     let popout = use_state(&cx, || false);
+    let show_forward = use_state(&cx, || false);
+    let show_profile = use_state(&cx, || false);
     // text has been lifted from the child components into Msg so that
     // a button press can be used to clear it.
     let text = use_state(&cx, String::new);
-    let value = cx.props.message.clone().value().join("\n");
+    // the sender's skin tone is applied when sending, the shortcodes left are from other clients
+    let value = shortcodes::replace_shortcodes(
+        &cx.props.message.clone().value().join("\n"),
+        SkinTone::Default,
+    );
+    let without_custom = packs::strip_references(&value);
+    let only_custom = without_custom.trim().is_empty() && without_custom != value;
+    let emoji_only = match attachments.is_empty()
+        && (only_custom || shortcodes::is_emoji_only(&without_custom))
+    {
+        true => "emoji-only",
+        false => "",
+    };
 
     let timestamp = cx.props.message.clone().date();
     let ht = iutils::display_msg_time(timestamp);
@@ -106,6 +142,11 @@ pub fn Msg<'a>(cx: Scope<'a, Props>) -> Element<'a> {
 
     let hover = use_state(&cx, || false);
 
+    let selected_class = match cx.props.selected {
+        true => "selected",
+        false => "",
+    };
+
     let hover_class = match hover.get() {
         true => "animate_animated animate__pulse",
         false => "not-hovered",
@@ -123,6 +164,7 @@ pub fn Msg<'a>(cx: Scope<'a, Props>) -> Element<'a> {
     // and we therefore must enable it explicitly.
     let mut options = Options::empty();
     options.insert(Options::ENABLE_STRIKETHROUGH);
+    let value = packs::render_references(&value);
     let parser = Parser::new_ext(&value, options);
 
     // Write to String buffer.
@@ -150,6 +192,22 @@ pub fn Msg<'a>(cx: Scope<'a, Props>) -> Element<'a> {
     cx.render(rsx! (
         div {
             class: "wrapper {remote}",
+            (**show_profile).then(|| rsx!(
+                FriendProfile {
+                    account: cx.props.account.clone(),
+                    messaging: cx.props.messaging.clone(),
+                    did: cx.props.sender.clone(),
+                    is_visible: show_profile.clone(),
+                }
+            )),
+            (**show_forward).then(|| rsx!(
+                ForwardPicker {
+                    account: cx.props.account.clone(),
+                    messaging: cx.props.messaging.clone(),
+                    messages: vec![cx.props.message.clone()],
+                    on_dismiss: move |_| show_forward.set(false),
+                }
+            )),
             Popout {
                 is_visible: popout.clone(),
                 remote: remote.to_string(),
@@ -165,7 +223,7 @@ pub fn Msg<'a>(cx: Scope<'a, Props>) -> Element<'a> {
                             size: ui_kit::profile_picture::Size::Normal
                         },
                         div {
-                            class: "value popout {first} {middle} {last}",
+                            class: "value popout {first} {middle} {last} {emoji_only}",
                             div {
                                 class: "message-content",
                                 dangerous_inner_html: "{output1}",
@@ -211,7 +269,7 @@ pub fn Msg<'a>(cx: Scope<'a, Props>) -> Element<'a> {
                 }
             },
             div {
-                class: "message {remote} {hover_class}",
+                class: "message {remote} {hover_class} {selected_class}",
                 id: "{id}-message",
                 ContextMenu {
                     parent: format!("{}-message", &id),
@@ -227,6 +285,16 @@ pub fn Msg<'a>(cx: Scope<'a, Props>) -> Element<'a> {
                                 text: String::from("Reply"),
                                 icon: Shape::ArrowUturnLeft,
                             },
+                            ContextItem {
+                                onpressed: move |_| show_forward.set(true),
+                                text: String::from("Forward"),
+                                icon: Shape::ArrowUturnRight,
+                            },
+                            ContextItem {
+                                onpressed: move |_| cx.props.on_select.call(false),
+                                text: String::from("Select"),
+                                icon: Shape::CheckCircle,
+                            },
                             ContextItem {
                                 onpressed: move |_| {
                                     state
@@ -262,6 +330,16 @@ pub fn Msg<'a>(cx: Scope<'a, Props>) -> Element<'a> {
                                 text: String::from("Reply"),
                                 icon: Shape::ArrowUturnLeft,
                             },
+                            ContextItem {
+                                onpressed: move |_| show_forward.set(true),
+                                text: String::from("Forward"),
+                                icon: Shape::ArrowUturnRight,
+                            },
+                            ContextItem {
+                                onpressed: move |_| cx.props.on_select.call(false),
+                                text: String::from("Select"),
+                                icon: Shape::CheckCircle,
+                            },
                             ContextItem {
                                 onpressed: move |_| popout.set(true),
                                 text: String::from("Edit"),
@@ -276,17 +354,30 @@ pub fn Msg<'a>(cx: Scope<'a, Props>) -> Element<'a> {
                         }}
                     })
                 },
+                cx.props.selecting.then(|| rsx!(
+                    input {
+                        class: "select-checkbox",
+                        "type": "checkbox",
+                        checked: "{cx.props.selected}",
+                        onclick: move |e| {
+                            e.cancel_bubble();
+                            cx.props.on_select.call(e.data.shift_key);
+                        },
+                    }
+                )),
                 if cx.props.remote {
                     rsx! (
                         if cx.props.last {
                             rsx!(
                                 span {
                                     id: "{id}-pfp-message",
+                                    class: "message-pfp",
+                                    onclick: move |_| show_profile.set(true),
                                     ContextMenu {
                                         parent: format!("{}-pfp-message", id),
                                         items: cx.render(rsx! {
                                             ContextItem {
-                                                onpressed: move |_| {},
+                                                onpressed: move |_| show_profile.set(true),
                                                 text: String::from("View Profile"),
                                             },
                                         })
@@ -301,9 +392,13 @@ pub fn Msg<'a>(cx: Scope<'a, Props>) -> Element<'a> {
                             rsx!( div { class: "pfp-void" } )
                         },
                         div { // todo: don't duplicate this
-                            class: "value {first} {middle} {last}",
-                            onclick: |_| {
-                                popout.set(true);
+                            class: "value {first} {middle} {last} {emoji_only}",
+                            onclick: move |e| {
+                                if cx.props.selecting {
+                                    cx.props.on_select.call(e.data.shift_key);
+                                } else {
+                                    popout.set(true);
+                                }
                             },
                             onmouseover: |_| {
                                 hover.set(true);
@@ -327,9 +422,13 @@ pub fn Msg<'a>(cx: Scope<'a, Props>) -> Element<'a> {
                 } else {
                     rsx!(
                         div {
-                            class: "value {first} {middle} {last}",
-                            onclick: |_| {
-                                popout.set(true);
+                            class: "value {first} {middle} {last} {emoji_only}",
+                            onclick: move |e| {
+                                if cx.props.selecting {
+                                    cx.props.on_select.call(e.data.shift_key);
+                                } else {
+                                    popout.set(true);
+                                }
                             },
                             onmouseover: |_| {
                                 hover.set(true);
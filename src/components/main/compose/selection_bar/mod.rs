@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+
+use arboard::Clipboard;
+use chrono::prelude::*;
+use dioxus::prelude::*;
+use dioxus_heroicons::outline::Shape;
+use dioxus_toast::{Position, ToastInfo};
+use rfd::FileDialog;
+use ui_kit::button::{self, Button};
+use uuid::Uuid;
+use warp::{crypto::DID, raygun::Message};
+
+use crate::{
+    components::main::compose::forward::ForwardPicker, iutils, Account, Messaging, TOAST_MANAGER,
+};
+
+#[derive(Props)]
+pub struct Props<'a> {
+    account: Account,
+    messaging: Messaging,
+    // the selected messages, in the order they are displayed
+    messages: Vec<Message>,
+    on_delete: EventHandler<'a, Vec<Uuid>>,
+    on_cancel: EventHandler<'a, ()>,
+}
+
+#[allow(non_snake_case)]
+pub fn SelectionBar<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
+    log::debug!("rendering compose/SelectionBar");
+    let toast = use_atom_ref(&cx, TOAST_MANAGER);
+    let show_forward = use_state(&cx, || false);
+    let eval = use_eval(&cx);
+
+    let num_selected = cx.props.messages.len();
+    let nothing_selected = num_selected == 0;
+    let label = match num_selected {
+        1 => String::from("1 message selected"),
+        _ => format!("{} messages selected", num_selected),
+    };
+
+    cx.render(rsx! {
+        div {
+            class: "selection-bar",
+            (**show_forward).then(|| rsx!(
+                ForwardPicker {
+                    account: cx.props.account.clone(),
+                    messaging: cx.props.messaging.clone(),
+                    messages: cx.props.messages.clone(),
+                    on_dismiss: move |_| {
+                        show_forward.set(false);
+                        cx.props.on_cancel.call(());
+                    },
+                }
+            )),
+            p {
+                class: "selection-bar-label",
+                "{label}"
+            },
+            div {
+                class: "selection-bar-controls",
+                Button {
+                    icon: Shape::ClipboardDocument,
+                    text: String::from("Copy"),
+                    hide_text: true,
+                    disabled: nothing_selected,
+                    state: button::State::Secondary,
+                    on_pressed: move |_| {
+                        let text = format_as_text(&cx.props.messages, &cx.props.account);
+                        match Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text)) {
+                            Ok(_) => {
+                                let single_toast = ToastInfo {
+                                    position: Position::TopRight,
+                                    ..ToastInfo::simple("Messages copied!")
+                                };
+                                let _id = toast.write().popup(single_toast);
+                            }
+                            Err(e) => log::error!("failed to copy messages: {}", e),
+                        }
+                    },
+                },
+                Button {
+                    icon: Shape::ChatBubbleLeftEllipsis,
+                    text: String::from("Quote"),
+                    hide_text: true,
+                    disabled: nothing_selected,
+                    state: button::State::Secondary,
+                    on_pressed: move |_| {
+                        let quote = format_as_quote(&cx.props.messages, &cx.props.account);
+                        // the quote is inserted the same way a user would type it so the composer's state stays in sync
+                        eval(format!(
+                            "
+                            document.querySelector('#write #TODO-textarea-input .dynamic-input').focus();
+                            document.execCommand('insertText', false, {});
+                        ",
+                            serde_json::to_string(&quote).unwrap_or_default()
+                        ));
+                        cx.props.on_cancel.call(());
+                    },
+                },
+                Button {
+                    icon: Shape::ArrowUturnRight,
+                    text: String::from("Forward"),
+                    hide_text: true,
+                    disabled: nothing_selected,
+                    state: button::State::Secondary,
+                    on_pressed: move |_| show_forward.set(true),
+                },
+                Button {
+                    icon: Shape::ArrowDownOnSquare,
+                    text: String::from("Export"),
+                    hide_text: true,
+                    disabled: nothing_selected,
+                    state: button::State::Secondary,
+                    on_pressed: move |_| {
+                        let path = match FileDialog::new()
+                            .set_directory("/")
+                            .set_file_name("messages.txt")
+                            .save_file()
+                        {
+                            Some(path) => path,
+                            None => return,
+                        };
+                        let text = format_as_text(&cx.props.messages, &cx.props.account);
+                        if let Err(e) = std::fs::write(&path, text) {
+                            log::error!("failed to export messages to {}: {}", path.to_string_lossy(), e);
+                        }
+                    },
+                },
+                Button {
+                    icon: Shape::Trash,
+                    text: String::from("Delete"),
+                    hide_text: true,
+                    disabled: nothing_selected,
+                    state: button::State::Danger,
+                    on_pressed: move |_| {
+                        let ids = cx.props.messages.iter().map(|message| message.id()).collect();
+                        cx.props.on_delete.call(ids);
+                    },
+                },
+                Button {
+                    icon: Shape::XMark,
+                    text: String::from("Cancel"),
+                    hide_text: true,
+                    state: button::State::Secondary,
+                    on_pressed: move |_| cx.props.on_cancel.call(()),
+                },
+            }
+        }
+    })
+}
+
+/// updates the selection after the message at `idx` was clicked. when `shift` is held, every message between
+/// the previously clicked message and this one is selected.
+pub fn update_selection(
+    idx: usize,
+    shift: bool,
+    displayed: &[Uuid],
+    selected: &mut std::collections::HashSet<Uuid>,
+    last_clicked: &mut Option<usize>,
+) {
+    match (*last_clicked, shift) {
+        (Some(prev), true) => {
+            let (start, end) = if prev < idx { (prev, idx) } else { (idx, prev) };
+            for id in displayed.iter().take(end + 1).skip(start) {
+                selected.insert(*id);
+            }
+        }
+        _ => {
+            if let Some(id) = displayed.get(idx) {
+                if !selected.remove(id) {
+                    selected.insert(*id);
+                }
+            }
+        }
+    }
+    *last_clicked = Some(idx);
+}
+
+/// each message is preceded by a line containing the sender and the time it was sent
+pub fn format_as_text(messages: &[Message], account: &Account) -> String {
+    let mut usernames: HashMap<DID, String> = HashMap::new();
+    messages
+        .iter()
+        .map(|message| {
+            let username = usernames
+                .entry(message.sender())
+                .or_insert_with(|| iutils::get_username_from_did(message.sender(), account));
+            let time = message
+                .date()
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M");
+
+            let mut lines = vec![format!("{} ({})", username, time)];
+            lines.extend(message.value());
+            lines.extend(
+                message
+                    .attachments()
+                    .iter()
+                    .map(|file| format!("[attachment: {}]", file.name())),
+            );
+            lines.join("\n")
+        })
+        .collect::<Vec<String>>()
+        .join("\n\n")
+}
+
+pub fn format_as_quote(messages: &[Message], account: &Account) -> String {
+    let quoted = format_as_text(messages, account)
+        .lines()
+        .map(|line| format!("> {}", line))
+        .collect::<Vec<String>>()
+        .join("\n");
+    quoted + "\n"
+}
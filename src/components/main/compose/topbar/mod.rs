@@ -1,17 +1,21 @@
 use crate::{
-    components::reusable::toolbar,
+    components::reusable::{profile::FriendProfile, toolbar, verification::VerifiedBadge},
     iutils::{self, config::Config},
-    state::Actions,
-    STATE,
+    state::{
+        retention::{ControlMessage, Retention},
+        verification, Actions,
+    },
+    Messaging, STATE,
 };
 
 use dioxus::prelude::*;
-use dioxus_heroicons::outline::Shape;
+use dioxus_heroicons::{outline::Shape, Icon};
 use ui_kit::{
     activity_indicator::ActivityIndicator,
     button::Button,
     context_menu::{ContextItem, ContextMenu},
     profile_picture::PFP,
+    select::{Select, SelectOption},
     skeletons::{inline::InlineSkeleton, pfp::PFPSkeleton},
 };
 use utils::Account;
@@ -19,6 +23,7 @@ use utils::Account;
 #[derive(Props)]
 pub struct Props<'a> {
     account: Account,
+    messaging: Messaging,
     on_call: EventHandler<'a, ()>,
 }
 
@@ -28,6 +33,7 @@ pub fn TopBar<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
     let state = use_atom_ref(&cx, STATE);
     let config = Config::load_config_or_default();
     let mut favorites = state.read().favorites.clone();
+    let show_profile = use_state(&cx, || false);
 
     // Read their values from locks
     let mp = cx.props.account.clone();
@@ -43,15 +49,61 @@ pub fn TopBar<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
         Some(conversation_info) => {
             let (display_did, display_username) =
                 iutils::get_username_from_conversation(conversation_info, &mp);
+            let real_username = iutils::get_identity_username(display_did.clone(), &mp);
+            let display_did_string = display_did.to_string();
+            let verified = state.read().is_verified(&display_did_string);
+            let key_changed = verification::key_change(
+                &state.read().identities,
+                &display_did_string,
+                &real_username,
+            )
+            .is_some();
             let profile_picture = iutils::get_pfp_from_did(display_did.clone(), &mp);
 
             let id = conversation_info.conversation.id();
 
             let is_favorite = favorites.contains(&id);
 
+            let retention = conversation_info.retention;
+            let retention_value = retention.as_secs().to_string();
+            let retention_label = retention.label();
+            let retention_short_label = retention.short_label();
+            let retention_options: Vec<SelectOption> = Retention::all()
+                .iter()
+                .map(|r| SelectOption {
+                    value: r.as_secs().to_string(),
+                    label: r.label(),
+                })
+                .collect();
+
             cx.render(rsx! {
                 toolbar::Toolbar {
                     controls: cx.render(rsx! {
+                        div {
+                            class: "retention-select",
+                            title: "Disappearing messages",
+                            Select {
+                                value: retention_value,
+                                options: retention_options,
+                                on_change: move |value: String| {
+                                    let new_retention = match value.parse::<i64>().ok().and_then(Retention::from_secs) {
+                                        Some(r) => r,
+                                        None => return,
+                                    };
+                                    if new_retention == retention {
+                                        return;
+                                    }
+                                    state.write().dispatch(Actions::SetRetention(id, new_retention));
+                                    // let the other participants know so their messages disappear too
+                                    let mut rg = cx.props.messaging.clone();
+                                    if let Err(e) = warp::async_block_in_place_uncheck(
+                                        rg.send(id, None, ControlMessage::SetRetention(new_retention).encode()),
+                                    ) {
+                                        log::error!("failed to announce disappearing messages setting: {}", e);
+                                    }
+                                },
+                            },
+                        },
                         Button {
                             icon: Shape::Heart,
                             state: match is_favorite {
@@ -89,13 +141,23 @@ pub fn TopBar<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
                             },
                         },
                     },
+                    (**show_profile).then(|| rsx!(
+                        FriendProfile {
+                            account: cx.props.account.clone(),
+                            messaging: cx.props.messaging.clone(),
+                            did: display_did.clone(),
+                            is_visible: show_profile.clone(),
+                        }
+                    )),
                     span {
                         id: "pfp-topbar",
+                        class: "topbar-pfp",
+                        onclick: move |_| show_profile.set(true),
                         ContextMenu {
                             parent: String::from("pfp-topbar"),
                             items: cx.render(rsx! {
                                 ContextItem {
-                                    onpressed: move |_| {},
+                                    onpressed: move |_| show_profile.set(true),
                                     text: String::from("View Profile"),
                                 },
                             })
@@ -109,15 +171,37 @@ pub fn TopBar<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
                         class: "topbar-user-info",
                         h3 {
                             class: "ellipsis",
-                            "{display_username}"
+                            title: "{real_username}",
+                            "{display_username}",
+                            verified.then(|| rsx!(VerifiedBadge {}))
                         }
                         div {
                             class: "user-info-inline",
                             ActivityIndicator {
                                 inline: true,
                                 remote_did: display_did.clone(),
-                                account: cx.props.account.clone(),
                             },
+                            key_changed.then(|| rsx!(
+                                span {
+                                    class: "key-change-icon",
+                                    title: "Another key was seen with the username {real_username}. Verify their identity from the friends list.",
+                                    Icon {
+                                        icon: Shape::ExclamationTriangle,
+                                    },
+                                }
+                            )),
+                            (retention != Retention::Off).then(|| rsx!(
+                                div {
+                                    class: "retention-timer",
+                                    title: "Messages disappear after {retention_label}",
+                                    Icon {
+                                        icon: Shape::Clock,
+                                    },
+                                    span {
+                                        "{retention_short_label}"
+                                    }
+                                }
+                            )),
                             p {
                                 class: "did",
                                 config.developer.developer_mode.then(|| rsx!(
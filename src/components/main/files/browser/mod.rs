@@ -2,9 +2,9 @@ use std::{collections::HashSet, time::Duration, path::{PathBuf}};
 
 use dioxus::prelude::*;
 use dioxus_heroicons::{Icon, outline::Shape};
-use utils::files_functions;
+use utils::{files_functions, upload_queue::APP_DIRECTORY};
 
-use crate::Storage;
+use crate::{components::main::files::preview::FilePreview, Storage};
 use ui_kit::{file::File, folder::{State, Folder}, new_folder::NewFolder};
 use warp::constellation::{item::{ItemType}};
 mod lib;
@@ -26,6 +26,7 @@ pub fn FileBrowser(cx: Scope<Props>) -> Element {
     let root_directory = cx.props.storage.root_directory();
     let current_directory = cx.props.storage.current_directory().unwrap_or_else(|_| root_directory.clone());
     let update_current_dir = use_state(&cx, || ());
+    let preview = use_state(&cx, || None::<String>);
     let dir_paths = cx.props.dir_paths.clone();
 
     use_future(
@@ -34,6 +35,7 @@ pub fn FileBrowser(cx: Scope<Props>) -> Element {
         |(files, files_sorted, current_directory, files_storage, dir_paths, show_upload, show_new_folder)| async move {
            
             let current_dir_path = files_storage.get_path().clone();
+            let is_root = current_dir_path.as_os_str().is_empty();
             let dir_paths_vec = dir_paths.with(|vec| vec.clone());
             let dir_paths_len = dir_paths.read().len();
             let final_dir_path = dir_paths.read().last().unwrap().clone();
@@ -50,7 +52,12 @@ pub fn FileBrowser(cx: Scope<Props>) -> Element {
             
 
             loop {
-                let files_updated: HashSet<_> = HashSet::from_iter(current_directory.get_items());
+                // the app's own files aren't shown
+                let files_updated: HashSet<_> = current_directory
+                    .get_items()
+                    .into_iter()
+                    .filter(|item| !(is_root && item.name() == APP_DIRECTORY))
+                    .collect();
                 if *files.read() != files_updated {
                     log::debug!("updating files list");
                     *files.write_silent() = files_updated.clone();
@@ -63,7 +70,7 @@ pub fn FileBrowser(cx: Scope<Props>) -> Element {
         },
     );
     let root_dir_id = root_directory.id();
-    let current_dir_items_len = current_directory.get_items().len();
+    let current_dir_items_len = files_sorted.len();
     let current_dir_size = files_functions::format_item_size(current_directory.size());
 
     cx.render(rsx! {
@@ -110,6 +117,12 @@ pub fn FileBrowser(cx: Scope<Props>) -> Element {
             margin_left: "8px",
             "{current_dir_size} / {current_dir_items_len} item(s)"
             },
+        preview.get().is_some().then(|| rsx!(
+            FilePreview {
+                storage: cx.props.storage.clone(),
+                file_name: preview.clone(),
+            }
+        )),
         div {
          id: "browser",
             (cx.props.show_new_folder).then(|| 
@@ -174,6 +187,7 @@ pub fn FileBrowser(cx: Scope<Props>) -> Element {
                             size: file.size(),
                             thumbnail: file.thumbnail(),
                             storage: cx.props.storage.clone(),
+                            preview: preview.clone(),
                         } 
                     }
                    )
@@ -14,6 +14,7 @@ use utils::DRAG_FILE_EVENT;
 use dioxus::desktop::wry::webview::FileDropEvent;
 
 pub mod browser;
+pub mod preview;
 pub mod sidebar;
 pub mod toolbar;
 pub mod upload;
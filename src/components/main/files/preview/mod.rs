@@ -0,0 +1,322 @@
+use dioxus::{
+    events::{KeyboardEvent, MouseEvent, WheelEvent},
+    prelude::*,
+};
+use dioxus_heroicons::{outline::Shape, Icon};
+use futures::StreamExt;
+use ui_kit::button::{self, Button};
+use utils::files_functions;
+use warp::constellation::item::ItemType;
+
+use crate::{
+    iutils::file_preview::{self, PreviewKind},
+    Storage,
+};
+
+const MIN_ZOOM: f64 = 0.25;
+const MAX_ZOOM: f64 = 8.0;
+const ZOOM_STEP: f64 = 1.25;
+
+#[derive(Clone, PartialEq)]
+enum Preview {
+    /// a data URL
+    Image(String),
+    Html {
+        html: String,
+        truncated: bool,
+    },
+    Pdf,
+    Unsupported,
+}
+
+#[derive(Props, PartialEq)]
+pub struct Props {
+    storage: Storage,
+    /// the file shown. None closes the preview
+    file_name: UseState<Option<String>>,
+}
+
+/// shows a file of the current directory. the arrow keys move through the directory's files in the browser's order
+#[allow(non_snake_case)]
+pub fn FilePreview(cx: Scope<Props>) -> Element {
+    log::debug!("rendering files/FilePreview");
+    let eval = use_eval(&cx);
+    let name = cx.props.file_name.get().clone().unwrap_or_default();
+
+    let preview = use_future(
+        &cx,
+        (&name, &cx.props.storage),
+        |(name, storage)| async move {
+            load(&storage, &name).await.map_err(|e| {
+                log::error!("failed to preview {}: {}", name, e);
+                e
+            })
+        },
+    );
+
+    let focus_eval = eval.clone();
+    use_effect(&cx, (), move |_| {
+        let eval = focus_eval;
+        async move {
+            // the keys only reach the preview while it has the focus
+            eval("document.getElementById('file-preview')?.focus();".to_string());
+        }
+    });
+
+    // newest first, the same order as the file browser
+    let mut files: Vec<_> = cx
+        .props
+        .storage
+        .current_directory()
+        .map(|dir| dir.get_items())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|item| item.item_type() == ItemType::FileItem)
+        .collect();
+    files.sort_by_key(|item| std::cmp::Reverse(item.modified()));
+    let names: Vec<String> = files.iter().map(|item| item.name()).collect();
+    let size = files
+        .iter()
+        .find(|item| item.name() == name)
+        .map(|item| files_functions::format_item_size(item.size()))
+        .unwrap_or_default();
+    let position = names.iter().position(|file| *file == name);
+    let counter = position
+        .map(|idx| format!("{} of {}", idx + 1, names.len()))
+        .unwrap_or_default();
+    let previous = position
+        .filter(|idx| *idx > 0)
+        .map(|idx| names[idx - 1].clone());
+    let next = position.and_then(|idx| names.get(idx + 1).cloned());
+
+    let close = move || cx.props.file_name.set(None);
+    let show = move |file: &Option<String>| {
+        if file.is_some() {
+            cx.props.file_name.set(file.clone());
+        }
+    };
+    let (previous2, next2) = (previous.clone(), next.clone());
+
+    cx.render(rsx! {
+        div {
+            id: "file-preview",
+            tabindex: "-1",
+            onclick: move |_| close(),
+            onkeydown: move |e: KeyboardEvent| {
+                // the keys must not trigger the global shortcuts
+                e.cancel_bubble();
+                match e.key.as_str() {
+                    "Escape" => close(),
+                    "ArrowLeft" => show(&previous2),
+                    "ArrowRight" => show(&next2),
+                    _ => {}
+                }
+            },
+            div {
+                class: "file-preview-body",
+                onclick: move |e| e.cancel_bubble(),
+                div {
+                    class: "file-preview-header",
+                    div {
+                        class: "file-preview-title",
+                        p {
+                            class: "ellipsis",
+                            title: "{name}",
+                            "{name}"
+                        },
+                        span {
+                            "{size}"
+                        },
+                    },
+                    span {
+                        class: "file-preview-counter",
+                        "{counter}"
+                    },
+                    Button {
+                        icon: Shape::ChevronLeft,
+                        state: button::State::Secondary,
+                        disabled: previous.is_none(),
+                        on_pressed: move |_| show(&previous),
+                    },
+                    Button {
+                        icon: Shape::ChevronRight,
+                        state: button::State::Secondary,
+                        disabled: next.is_none(),
+                        on_pressed: move |_| show(&next),
+                    },
+                    Button {
+                        icon: Shape::XMark,
+                        state: button::State::Secondary,
+                        on_pressed: move |_| close(),
+                    },
+                },
+                div {
+                    class: "file-preview-content",
+                    match preview.value() {
+                        None => rsx!(
+                            p {
+                                class: "file-preview-message",
+                                "Loading…"
+                            }
+                        ),
+                        Some(Err(e)) => rsx!(
+                            p {
+                                class: "file-preview-message",
+                                "Can't preview this file: {e}"
+                            }
+                        ),
+                        Some(Ok(Preview::Image(src))) => rsx!(
+                            ImageViewer {
+                                key: "{name}",
+                                src: src.clone(),
+                            }
+                        ),
+                        Some(Ok(Preview::Html { html, truncated })) => rsx!(
+                            div {
+                                class: "file-preview-document",
+                                dangerous_inner_html: "{html}",
+                            },
+                            (*truncated).then(|| rsx!(
+                                p {
+                                    class: "file-preview-message",
+                                    "Only the beginning of the file is shown. Download it to see the rest."
+                                }
+                            ))
+                        ),
+                        Some(Ok(Preview::Pdf)) => rsx!(
+                            div {
+                                class: "file-preview-message",
+                                Icon { icon: Shape::DocumentText },
+                                p { "PDF pages can't be shown yet. Download the file to read it." }
+                            }
+                        ),
+                        Some(Ok(Preview::Unsupported)) => rsx!(
+                            div {
+                                class: "file-preview-message",
+                                Icon { icon: Shape::Document },
+                                p { "There is no preview for this type of file." }
+                            }
+                        ),
+                    }
+                }
+            }
+        }
+    })
+}
+
+#[derive(Props, PartialEq)]
+struct ImageProps {
+    src: String,
+}
+
+// zooms with the wheel or the buttons and pans by dragging
+#[allow(non_snake_case)]
+fn ImageViewer(cx: Scope<ImageProps>) -> Element {
+    let zoom = use_state(&cx, || 1.0_f64);
+    let offset = use_state(&cx, || (0_i32, 0_i32));
+    // the pointer position while dragging
+    let dragging = use_ref(&cx, || None::<(i32, i32)>);
+
+    let zoom_to = move |value: f64| zoom.set(value.clamp(MIN_ZOOM, MAX_ZOOM));
+    let (x, y) = **offset;
+    let transform = format!("transform: translate({}px, {}px) scale({});", x, y, **zoom);
+    let percentage = format!("{}%", (**zoom * 100.) as i32);
+    let src = &cx.props.src;
+
+    cx.render(rsx! {
+        div {
+            class: "file-preview-image",
+            onwheel: move |e: WheelEvent| {
+                if e.delta_y < 0. {
+                    zoom_to(**zoom * ZOOM_STEP);
+                } else if e.delta_y > 0. {
+                    zoom_to(**zoom / ZOOM_STEP);
+                }
+            },
+            onmousedown: move |e: MouseEvent| {
+                *dragging.write_silent() = Some((e.client_x, e.client_y));
+            },
+            onmousemove: move |e: MouseEvent| {
+                let last = *dragging.read();
+                if let Some((last_x, last_y)) = last {
+                    let (x, y) = **offset;
+                    offset.set((x + e.client_x - last_x, y + e.client_y - last_y));
+                    *dragging.write_silent() = Some((e.client_x, e.client_y));
+                }
+            },
+            onmouseup: move |_| *dragging.write_silent() = None,
+            onmouseleave: move |_| *dragging.write_silent() = None,
+            img {
+                src: "{src}",
+                draggable: "false",
+                style: "{transform}",
+            }
+        },
+        div {
+            class: "file-preview-zoom",
+            Button {
+                icon: Shape::MagnifyingGlassMinus,
+                state: button::State::Secondary,
+                on_pressed: move |_| zoom_to(**zoom / ZOOM_STEP),
+            },
+            span {
+                "{percentage}"
+            },
+            Button {
+                icon: Shape::MagnifyingGlassPlus,
+                state: button::State::Secondary,
+                on_pressed: move |_| zoom_to(**zoom * ZOOM_STEP),
+            },
+            Button {
+                text: String::from("Reset"),
+                state: button::State::Secondary,
+                on_pressed: move |_| {
+                    zoom.set(1.0);
+                    offset.set((0, 0));
+                },
+            },
+        }
+    })
+}
+
+async fn load(storage: &Storage, name: &str) -> Result<Preview, String> {
+    let kind = PreviewKind::of(name);
+    match kind {
+        PreviewKind::Pdf => return Ok(Preview::Pdf),
+        PreviewKind::Unsupported => return Ok(Preview::Unsupported),
+        _ => {}
+    }
+    let (bytes, truncated) = read(storage, name, kind.max_bytes()).await?;
+    let preview = match kind {
+        PreviewKind::Image(_) if truncated => {
+            return Err(String::from("the image is too large"));
+        }
+        PreviewKind::Image(mime) => Preview::Image(file_preview::image_data_url(mime, &bytes)),
+        // highlighting a long file takes a while
+        kind => tokio::task::spawn_blocking(move || {
+            let text = String::from_utf8_lossy(&bytes);
+            let html = match kind {
+                PreviewKind::Text(extension) => file_preview::highlighted_html(&text, &extension),
+                _ => file_preview::markdown_html(&text),
+            };
+            Preview::Html { html, truncated }
+        })
+        .await
+        .map_err(|e| e.to_string())?,
+    };
+    Ok(preview)
+}
+
+// reads at most `limit` bytes of the file. true when the file is longer
+async fn read(storage: &Storage, name: &str, limit: usize) -> Result<(Vec<u8>, bool), String> {
+    let mut stream = storage.get_stream(name).await.map_err(|e| e.to_string())?;
+    let mut bytes = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        bytes.extend(chunk.map_err(|e| e.to_string())?);
+        if bytes.len() > limit {
+            bytes.truncate(limit);
+            return Ok((bytes, true));
+        }
+    }
+    Ok((bytes, false))
+}
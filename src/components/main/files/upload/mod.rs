@@ -14,7 +14,7 @@ use futures::StreamExt;
 use ui_kit::button::Button;
 use rfd::FileDialog;
 use crate::{DRAG_FILE_EVENT};
-use utils::files_functions;
+use utils::upload_queue;
 
 #[derive(Props)]
 pub struct Props<'a> {
@@ -75,16 +75,7 @@ pub fn Upload<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
 
                             if let FileDropEvent::Dropped(files_local_path) = drag_file_event {
                                 *drag_over_dropzone.write_silent() = false;
-                                for file_path in &files_local_path {
-                                    files_functions::upload_file(
-                                        file_storage.clone(),
-                                        file_path.clone(),
-                                        eval_script.clone(),
-                                    )
-                                    .await;
-                                    tokio::time::sleep(std::time::Duration::from_millis(150)).await;
-                                    log::info!("{} file uploaded!", file_path.to_string_lossy());
-                                }
+                                upload_queue::enqueue(files_local_path, file_storage.get_path());
                                 // TODO(use_eval): Try new solution in the future
                                 eval_script.eval(&file_leave_dropzone_js);
                             }
@@ -157,19 +148,19 @@ pub fn Upload<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
                                     Some(path) => path,
                                     None => return
                                 };
-                                let file_storage = cx.props.storage.clone();
-                                cx.spawn({
-                                    to_owned![file_storage, files_local_path, eval_script];
-                                    async move {
-                                        for file_path in &files_local_path {
-                                            files_functions::upload_file(file_storage.clone(), file_path.clone(), 
-                                            eval_script.clone(),
-                                        ).await;
-                                        }
-                                    }
-                                });
+                                upload_queue::enqueue(files_local_path, cx.props.storage.get_path());
                             }
                         }
+                        Button {
+                            text: String::from("Folder"),
+                            icon: Shape::FolderArrowDown,
+                            state: ui_kit::button::State::Secondary,
+                            on_pressed: move |_| {
+                                if let Some(folders) = FileDialog::new().set_directory(".").pick_folders() {
+                                    upload_queue::enqueue(folders, cx.props.storage.get_path());
+                                }
+                            },
+                        }
                         hr {
                            class: "hr-between-input-and-dropzone",
                         }
@@ -177,7 +168,7 @@ pub fn Upload<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
                             id: "dropzone",
                             readonly: "true",
                             class: "dropzone",
-                            value: "Drop files here to upload",
+                            value: "Drop files or folders here to upload",
                             prevent_default: "onmouseover",
                             onmouseover: move |_| {
                                 // HACK(Windows): When drop over dropzone, onmouseover is pushed
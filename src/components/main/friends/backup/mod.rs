@@ -0,0 +1,360 @@
+use std::collections::HashSet;
+
+use dioxus::prelude::*;
+use dioxus_heroicons::outline::Shape;
+use rfd::FileDialog;
+use ui_kit::{
+    button::{self, Button},
+    switch::Switch,
+};
+use warp::crypto::DID;
+
+use crate::{
+    components::reusable::popout::Popout,
+    iutils,
+    state::{
+        backup::{self, Conflict, ContactBackup, ImportPlan, LocalContacts},
+        Actions,
+    },
+    Account, STATE,
+};
+
+#[derive(Clone, PartialEq)]
+struct Review {
+    // the backup was signed by another account
+    other_account: bool,
+    plan: ImportPlan,
+}
+
+/// exports the contacts to a signed file and imports them after the changes are reviewed
+#[inline_props]
+#[allow(non_snake_case)]
+pub fn ContactBackups(cx: Scope, account: Account) -> Element {
+    log::debug!("rendering friends/ContactBackups");
+    let state = use_atom_ref(&cx, STATE);
+    let status = use_state(&cx, String::new);
+    let show_review = use_state(&cx, || false);
+    let review = use_state(&cx, || None::<Review>);
+    // the conflicts resolved in favor of the backup, by index
+    let take_imported = use_ref(&cx, HashSet::<usize>::new);
+    // missing contacts the user chose not to send a request to
+    let skipped = use_ref(&cx, HashSet::<String>::new);
+
+    let export = move |_| {
+        let path = match FileDialog::new()
+            .set_file_name("uplink-contacts.json")
+            .add_filter("JSON", &["json"])
+            .save_file()
+        {
+            Some(path) => path,
+            None => return,
+        };
+        let friends = warp::async_block_in_place_uncheck(account.list_friends())
+            .unwrap_or_default()
+            .iter()
+            .map(|did| did.to_string())
+            .collect();
+        let blocked = warp::async_block_in_place_uncheck(account.block_list())
+            .unwrap_or_default()
+            .iter()
+            .map(|did| did.to_string())
+            .collect();
+        let contacts = ContactBackup::new(
+            friends,
+            blocked,
+            state.read().contacts.clone(),
+            state.read().friend_groups.clone(),
+        );
+        let result = warp::async_block_in_place_uncheck(backup::export(account, &contacts))
+            .map_err(|e| e.to_string())
+            .and_then(|json| std::fs::write(&path, json).map_err(|e| e.to_string()));
+        match result {
+            Ok(_) => status.set(String::from("Contacts exported")),
+            Err(e) => {
+                log::error!("failed to export contacts: {}", e);
+                status.set(String::from("Failed to export the contacts"));
+            }
+        }
+    };
+
+    let import = move |_| {
+        let path = match FileDialog::new().add_filter("JSON", &["json"]).pick_file() {
+            Some(path) => path,
+            None => return,
+        };
+        let result = std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|json| backup::import(&json).map_err(|e| e.to_string()));
+        let (signer, contacts) = match result {
+            Ok(imported) => imported,
+            Err(e) => {
+                log::error!("failed to import contacts: {}", e);
+                status.set(format!("Can't import the contacts: {}", e));
+                return;
+            }
+        };
+        let own_did = warp::async_block_in_place_uncheck(account.get_own_identity())
+            .map(|identity| identity.did_key().to_string())
+            .unwrap_or_default();
+        let friends: HashSet<String> = warp::async_block_in_place_uncheck(account.list_friends())
+            .unwrap_or_default()
+            .iter()
+            .map(|did| did.to_string())
+            .collect();
+        let blocked: HashSet<String> = warp::async_block_in_place_uncheck(account.block_list())
+            .unwrap_or_default()
+            .iter()
+            .map(|did| did.to_string())
+            .collect();
+        let pending: HashSet<String> =
+            warp::async_block_in_place_uncheck(account.list_outgoing_request())
+                .unwrap_or_default()
+                .iter()
+                .map(|request| request.to().to_string())
+                .collect();
+        let plan = backup::plan(
+            &contacts,
+            &LocalContacts {
+                own_did: &own_did,
+                friends: &friends,
+                blocked: &blocked,
+                pending: &pending,
+                contacts: &state.read().contacts,
+            },
+        );
+        if plan.is_empty() {
+            status.set(String::from("Everything in the backup is already here"));
+            return;
+        }
+        status.set(String::new());
+        take_imported.write().clear();
+        skipped.write().clear();
+        review.set(Some(Review {
+            other_account: signer != own_did,
+            plan,
+        }));
+        show_review.set(true);
+    };
+
+    let apply = move |_| {
+        let Some(Review { mut plan, .. }) = review.get().clone() else {
+            return;
+        };
+        for (idx, conflict) in plan.conflicts.clone().iter().enumerate() {
+            if take_imported.read().contains(&idx) {
+                plan.take_imported(conflict);
+            }
+        }
+        plan.send_requests
+            .retain(|did| !skipped.read().contains(did));
+
+        let mut account = account.clone();
+        let mut failed = 0;
+        // unblocking comes first, since a blocked user can't receive a request
+        for did in plan.unblocks.iter().filter_map(|did| parse_did(did)) {
+            if let Err(e) = warp::async_block_in_place_uncheck(account.unblock(&did)) {
+                log::error!("failed to unblock {}: {}", did, e);
+                failed += 1;
+            }
+        }
+        for did in plan.blocks.iter().filter_map(|did| parse_did(did)) {
+            if let Err(e) = warp::async_block_in_place_uncheck(account.block(&did)) {
+                log::error!("failed to block {}: {}", did, e);
+                failed += 1;
+            }
+        }
+        for did in plan.send_requests.iter().filter_map(|did| parse_did(did)) {
+            if let Err(e) = warp::async_block_in_place_uncheck(account.send_request(&did)) {
+                log::error!("failed to send a friend request to {}: {}", did, e);
+                failed += 1;
+            }
+        }
+        state
+            .write()
+            .dispatch(Actions::ImportContacts(plan.contacts));
+        state
+            .write()
+            .dispatch(Actions::ImportFriendGroups(plan.groups));
+
+        if failed == 0 {
+            status.set(String::from("Contacts imported"));
+        } else {
+            status.set(format!("Contacts imported, but {} changes failed", failed));
+        }
+        show_review.set(false);
+        review.set(None);
+    };
+
+    cx.render(rsx! {
+        div {
+            class: "contact-backups",
+            div {
+                class: "contact-backup-controls",
+                Button {
+                    text: String::from("Export Contacts"),
+                    icon: Shape::ArrowUpTray,
+                    state: button::State::Secondary,
+                    on_pressed: export,
+                },
+                Button {
+                    text: String::from("Import Contacts"),
+                    icon: Shape::ArrowDownTray,
+                    state: button::State::Secondary,
+                    on_pressed: import,
+                },
+            },
+            (!status.is_empty()).then(|| rsx!(
+                p {
+                    class: "contact-backup-status",
+                    "{status}"
+                }
+            )),
+            review.get().as_ref().map(|current| {
+                let plan = &current.plan;
+                let blocks = plan.blocks.len();
+                let contacts = plan.contacts.len();
+                let groups = plan.groups.len();
+                rsx!(
+                    Popout {
+                        is_visible: show_review.clone(),
+                        remote: String::new(),
+                        div {
+                            class: "contact-import-review",
+                            onclick: move |e| e.cancel_bubble(),
+                            h3 {
+                                "Import Contacts"
+                            },
+                            current.other_account.then(|| rsx!(
+                                p {
+                                    class: "contact-import-warning",
+                                    "This backup was made by another account."
+                                }
+                            )),
+                            ul {
+                                (blocks > 0).then(|| rsx!(li { "{blocks} users will be blocked" })),
+                                (contacts > 0).then(|| rsx!(li { "{contacts} nicknames and notes will be added" })),
+                                (groups > 0).then(|| rsx!(li { "{groups} groups will be merged" })),
+                            },
+                            (!plan.send_requests.is_empty()).then(|| rsx!(
+                                label {
+                                    "Send friend requests"
+                                },
+                                plan.send_requests.iter().map(|did| {
+                                    let name = contact_name(did, account);
+                                    let active = !skipped.read().contains(did);
+                                    rsx!(
+                                        div {
+                                            key: "{did}",
+                                            class: "contact-import-item",
+                                            span {
+                                                class: "ellipsis",
+                                                title: "{did}",
+                                                "{name}"
+                                            },
+                                            Switch {
+                                                active: active,
+                                                on_change: move |_| {
+                                                    let mut skipped = skipped.write();
+                                                    if !skipped.remove(did) {
+                                                        skipped.insert(did.clone());
+                                                    }
+                                                },
+                                            }
+                                        }
+                                    )
+                                })
+                            )),
+                            (!plan.conflicts.is_empty()).then(|| rsx!(
+                                label {
+                                    "Conflicts. Switch on to use the backup"
+                                },
+                                plan.conflicts.iter().enumerate().map(|(idx, conflict)| {
+                                    let description = describe(conflict, &contact_name(conflict.did(), account));
+                                    let active = take_imported.read().contains(&idx);
+                                    rsx!(
+                                        div {
+                                            key: "{idx}",
+                                            class: "contact-import-item",
+                                            span {
+                                                "{description}"
+                                            },
+                                            Switch {
+                                                active: active,
+                                                on_change: move |_| {
+                                                    let mut take_imported = take_imported.write();
+                                                    if !take_imported.remove(&idx) {
+                                                        take_imported.insert(idx);
+                                                    }
+                                                },
+                                            }
+                                        }
+                                    )
+                                })
+                            )),
+                            div {
+                                class: "contact-import-controls",
+                                Button {
+                                    text: String::from("Cancel"),
+                                    state: button::State::Secondary,
+                                    on_pressed: move |_| {
+                                        show_review.set(false);
+                                        review.set(None);
+                                    },
+                                },
+                                Button {
+                                    text: String::from("Apply"),
+                                    icon: Shape::Check,
+                                    on_pressed: apply,
+                                },
+                            }
+                        }
+                    }
+                )
+            })
+        }
+    })
+}
+
+fn parse_did(did: &str) -> Option<DID> {
+    match DID::try_from(did.to_string()) {
+        Ok(did) => Some(did),
+        Err(e) => {
+            log::error!("invalid DID in the contact backup: {}", e);
+            None
+        }
+    }
+}
+
+// the username, or the DID when the identity can't be found. nicknames may be part of a conflict, so they aren't used
+fn contact_name(did: &str, account: &Account) -> String {
+    DID::try_from(did.to_string())
+        .ok()
+        .map(|did| iutils::get_identity_username(did, account))
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| did.to_string())
+}
+
+fn describe(conflict: &Conflict, name: &str) -> String {
+    match conflict {
+        Conflict::Nickname {
+            local, imported, ..
+        } => format!(
+            "{} is called {} here and {} in the backup",
+            name, local, imported
+        ),
+        Conflict::Notes {
+            local, imported, ..
+        } => format!(
+            "The notes for {} differ. Here: {}. Backup: {}",
+            name, local, imported
+        ),
+        Conflict::BlockedHere(_) => format!(
+            "{} is a friend in the backup but blocked here. Using the backup unblocks them and sends a request",
+            name
+        ),
+        Conflict::FriendHere(_) => format!(
+            "{} is blocked in the backup but a friend here. Using the backup blocks them",
+            name
+        ),
+    }
+}
@@ -6,8 +6,10 @@ use dioxus::{
 };
 use dioxus_heroicons::outline::Shape;
 use dioxus_toast::{Position, ToastInfo};
+use rfd::FileDialog;
+use utils::friend_code;
 
-use crate::{Account, LANGUAGE, TOAST_MANAGER};
+use crate::{state::Actions, Account, LANGUAGE, STATE, TOAST_MANAGER};
 
 use ui_kit::{button::Button, input::Input, select::SelectOption};
 
@@ -24,6 +26,9 @@ pub fn FindFriends(
     let toast = use_atom_ref(&cx, TOAST_MANAGER);
     let l = use_atom_ref(&cx, LANGUAGE).read();
     let remote_friend = use_state(&cx, String::new);
+    // sent as the first message once the request is accepted
+    let intro_note = use_state(&cx, String::new);
+    let state = use_atom_ref(&cx, STATE);
 
     let l2 = l.clone();
     let l3 = l.clone();
@@ -45,6 +50,74 @@ pub fn FindFriends(
     };
     let copy_friend_id2 = copy_friend_id.clone();
 
+    // the QR code of our friend code, as a data URI
+    let qr_code = use_state(&cx, || {
+        warp::async_block_in_place_uncheck(account.get_own_identity())
+            .map_err(|e| e.to_string())
+            .and_then(|ident| {
+                friend_code::qr_data_uri(&friend_code::from_did(&ident.did_key().to_string()))
+            })
+            .map_err(|e| log::error!("failed to render the friend code QR code: {}", e))
+            .ok()
+    });
+    let account3 = account.clone();
+    let save_qr_code = move |_| {
+        let ident = match warp::async_block_in_place_uncheck(account3.get_own_identity()) {
+            Ok(ident) => ident,
+            Err(e) => {
+                log::error!("failed to get own identity: {}", e);
+                return;
+            }
+        };
+        let path = match FileDialog::new()
+            .set_file_name("uplink-friend-code.png")
+            .add_filter("PNG", &["png"])
+            .save_file()
+        {
+            Some(path) => path,
+            None => return,
+        };
+        let code = friend_code::from_did(&ident.did_key().to_string());
+        if let Err(e) = friend_code::save_qr_png(&code, &path) {
+            log::error!("failed to save the friend code QR code: {}", e);
+        }
+    };
+
+    // a friend code read from a QR code is put in the input, so that the user can check it before sending the request
+    let l4 = l.clone();
+    let prefill_from_qr = move |result: Result<String, String>| match result {
+        Ok(code) => {
+            remote_friend.set(code);
+            add_error.set("".into());
+        }
+        Err(e) => {
+            log::debug!("failed to read a QR code: {}", e);
+            add_error.set(l4.no_qr_code.to_string());
+        }
+    };
+    let prefill_from_qr2 = prefill_from_qr.clone();
+    let add_from_image = move |_| {
+        if let Some(path) = FileDialog::new()
+            .add_filter("Image", &["png", "jpg", "jpeg", "bmp", "gif", "webp"])
+            .pick_file()
+        {
+            prefill_from_qr(friend_code::decode_qr_file(&path));
+        }
+    };
+    let paste_qr_image = move |_| {
+        let result = Clipboard::new()
+            .and_then(|mut clipboard| clipboard.get_image())
+            .map_err(|e| e.to_string())
+            .and_then(|image| {
+                friend_code::decode_qr_rgba(
+                    image.width as u32,
+                    image.height as u32,
+                    image.bytes.into_owned(),
+                )
+            });
+        prefill_from_qr2(result);
+    };
+
     let search_results = use_state(&cx, Vec::<SelectOption>::new);
 
     let on_change = move |ev: FormEvent| {
@@ -100,6 +173,11 @@ pub fn FindFriends(
                                         };
                                         let _id = toast.write().popup(single_toast);
                                         add_error.set("".into());
+                                        state.write().dispatch(Actions::SetIntroNote(
+                                            d.to_string(),
+                                            Some(intro_note.get().clone()),
+                                        ));
+                                        intro_note.set(String::new());
                                     }
                                     Err(e) => {
                                         add_error.set(match e {
@@ -141,6 +219,11 @@ pub fn FindFriends(
                                         };
                                         let _id = toast.write().popup(single_toast);
                                         add_error.set("".into());
+                                        state.write().dispatch(Actions::SetIntroNote(
+                                            d.to_string(),
+                                            Some(intro_note.get().clone()),
+                                        ));
+                                        intro_note.set(String::new());
                                     }
                                     Err(e) => {
                                         add_error.set(match e {
@@ -171,6 +254,19 @@ pub fn FindFriends(
                     }
                 }),
             },
+            input {
+                class: "intro-note",
+                value: "{intro_note}",
+                placeholder: "{l.intro_note_placeholder}",
+                oninput: move |e| intro_note.set(e.value.clone()),
+            },
+            // the placeholder is gone once there's a note
+            (!intro_note.is_empty()).then(|| rsx!(
+                p {
+                    class: "intro-note-hint",
+                    "{l.intro_note_pending}"
+                }
+            )),
             div {
                 class: "error_text",
                 "{add_error}"
@@ -193,6 +289,36 @@ pub fn FindFriends(
                             }
                         }
                     },
+                    qr_code.as_ref().map(|src| rsx!(
+                        div {
+                            class: "friend-qr",
+                            img {
+                                src: "{src}",
+                                alt: "{l3.copy_friend_code}",
+                            },
+                            Button {
+                                text: l3.save_qr_code.to_string(),
+                                icon: Shape::ArrowDownTray,
+                                state: ui_kit::button::State::Secondary,
+                                on_pressed: save_qr_code,
+                            }
+                        }
+                    )),
+                    div {
+                        class: "add-from-image",
+                        Button {
+                            text: l3.add_from_image.to_string(),
+                            icon: Shape::Photo,
+                            state: ui_kit::button::State::Secondary,
+                            on_pressed: add_from_image,
+                        },
+                        Button {
+                            text: l3.paste_qr_image.to_string(),
+                            icon: Shape::QrCode,
+                            state: ui_kit::button::State::Secondary,
+                            on_pressed: paste_qr_image,
+                        },
+                    }
                 }
             }),
         }
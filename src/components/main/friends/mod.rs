@@ -1,3 +1,4 @@
+pub mod backup;
 pub mod find;
 pub mod list_type_button;
 pub mod request;
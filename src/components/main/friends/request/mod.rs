@@ -1,7 +1,10 @@
 use dioxus::prelude::*;
 use dioxus_heroicons::outline::Shape;
 
-use crate::iutils;
+use crate::{
+    components::reusable::verification::KeyChangeWarning, iutils, state::verification, LANGUAGE,
+    STATE,
+};
 use utils::Account;
 
 use ui_kit::{
@@ -16,13 +19,18 @@ pub struct Props<'a> {
     account: Account,
     request: FriendRequest,
     deny_only: bool,
+    // whether the sender's requests are silently ignored
+    ignored: bool,
     on_deny: EventHandler<'a, ()>,
     on_accept: EventHandler<'a, ()>,
+    on_ignore: EventHandler<'a, ()>,
 }
 
 #[allow(non_snake_case)]
 pub fn FriendRequest<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
     log::debug!("rendering FriendRequest");
+    let state = use_atom_ref(&cx, STATE);
+    let l = use_atom_ref(&cx, LANGUAGE).read();
     let mp = cx.props.account.clone();
 
     let did = if cx.props.deny_only {
@@ -33,6 +41,17 @@ pub fn FriendRequest<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
 
     let username = iutils::get_username_from_did(did.clone(), &mp);
     let show_skeleton = username.is_empty();
+    // a request from someone using the username of a friend is a likely impersonation
+    let real_username = iutils::get_identity_username(did.clone(), &mp);
+    let key_changed =
+        verification::key_change(&state.read().identities, &did.to_string(), &real_username)
+            .is_some();
+    // the note attached to an outgoing request, which is only sent once they accept
+    let intro = cx
+        .props
+        .deny_only
+        .then(|| state.read().requests.intros.get(&did.to_string()).cloned())
+        .flatten();
     let profile_picture = iutils::get_pfp_from_did(did, &mp);
 
     cx.render(rsx! {
@@ -53,7 +72,17 @@ pub fn FriendRequest<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
                 )} else {rsx!(
                     h3 {
                         "{username}",
-                    }
+                    },
+                    key_changed.then(|| rsx!(
+                        KeyChangeWarning { username: real_username.clone() }
+                    )),
+                    intro.map(|note| rsx!(
+                        p {
+                            class: "request-note ellipsis",
+                            title: "{l.intro_note_pending}: {note}",
+                            "{l.intro_note_pending}: {note}"
+                        }
+                    ))
                 )}
             },
             div {
@@ -71,6 +100,18 @@ pub fn FriendRequest<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
                     )
                 }
                 (!cx.props.deny_only).then(|| rsx!{
+                    div {
+                        class: "control-wrap",
+                        Button {
+                            icon: if cx.props.ignored { Shape::Eye } else { Shape::EyeSlash },
+                            state: button::State::Secondary,
+                            text: if cx.props.ignored { "Stop Ignoring".to_string() } else { "Ignore".to_string() },
+                            hide_text: true,
+                            on_pressed: move |_| {
+                                cx.props.on_ignore.call(());
+                            }
+                        }
+                    }
                     if show_skeleton {rsx!(
                         div {
                             class: "control-wrap",
@@ -1,10 +1,12 @@
 use crate::components::main::friends::request::FriendRequest;
-use crate::{Account, LANGUAGE};
+use crate::{state::Actions, Account, LANGUAGE, STATE};
 
 use std::collections::HashSet;
 
 use dioxus::prelude::*;
+use dioxus_heroicons::outline::Shape;
 use futures::StreamExt;
+use ui_kit::button::{self, Button};
 use warp::multipass::MultiPassEventKind;
 
 #[inline_props]
@@ -12,9 +14,13 @@ use warp::multipass::MultiPassEventKind;
 pub fn FriendRequests(cx: Scope, account: Account, add_error: UseState<String>) -> Element {
     log::debug!("rendering FriendRequests");
 
+    let state = use_atom_ref(&cx, STATE);
     let l = use_atom_ref(&cx, LANGUAGE).read();
     let incomingRequestsLang = { l.incoming_requests.to_string() };
     let outgoingRequestsLang = { l.outgoing_requests.to_string() };
+    let ignoredRequestsLang = { l.ignored_requests.to_string() };
+    let filteredRequestsLang = { l.filtered_requests.to_string() };
+    let show_ignored = use_state(&cx, || false);
 
     let incoming: &UseState<HashSet<_>> = use_state(&cx, || {
         HashSet::from_iter(
@@ -128,15 +134,38 @@ pub fn FriendRequests(cx: Scope, account: Account, add_error: UseState<String>)
         },
     );
 
+    // requests from ignored senders are kept out of the main list
+    let ignored = state.read().requests.ignored.clone();
+    let (ignored_incoming, pending_incoming): (Vec<_>, Vec<_>) = incoming
+        .iter()
+        .partition(|request| ignored.contains(&request.from().to_string()));
+    let ignored_count = ignored_incoming.len();
+    let filtered_count = state.read().requests.filtered_count;
+
     cx.render(rsx! {
         div {
             div {
-                (!incoming.is_empty()).then(|| rsx!(
+                (filtered_count > 0).then(|| rsx!(
+                    div {
+                        class: "filtered-requests",
+                        span {
+                            "{filtered_count} {filteredRequestsLang}"
+                        },
+                        Button {
+                            icon: Shape::XMark,
+                            state: button::State::Transparent,
+                            on_pressed: move |_| {
+                                state.write().dispatch(Actions::ResetFilteredRequests);
+                            },
+                        },
+                    }
+                )),
+                (!pending_incoming.is_empty()).then(|| rsx!(
                     label {
                     "{incomingRequestsLang}"
                     },
                     div {
-                        incoming.iter().map(|request| rsx!(
+                        pending_incoming.iter().map(|&request| rsx!(
                             FriendRequest {
                                 account: account.clone(),
                                 request: request.clone(),
@@ -168,11 +197,61 @@ pub fn FriendRequests(cx: Scope, account: Account, add_error: UseState<String>)
                                         },
                                     }
                                 },
+                                on_ignore: move |_| {
+                                    state.write().dispatch(Actions::SetRequestsIgnored(request.from().to_string(), true));
+                                },
                                 deny_only: false,
+                                ignored: false,
                             }
                         )),
                     }
                 )),
+                (!ignored_incoming.is_empty()).then(|| rsx!(
+                    label {
+                        class: "ignored-requests-toggle",
+                        onclick: move |_| show_ignored.set(!**show_ignored),
+                        "{ignoredRequestsLang} ({ignored_count})"
+                    },
+                    show_ignored.then(|| rsx!(
+                        div {
+                            ignored_incoming.iter().map(|&request| rsx!(
+                                FriendRequest {
+                                    account: account.clone(),
+                                    request: request.clone(),
+                                    on_accept: move |_| {
+                                        match warp::async_block_in_place_uncheck(account.clone()
+                                            .accept_request(&request.from()))
+                                        {
+                                            Ok(_) => {
+                                                add_error.set("".into());
+                                            },
+                                            Err(e) => {
+                                                log::error!("error accepting friend request: {e}");
+                                            },
+                                        }
+                                    },
+                                    on_deny: move |_| {
+                                        match warp::async_block_in_place_uncheck(account.clone()
+                                            .deny_request(&request.from()))
+                                        {
+                                            Ok(_) => {
+                                                add_error.set("".into());
+                                            },
+                                            Err(e) => {
+                                                log::error!("error denying friend request: {e}");
+                                            },
+                                        }
+                                    },
+                                    on_ignore: move |_| {
+                                        state.write().dispatch(Actions::SetRequestsIgnored(request.from().to_string(), false));
+                                    },
+                                    deny_only: false,
+                                    ignored: true,
+                                }
+                            )),
+                        }
+                    )),
+                )),
                 (!outgoing.is_empty()).then(|| rsx!(
                     label {
                         "{outgoingRequestsLang}"
@@ -197,7 +276,9 @@ pub fn FriendRequests(cx: Scope, account: Account, add_error: UseState<String>)
                                     }
                                 },
                                 on_accept: move |_| {},
+                                on_ignore: move |_| {},
                                 deny_only: true,
+                                ignored: false,
                             }
                         )),
                     }
@@ -2,7 +2,9 @@ use dioxus::prelude::*;
 use utils::Account;
 
 use crate::{
-    components::main::friends::{find::FindFriends, requests::FriendRequests},
+    components::main::friends::{
+        backup::ContactBackups, find::FindFriends, requests::FriendRequests,
+    },
     Messaging,
 };
 
@@ -22,6 +24,7 @@ pub fn Sidebar(
             messaging: messaging.clone(),
             FindFriends { account: account.clone(), add_error: add_error.clone(), is_compact: false },
             FriendRequests {account: account.clone(), add_error: add_error.clone()},
+            ContactBackups { account: account.clone() },
         }
     ))
 }
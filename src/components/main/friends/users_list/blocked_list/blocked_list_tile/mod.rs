@@ -51,7 +51,6 @@ pub fn BlockedListTile(cx: Scope<Props>) -> Element {
                     ActivityIndicator {
                         inline: true,
                         remote_did: cx.props.friend.clone(),
-                        account: cx.props.account.clone(),
                     }
                 )}
             },
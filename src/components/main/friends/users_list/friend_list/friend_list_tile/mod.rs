@@ -11,7 +11,10 @@ use warp::crypto::DID;
 
 use crate::{
     components::main::friends::users_list::friend_list::friend_list_tile::more_menu::MoreMenu,
-    iutils, state::Actions, Messaging, STATE,
+    components::reusable::verification::{KeyChangeWarning, VerifiedBadge, VerifyIdentity},
+    iutils,
+    state::{groups::groups_of, verification, Actions},
+    Messaging, STATE,
 };
 use utils::Account;
 
@@ -31,12 +34,29 @@ pub fn FriendListTile<'a>(cx: Scope<'a, Props>) -> Element<'a> {
     let mp = cx.props.account.clone();
     let mut rg = cx.props.messaging.clone();
 
-    let username = cx.props.friend_username.clone();
-    let show_skeleton = username.is_empty();
+    let show_skeleton = cx.props.friend_username.is_empty();
 
     let profile_picture = iutils::get_pfp_from_did(cx.props.friend.clone(), &mp);
     let state = use_atom_ref(&cx, STATE);
 
+    // the nickname is shown in place of the username, which appears on hover
+    let did_string = cx.props.friend.to_string();
+    let contact = state.read().contact(&did_string);
+    let username = iutils::get_identity_username(cx.props.friend.clone(), &mp);
+    let display_name = contact.nickname.clone().unwrap_or_else(|| username.clone());
+    let notes = contact.notes.clone();
+    let labels: Vec<(String, String)> = groups_of(&state.read().friend_groups, &did_string)
+        .into_iter()
+        .map(|group| (group.name.clone(), group.color.clone()))
+        .collect();
+    let verified = state.read().is_verified(&did_string);
+    let key_changed =
+        verification::key_change(&state.read().identities, &did_string, &username).is_some();
+    let show_verify = use_state(&cx, || false);
+    let editing = use_state(&cx, || false);
+    let nickname_input = use_state(&cx, String::new);
+    let notes_input = use_state(&cx, String::new);
+
     let friend_id = &cx.props.friend.to_string()[8..];
 
     let show_more_menu_script = include_str!("./show_more_menu.js").replace("friend_id", friend_id);
@@ -58,13 +78,36 @@ pub fn FriendListTile<'a>(cx: Scope<'a, Props>) -> Element<'a> {
                     InlineSkeleton {}
                 )} else {rsx!(
                     h3 {
-                        "{username}"
+                        title: "{username}",
+                        "{display_name}",
+                        verified.then(|| rsx!(VerifiedBadge {}))
                     },
                     ActivityIndicator {
                         inline: true,
                         remote_did: cx.props.friend.clone(),
-                        account: cx.props.account.clone(),
-                    }
+                    },
+                    (!labels.is_empty()).then(|| rsx!(
+                        div {
+                            class: "friend-labels",
+                            labels.iter().map(|(name, color)| rsx!(
+                                span {
+                                    class: "friend-label",
+                                    style: "border-color: {color}; color: {color}",
+                                    "{name}"
+                                }
+                            ))
+                        }
+                    )),
+                    key_changed.then(|| rsx!(
+                        KeyChangeWarning { username: username.clone() }
+                    )),
+                    (!notes.is_empty()).then(|| rsx!(
+                        p {
+                            class: "contact-notes ellipsis",
+                            title: "{notes}",
+                            "{notes}"
+                        }
+                    ))
                 )}
             },
             div {
@@ -97,6 +140,26 @@ pub fn FriendListTile<'a>(cx: Scope<'a, Props>) -> Element<'a> {
                                     cx.props.on_chat.call(());
                                 }
                         },
+                        Button {
+                            icon: if key_changed { Shape::ShieldExclamation } else { Shape::ShieldCheck },
+                            state: ui_kit::button::State::Secondary,
+                            text: "Verify Identity".to_string(),
+                            hide_text: true,
+                            on_pressed: move |_| show_verify.set(true),
+                        },
+                        Button {
+                            icon: Shape::PencilSquare,
+                            state: ui_kit::button::State::Secondary,
+                            text: "Nickname & Notes".to_string(),
+                            hide_text: true,
+                            on_pressed: move |_| {
+                                if !**editing {
+                                    nickname_input.set(contact.nickname.clone().unwrap_or_default());
+                                    notes_input.set(contact.notes.clone());
+                                }
+                                editing.set(!**editing);
+                            }
+                        },
                         div{
                             div{
                                 id:"{friend_id}-more-button",
@@ -122,7 +185,52 @@ pub fn FriendListTile<'a>(cx: Scope<'a, Props>) -> Element<'a> {
                         }
                     )}
                 }
-            }
+            },
+            VerifyIdentity {
+                account: cx.props.account.clone(),
+                did: cx.props.friend.clone(),
+                is_visible: show_verify.clone(),
+            },
+            editing.then(|| rsx!(
+                div {
+                    class: "contact-editor",
+                    input {
+                        class: "contact-input",
+                        value: "{nickname_input}",
+                        placeholder: "Nickname for {username}",
+                        oninput: move |e| nickname_input.set(e.value.clone()),
+                    },
+                    textarea {
+                        class: "contact-input",
+                        value: "{notes_input}",
+                        placeholder: "Notes, only visible to you",
+                        oninput: move |e| notes_input.set(e.value.clone()),
+                    },
+                    div {
+                        class: "contact-editor-controls",
+                        Button {
+                            text: "Cancel".to_string(),
+                            state: ui_kit::button::State::Secondary,
+                            on_pressed: move |_| editing.set(false),
+                        },
+                        Button {
+                            text: "Save".to_string(),
+                            on_pressed: move |_| {
+                                let mut state = state.write();
+                                state.dispatch(Actions::SetNickname(
+                                    did_string.clone(),
+                                    Some(nickname_input.get().clone()),
+                                ));
+                                state.dispatch(Actions::SetContactNotes(
+                                    did_string.clone(),
+                                    notes_input.trim().to_string(),
+                                ));
+                                editing.set(false);
+                            },
+                        },
+                    }
+                }
+            ))
         }
     })
 }
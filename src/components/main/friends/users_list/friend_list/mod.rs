@@ -1,16 +1,26 @@
-use dioxus::prelude::*;
+use dioxus::{fermi::UseAtomRef, prelude::*, router::use_router};
+use dioxus_heroicons::{outline::Shape, Icon};
 use futures::StreamExt;
+use rfd::FileDialog;
+use ui_kit::button::{self, Button};
 use utils::Account;
-use warp::multipass::MultiPassEventKind;
+use uuid::Uuid;
+use warp::{crypto::DID, multipass::MultiPassEventKind};
 mod friend_list_tile;
 
 use std::collections::HashSet;
 
 use crate::{
-    components::main::friends::users_list::{
-        friend_list::friend_list_tile::FriendListTile, get_alpha, order_friend_list,
+    components::main::friends::{
+        users_list::{friend_list::friend_list_tile::FriendListTile, get_alpha, order_friend_list},
+        UsernameAndDID,
     },
-    Messaging,
+    iutils,
+    state::{
+        groups::{self, FriendGroup},
+        Actions, PersistedState,
+    },
+    Messaging, STATE,
 };
 
 #[derive(Props, PartialEq)]
@@ -24,11 +34,33 @@ pub fn FriendList(cx: Scope<FriendListProps>) -> Element {
     let disp_friends = use_state(&cx, Vec::new);
     let friends = use_ref(&cx, HashSet::new);
     let alpha = get_alpha();
+    let state = use_atom_ref(&cx, STATE);
+    let router = use_router(&cx);
+    // only the friends in this group are shown
+    let group_filter: &UseState<Option<Uuid>> = use_state(&cx, || None);
+    let show_new_group = use_state(&cx, || false);
+    let new_group_name = use_state(&cx, String::new);
+    let group_error = use_state(&cx, String::new);
+    // the friend being dragged and the group section it was dragged out of
+    let dragging: &UseRef<Option<(DID, Option<Uuid>)>> = use_ref(&cx, || None);
+    // the section under the dragged friend. Some(None) is the list of all friends, which removes the friend from
+    // the group it was dragged out of. the drop is handled when the drag ends, like files dropped on folders
+    let drop_target: &UseRef<Option<Option<Uuid>>> = use_ref(&cx, || None);
+
+    let friend_groups = state.read().friend_groups.clone();
+    let group_filter_value = (**group_filter)
+        .filter(|id| friend_groups.iter().any(|group| group.id == *id));
+    let all_friends: Vec<UsernameAndDID> = disp_friends
+        .iter()
+        .flat_map(|list| list.friends.iter().cloned())
+        .collect();
 
+    let identities_state = state.clone();
     use_future(
         &cx,
         (friends, &cx.props.account.clone(), disp_friends),
         |(friends, mut mp, disp_friends)| async move {
+            let state = identities_state;
             let mut stream = loop {
                 match mp.subscribe().await {
                     Ok(stream) => break stream,
@@ -46,6 +78,7 @@ pub fn FriendList(cx: Scope<FriendListProps>) -> Element {
 
             let friends_list: HashSet<_> =
                 HashSet::from_iter(mp.list_friends().await.unwrap_or_default());
+            record_identities(&state, friends_list.iter(), &mp);
 
             if *friends.read() != friends_list {
                 log::debug!("updating friends list ");
@@ -59,6 +92,7 @@ pub fn FriendList(cx: Scope<FriendListProps>) -> Element {
             while let Some(event) = stream.next().await {
                 match event {
                     MultiPassEventKind::FriendAdded { did } => {
+                        record_identities(&state, [&did].into_iter(), &mp);
                         friends.write_silent().insert(did);
                         if let Some(new_disp) = order_friend_list(&friends.read(), &mp) {
                             disp_friends.set(new_disp);
@@ -76,9 +110,271 @@ pub fn FriendList(cx: Scope<FriendListProps>) -> Element {
         },
     );
 
+    let create_group = move || {
+        let name = new_group_name.trim().to_string();
+        if name.is_empty() {
+            return;
+        }
+        let mut state = state.write();
+        if state
+            .friend_groups
+            .iter()
+            .any(|group| group.name.to_lowercase() == name.to_lowercase())
+        {
+            group_error.set(format!("A group named {} already exists", name));
+            return;
+        }
+        let color = groups::default_color(&state.friend_groups);
+        state.dispatch(Actions::CreateFriendGroup(FriendGroup::new(&name, &color)));
+        group_error.set(String::new());
+        new_group_name.set(String::new());
+        show_new_group.set(false);
+    };
+
+    let export_groups = move |_| {
+        let path = match FileDialog::new()
+            .set_file_name("uplink-friend-groups.json")
+            .add_filter("JSON", &["json"])
+            .save_file()
+        {
+            Some(path) => path,
+            None => return,
+        };
+        let result = groups::export(&state.read().friend_groups)
+            .map_err(|e| e.to_string())
+            .and_then(|json| std::fs::write(&path, json).map_err(|e| e.to_string()));
+        match result {
+            Ok(_) => group_error.set(String::new()),
+            Err(e) => {
+                log::error!("failed to export friend groups: {}", e);
+                group_error.set(String::from("Failed to export the groups"));
+            }
+        }
+    };
+
+    let import_groups = move |_| {
+        let path = match FileDialog::new().add_filter("JSON", &["json"]).pick_file() {
+            Some(path) => path,
+            None => return,
+        };
+        let result = std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|json| groups::import(&json).map_err(|e| e.to_string()));
+        match result {
+            Ok(imported) => {
+                state.write().dispatch(Actions::ImportFriendGroups(imported));
+                group_error.set(String::new());
+            }
+            Err(e) => {
+                log::error!("failed to import friend groups: {}", e);
+                group_error.set(String::from("The file doesn't contain friend groups"));
+            }
+        }
+    };
+
+    // moves the dragged friend into the section it was released over
+    let finish_drag = move || {
+        let dragged = dragging.write_silent().take();
+        let target = drop_target.write_silent().take();
+        if let (Some((did, from)), Some(to)) = (dragged, target) {
+            if from == to {
+                return;
+            }
+            let did = did.to_string();
+            let mut state = state.write();
+            if let Some(from) = from {
+                state.dispatch(Actions::RemoveFromFriendGroup(from, did.clone()));
+            }
+            if let Some(to) = to {
+                state.dispatch(Actions::AddToFriendGroup(to, did));
+            }
+        }
+    };
+
+    let friend_tile = move |user: UsernameAndDID, source: Option<Uuid>| {
+        let did = user.did.clone();
+        rsx!(
+            div {
+                class: "friend-draggable",
+                draggable: "true",
+                ondragstart: move |_| *dragging.write_silent() = Some((did.clone(), source)),
+                ondragend: move |_| finish_drag(),
+                FriendListTile {
+                    account: cx.props.account.clone(),
+                    messaging: cx.props.messaging.clone(),
+                    friend: user.did.clone(),
+                    friend_username: user.username.clone(),
+                    on_chat: move |_| {
+                        router.push_route("/main", None, None);
+                    }
+                }
+            }
+        )
+    };
+
+    let group_sections = friend_groups
+        .iter()
+        .filter(|group| group_filter_value.map_or(true, |id| id == group.id))
+        .map(|group| {
+            let id = group.id;
+            let name = group.name.clone();
+            let color = group.color.clone();
+            let next_color = group.next_color();
+            // a filtered group is always expanded
+            let collapsed = group.collapsed && group_filter_value.is_none();
+            let members: Vec<UsernameAndDID> = all_friends
+                .iter()
+                .filter(|user| group.contains(&user.did.to_string()))
+                .cloned()
+                .collect();
+            let count = members.len();
+            rsx!(
+                div {
+                    key: "{id}",
+                    class: "friend-group",
+                    div {
+                        class: "friend-group-header",
+                        prevent_default: "ondragover",
+                        ondragover: move |_| *drop_target.write_silent() = Some(Some(id)),
+                        ondragleave: move |_| *drop_target.write_silent() = None,
+                        span {
+                            class: "friend-group-color",
+                            style: "background: {color}",
+                            title: "Change color",
+                            onclick: move |_| state.write().dispatch(Actions::SetFriendGroupColor(id, next_color.clone())),
+                        },
+                        div {
+                            class: "friend-group-toggle",
+                            onclick: move |_| state.write().dispatch(Actions::ToggleFriendGroupCollapsed(id)),
+                            Icon {
+                                icon: if collapsed { Shape::ChevronRight } else { Shape::ChevronDown },
+                            },
+                            h5 {
+                                "{name}"
+                            },
+                            span {
+                                class: "friend-group-count",
+                                "{count}"
+                            },
+                        },
+                        Button {
+                            icon: Shape::Trash,
+                            state: button::State::Secondary,
+                            text: "Delete Group".to_string(),
+                            hide_text: true,
+                            on_pressed: move |_| {
+                                if group_filter_value == Some(id) {
+                                    group_filter.set(None);
+                                }
+                                state.write().dispatch(Actions::DeleteFriendGroup(id));
+                            },
+                        },
+                    },
+                    (!collapsed).then(|| rsx!(
+                        (members.is_empty()).then(|| rsx!(
+                            p {
+                                class: "friend-group-empty",
+                                "Drag friends here to add them to {name}"
+                            }
+                        )),
+                        members.into_iter().map(|user| friend_tile(user, Some(id)))
+                    ))
+                }
+            )
+        });
+
+    let all_active = if group_filter_value.is_none() { "active" } else { "" };
     let friend_list = rsx!(
     div {
                     class: "users-list",
+                    div {
+                        class: "friend-groups-bar",
+                        div {
+                            class: "friend-group-chip {all_active}",
+                            onclick: move |_| group_filter.set(None),
+                            "All"
+                        },
+                        friend_groups.iter().map(|group| {
+                            let id = group.id;
+                            let name = group.name.clone();
+                            let color = group.color.clone();
+                            let active = if group_filter_value == Some(id) { "active" } else { "" };
+                            rsx!(
+                                div {
+                                    key: "{id}",
+                                    class: "friend-group-chip {active}",
+                                    onclick: move |_| group_filter.set(Some(id)),
+                                    span {
+                                        class: "friend-group-color",
+                                        style: "background: {color}",
+                                    },
+                                    "{name}"
+                                }
+                            )
+                        }),
+                        Button {
+                            icon: Shape::Plus,
+                            state: button::State::Secondary,
+                            text: "New Group".to_string(),
+                            hide_text: true,
+                            on_pressed: move |_| show_new_group.set(!**show_new_group),
+                        },
+                        Button {
+                            icon: Shape::ArrowUpTray,
+                            state: button::State::Secondary,
+                            text: "Export Groups".to_string(),
+                            hide_text: true,
+                            disabled: friend_groups.is_empty(),
+                            on_pressed: export_groups,
+                        },
+                        Button {
+                            icon: Shape::ArrowDownTray,
+                            state: button::State::Secondary,
+                            text: "Import Groups".to_string(),
+                            hide_text: true,
+                            on_pressed: import_groups,
+                        },
+                    },
+                    show_new_group.then(|| rsx!(
+                        div {
+                            class: "friend-group-new",
+                            input {
+                                class: "friend-group-input",
+                                value: "{new_group_name}",
+                                placeholder: "Group name, like Team or Family",
+                                oninput: move |e| new_group_name.set(e.value.clone()),
+                                onkeyup: move |e| {
+                                    if e.key == "Enter" {
+                                        create_group();
+                                    }
+                                },
+                            },
+                            Button {
+                                text: "Create".to_string(),
+                                on_pressed: move |_| create_group(),
+                            },
+                        }
+                    )),
+                    (!group_error.is_empty()).then(|| rsx!(
+                        p {
+                            class: "friend-group-error",
+                            "{group_error}"
+                        }
+                    )),
+                    group_sections,
+                    group_filter_value.is_none().then(|| rsx!(
+                        (!friend_groups.is_empty()).then(|| rsx!(
+                            div {
+                                class: "friend-group-header",
+                                prevent_default: "ondragover",
+                                ondragover: move |_| *drop_target.write_silent() = Some(None),
+                                ondragleave: move |_| *drop_target.write_silent() = None,
+                                h5 {
+                                    title: "Drop a friend here to remove them from the group",
+                                    "All Friends"
+                                }
+                            }
+                        )),
                       disp_friends.iter().map(|friends_per_char_list| {
                         let first_username_char = friends_per_char_list.letter;
                         rsx!(
@@ -89,24 +385,10 @@ pub fn FriendList(cx: Scope<FriendListProps>) -> Element {
                                     "{first_username_char}"
                                 }
                             }
-                            friends_per_char_list.friends.iter().map(|user| {
-                                    rsx!(
-                                        FriendListTile {
-                                            account: cx.props.account.clone(),
-                                            messaging: cx.props.messaging.clone(),
-                                            friend: user.did.clone(),
-                                            friend_username: user.username.clone(),
-                                            on_chat: move |_| {
-                                                use_router(&cx).push_route("/main", None, None);
-                                            }
-                                        }
-                                    )
-
-
-
-                            }),
+                            friends_per_char_list.friends.iter().map(|user| friend_tile(user.clone(), None)),
                         )
                     }),
+                    ))
                 },
                 ul {
                     class: "a-z-list",
@@ -119,3 +401,20 @@ pub fn FriendList(cx: Scope<FriendListProps>) -> Element {
 
     cx.render(friend_list)
 }
+
+// remembers the username of each friend, so that a new DID using the same username can be detected
+fn record_identities<'a>(
+    state: &UseAtomRef<PersistedState>,
+    dids: impl Iterator<Item = &'a DID>,
+    mp: &Account,
+) {
+    for did in dids {
+        let did_string = did.to_string();
+        let username = iutils::get_identity_username(did.clone(), mp);
+        if state.read().needs_identity_update(&did_string, &username) {
+            state
+                .write()
+                .dispatch(Actions::RecordIdentity(did_string, username));
+        }
+    }
+}
@@ -1,14 +1,18 @@
 use crate::{
     main::{compose::Compose, sidebar::Sidebar, welcome::Welcome},
-    state::Actions,
+    state::{
+        retention::{ControlMessage, Retention},
+        Actions,
+    },
     Account, Messaging, STATE,
 };
 
+use chrono::{DateTime, Utc};
 use dioxus::prelude::*;
 use futures::StreamExt;
 use std::collections::HashMap;
 use uuid::Uuid;
-use warp::raygun::{Conversation, RayGunEventKind};
+use warp::raygun::{Conversation, MessageOptions, RayGunEventKind};
 
 pub mod compose;
 pub mod files;
@@ -27,6 +31,7 @@ pub struct Prop {
 pub fn Main(cx: Scope<Prop>) -> Element {
     log::debug!("rendering Main");
     let state = use_atom_ref(&cx, STATE).clone();
+    let state2 = state.clone();
     let rg = cx.props.messaging.clone();
     let mp = cx.props.account.clone();
     let display_welcome = state.read().selected_chat.is_none();
@@ -34,6 +39,14 @@ pub fn Main(cx: Scope<Prop>) -> Element {
         false => "main-sidebar",
         true => "main-chat",
     };
+    let total_unreads = state.read().total_unreads;
+
+    // keep the dock/launcher badge in sync with the unread count
+    use_effect(&cx, &total_unreads, |count| async move {
+        if let Err(e) = ::utils::notifications::set_badge(count).await {
+            log::error!("failed to update the unread badge: {}", e);
+        }
+    });
 
     use_future(&cx, (&rg, &mp), |(mut rg, _mp)| async move {
         log::debug!("streaming conversations");
@@ -110,6 +123,77 @@ pub fn Main(cx: Scope<Prop>) -> Element {
         }
     });
 
+    // delete expired messages. this runs for every conversation, not just the open one, so that messages
+    // received while the conversation isn't displayed also disappear on time.
+    use_future(&cx, &rg, |mut rg| async move {
+        // a conversation is only fetched again once its oldest message can have expired. the retention is kept
+        // with the time, since changing it moves the time
+        let mut next_sweep: HashMap<Uuid, (Retention, DateTime<Utc>)> = HashMap::new();
+        loop {
+            let conversations: Vec<(Uuid, Retention)> = state2
+                .read()
+                .all_chats
+                .values()
+                .filter(|info| info.retention != Retention::Off)
+                .map(|info| (info.conversation.id(), info.retention))
+                .collect();
+
+            let now = Utc::now();
+            next_sweep.retain(|id, _| conversations.iter().any(|(other, _)| other == id));
+            for (conversation_id, retention) in conversations {
+                let waiting = next_sweep
+                    .get(&conversation_id)
+                    .map_or(false, |(swept_with, next)| {
+                        *swept_with == retention && *next > now
+                    });
+                if waiting {
+                    continue;
+                }
+                let messages = match rg
+                    .get_messages(conversation_id, MessageOptions::default())
+                    .await
+                {
+                    Ok(messages) => messages,
+                    Err(e) => {
+                        log::error!("failed to get messages for retention sweep: {}", e);
+                        continue;
+                    }
+                };
+
+                // opening a conversation reads the retention from its latest control message, so those are kept
+                let mut kept = vec![];
+                let mut failed = false;
+                for message in messages
+                    .iter()
+                    .filter(|message| !ControlMessage::is_control_message(&message.value()))
+                {
+                    if !retention.is_expired(message.date()) {
+                        kept.push(message.date());
+                        continue;
+                    }
+                    log::debug!("deleting expired message");
+                    if let Err(e) = rg.delete(conversation_id, Some(message.id())).await {
+                        log::error!("failed to delete expired message: {}", e);
+                        failed = true;
+                        continue;
+                    }
+                    state2
+                        .write()
+                        .dispatch(Actions::RemoveMessageActivity(message.id()));
+                }
+
+                // the messages which couldn't be deleted are tried again on the next round
+                if failed {
+                    next_sweep.remove(&conversation_id);
+                } else if let Some(next) = retention.next_expiry(kept, now) {
+                    next_sweep.insert(conversation_id, (retention, next));
+                }
+            }
+
+            tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+        }
+    });
+
     cx.render(rsx! {
         div {
             class: "main {sidebar_visibility}",
@@ -5,7 +5,7 @@ use ui_kit::button::Button;
 use crate::{
     components::main::settings::pages::{
         audio_video::AudioVideo, developer::Developer, extensions::Extensions, general::General,
-        profile::Profile,
+        keybinds::Keybinds, notifications::Notifications, profile::Profile,
     },
     components::reusable::page_header,
     state::Actions,
@@ -28,11 +28,7 @@ pub struct Props {
 pub fn Settings(cx: Scope<Props>) -> Element {
     let state = use_atom_ref(&cx, STATE).clone();
     log::debug!("rendering Settings");
-    let page_to_open_on_settings = match cx.props.page_to_open {
-        Route::Profile => Route::Profile,
-        Route::Developer => Route::Developer,
-        _ => Route::General,
-    };
+    let page_to_open_on_settings = cx.props.page_to_open;
 
     let sidebar_visibility = match state.read().hide_sidebar {
         false => "mobile-sidebar-visible",
@@ -46,6 +42,8 @@ pub fn Settings(cx: Scope<Props>) -> Element {
         Route::Privacy => "Privacy",
         Route::AudioVideo => "Audio Video",
         Route::Extensions => "Extensions",
+        Route::Keybinds => "Keybinds",
+        Route::Notifications => "Notifications",
         Route::Developer => "Developer",
         _ => "General",
     };
@@ -61,11 +59,7 @@ pub fn Settings(cx: Scope<Props>) -> Element {
                     active_page.set(ne);
                     state.write().dispatch(Actions::HideSidebar(true));
                 },
-                initial_value: match active_page.get() {
-                    Route::Profile => Route::Profile,
-                    Route::Developer => Route::Developer,
-                    _ => Route::General,
-                },
+                initial_value: *active_page.get(),
             },
             div {
                 id: "content",
@@ -100,6 +94,8 @@ pub fn Settings(cx: Scope<Props>) -> Element {
                                 Route::Profile => rsx!(Profile { account: cx.props.account.clone() }),
                                 Route::Extensions => rsx!(Extensions {}),
                                 Route::AudioVideo => rsx!(AudioVideo {}),
+                                Route::Keybinds => rsx!(Keybinds {}),
+                                Route::Notifications => rsx!(Notifications { account: cx.props.account.clone() }),
                                 _ => rsx!(Developer { account: cx.props.account.clone() }),
                             }
                         }
@@ -1,12 +1,41 @@
 use dioxus::prelude::*;
 
 use crate::iutils::config::Config;
-use ui_kit::switch::Switch;
+use ui_kit::{select::*, switch::Switch};
+use utils::sounds::{self, SoundSettings, Sounds};
+
+// the value of the pack select which stands for the bundled sounds
+const DEFAULT_PACK: &str = "";
 
 #[allow(non_snake_case)]
 pub fn AudioVideo(cx: Scope) -> Element {
     log::debug!("rendering settings/pages/AudioVideo");
     let mut config = Config::load_config_or_default();
+    let sound_settings = use_state(&cx, || config.sounds.clone());
+    let volume = (sound_settings.volume * 100.0).round() as u32;
+
+    let save_sounds = move |new_settings: SoundSettings| {
+        let mut config = Config::load_config_or_default();
+        config.sounds = new_settings.clone();
+        if let Err(e) = config.save() {
+            log::error!("failed to save sound settings: {}", e);
+        }
+        sounds::configure(new_settings.clone());
+        sound_settings.set(new_settings);
+    };
+
+    let mut pack_options = vec![SelectOption {
+        value: String::from(DEFAULT_PACK),
+        label: String::from("Default"),
+    }];
+    pack_options.extend(sounds::list_packs().into_iter().map(|pack| SelectOption {
+        value: pack.clone(),
+        label: pack,
+    }));
+    let current_pack = sound_settings
+        .pack
+        .clone()
+        .unwrap_or_else(|| String::from(DEFAULT_PACK));
 
     cx.render(rsx! {
         div {
@@ -33,7 +62,90 @@ pub fn AudioVideo(cx: Scope) -> Element {
                         }
                     }
                 }
-            }
+            },
+            div {
+                class: "item",
+                div {
+                    class: "description",
+                    label {
+                        "Volume"
+                    },
+                    p {
+                        "The volume of notification sounds."
+                    }
+                },
+                div {
+                    class: "interactive",
+                    input {
+                        "type": "range",
+                        min: "0",
+                        max: "100",
+                        value: "{volume}",
+                        onchange: move |e| {
+                            if let Ok(volume) = e.value.parse::<f32>() {
+                                let mut new_settings = sound_settings.get().clone();
+                                new_settings.volume = (volume / 100.0).clamp(0.0, 1.0);
+                                save_sounds(new_settings);
+                                sounds::Play(Sounds::Notification);
+                            }
+                        },
+                    }
+                }
+            },
+            div {
+                class: "item",
+                div {
+                    class: "description",
+                    label {
+                        "Sound Pack"
+                    },
+                    p {
+                        "Packs are folders in the sounds directory of your data folder containing notification, friend_request and general audio files. Missing sounds use the defaults."
+                    }
+                },
+                div {
+                    class: "interactive",
+                    Select {
+                        value: current_pack,
+                        options: pack_options,
+                        on_change: move |value: String| {
+                            let mut new_settings = sound_settings.get().clone();
+                            new_settings.pack = (value != DEFAULT_PACK).then(|| value);
+                            save_sounds(new_settings);
+                        },
+                    }
+                }
+            },
+            Sounds::all().into_iter().map(|sound| {
+                let label = sound.label();
+                let enabled = sound_settings.is_enabled(sound);
+                rsx!(
+                    div {
+                        key: "{label}",
+                        class: "item",
+                        div {
+                            class: "description",
+                            label {
+                                "{label} Sound"
+                            },
+                        },
+                        div {
+                            class: "interactive",
+                            Switch {
+                                active: enabled,
+                                on_change: move |_| {
+                                    let mut new_settings = sound_settings.get().clone();
+                                    new_settings.set_enabled(sound, !enabled);
+                                    save_sounds(new_settings);
+                                    if !enabled {
+                                        sounds::Play(sound);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                )
+            })
         },
     })
 }
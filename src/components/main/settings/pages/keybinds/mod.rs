@@ -0,0 +1,128 @@
+use dioxus::{events::KeyboardEvent, prelude::*};
+use dioxus_heroicons::outline::Shape;
+use ui_kit::button::{Button, State};
+
+use crate::{
+    iutils::{
+        config::Config,
+        shortcuts::{KeyBinding, Keybinds as KeybindsConfig, ShortcutAction},
+    },
+    CONFIG,
+};
+
+#[allow(non_snake_case)]
+pub fn Keybinds(cx: Scope) -> Element {
+    log::debug!("rendering settings/pages/Keybinds");
+    let config_atom = use_atom_ref(&cx, CONFIG);
+    let keybinds = use_state(&cx, || config_atom.read().keybinds.clone());
+    // the action whose binding is being recorded
+    let recording: &UseState<Option<ShortcutAction>> = use_state(&cx, || None);
+    let error = use_state(&cx, String::new);
+
+    let save = move |new_keybinds: KeybindsConfig| {
+        let mut config = Config::load_config_or_default();
+        config.keybinds = new_keybinds.clone();
+        if let Err(e) = config.save() {
+            log::error!("failed to save keybinds: {}", e);
+        }
+        *config_atom.write() = config;
+        keybinds.set(new_keybinds);
+    };
+
+    cx.render(rsx! {
+        div {
+            id: "page_keybinds",
+            class: "padded",
+            (!error.is_empty()).then(|| rsx!(
+                p {
+                    class: "error_text",
+                    "{error}"
+                }
+            )),
+            ShortcutAction::all().into_iter().map(|action| {
+                let is_recording = **recording == Some(action);
+                let value = if is_recording {
+                    String::from("Press a key combination...")
+                } else {
+                    keybinds.get().get(action).to_string()
+                };
+                let recording_class = if is_recording { "recording" } else { "" };
+                let label = action.label();
+                rsx!(
+                    div {
+                        key: "{label}",
+                        class: "item",
+                        div {
+                            class: "description",
+                            label {
+                                "{label}"
+                            },
+                        },
+                        div {
+                            class: "interactive",
+                            input {
+                                class: "keybind-input {recording_class}",
+                                readonly: "true",
+                                value: "{value}",
+                                onclick: move |_| {
+                                    error.set(String::new());
+                                    recording.set(Some(action));
+                                },
+                                onblur: move |_| {
+                                    if **recording == Some(action) {
+                                        recording.set(None);
+                                    }
+                                },
+                                onkeydown: move |e: KeyboardEvent| {
+                                    // the global shortcut handler must not see the keys being recorded
+                                    e.cancel_bubble();
+                                    if **recording != Some(action) {
+                                        return;
+                                    }
+                                    let binding = match KeyBinding::from_key_event(e.ctrl_key, e.alt_key, e.shift_key, e.meta_key, &e.key) {
+                                        Some(binding) => binding,
+                                        None => return,
+                                    };
+                                    let mut new_keybinds = keybinds.get().clone();
+                                    match new_keybinds.set(action, binding) {
+                                        Ok(_) => {
+                                            error.set(String::new());
+                                            save(new_keybinds);
+                                        }
+                                        Err(msg) => error.set(msg),
+                                    }
+                                    recording.set(None);
+                                },
+                            }
+                        }
+                    }
+                )
+            }),
+            div {
+                class: "item",
+                div {
+                    class: "description",
+                    label {
+                        "Reset Keybinds"
+                    },
+                    p {
+                        "Restore every shortcut to its default key combination."
+                    }
+                },
+                div {
+                    class: "interactive",
+                    Button {
+                        text: String::from("Reset to defaults"),
+                        icon: Shape::ArrowPath,
+                        state: State::Secondary,
+                        on_pressed: move |_| {
+                            error.set(String::new());
+                            recording.set(None);
+                            save(KeybindsConfig::default());
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
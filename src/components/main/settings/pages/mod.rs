@@ -2,4 +2,6 @@ pub mod audio_video;
 pub mod developer;
 pub mod extensions;
 pub mod general;
+pub mod keybinds;
+pub mod notifications;
 pub mod profile;
@@ -0,0 +1,450 @@
+use dioxus::prelude::*;
+use ui_kit::{select::*, switch::Switch};
+use uuid::Uuid;
+
+use crate::{
+    iutils::{
+        config::Config,
+        get_username_from_conversation,
+        notification_queue::NotificationPrivacy,
+        notification_rules::{parse_time, ConversationRule, NotificationRules},
+        request_filter::RequestFilterRules,
+    },
+    state::presence::Presence,
+    Account, CONFIG, STATE,
+};
+
+const DAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+#[derive(Props, PartialEq)]
+pub struct Props {
+    account: Account,
+}
+
+#[allow(non_snake_case)]
+pub fn Notifications(cx: Scope<Props>) -> Element {
+    log::debug!("rendering settings/pages/Notifications");
+    let state = use_atom_ref(&cx, STATE);
+    let config_atom = use_atom_ref(&cx, CONFIG);
+    let rules = use_state(&cx, || Config::load_config_or_default().notifications);
+    let keywords = use_state(&cx, || rules.keywords.join(", "));
+
+    let save = move |new_rules: NotificationRules| {
+        let mut config = Config::load_config_or_default();
+        config.notifications = new_rules.clone();
+        if let Err(e) = config.save() {
+            log::error!("failed to save notification rules: {}", e);
+        }
+        *config_atom.write() = config;
+        rules.set(new_rules);
+    };
+    let update = move |f: &dyn Fn(&mut NotificationRules)| {
+        let mut new_rules = rules.get().clone();
+        f(&mut new_rules);
+        save(new_rules);
+    };
+
+    let filters = use_state(&cx, || Config::load_config_or_default().friend_requests);
+    let blocked_words = use_state(&cx, || filters.keywords.join(", "));
+    let update_filters = move |f: &dyn Fn(&mut RequestFilterRules)| {
+        let mut new_filters = filters.get().clone();
+        f(&mut new_filters);
+        let mut config = Config::load_config_or_default();
+        config.friend_requests = new_filters.clone();
+        if let Err(e) = config.save() {
+            log::error!("failed to save friend request filters: {}", e);
+        }
+        *config_atom.write() = config;
+        filters.set(new_filters);
+    };
+
+    let quiet_hours = rules.quiet_hours.clone();
+    let coalesce_seconds = rules.coalesce_seconds;
+    let max_per_minute = rules.max_per_minute;
+    let mut chats: Vec<_> = state.read().active_chats.values().cloned().collect();
+    chats.sort();
+    let conversations: Vec<(Uuid, String)> = chats
+        .iter()
+        .map(|info| {
+            let (_, name) = get_username_from_conversation(info, &cx.props.account);
+            (info.conversation.id(), name)
+        })
+        .collect();
+    let rule_options: Vec<SelectOption> = ConversationRule::all()
+        .into_iter()
+        .map(|rule| SelectOption {
+            value: format!("{:?}", rule),
+            label: rule.label(),
+        })
+        .collect();
+
+    let privacy_options: Vec<SelectOption> = NotificationPrivacy::all()
+        .into_iter()
+        .map(|privacy| SelectOption {
+            value: format!("{:?}", privacy),
+            label: privacy.label(),
+        })
+        .collect();
+
+    cx.render(rsx! {
+        div {
+            id: "page_notifications",
+            class: "padded",
+            div {
+                class: "item",
+                div {
+                    class: "description",
+                    label {
+                        "Do Not Disturb"
+                    },
+                    p {
+                        "Silence all notifications except mentions and keyword alerts."
+                    }
+                },
+                div {
+                    class: "interactive",
+                    Switch {
+                        active: rules.do_not_disturb,
+                        on_change: move |_| update(&|r| r.do_not_disturb = !r.do_not_disturb),
+                    }
+                }
+            },
+            div {
+                class: "item",
+                div {
+                    class: "description",
+                    label {
+                        "Always Notify Mentions"
+                    },
+                    p {
+                        "Mentions of your username notify you during Do Not Disturb, quiet hours and while busy."
+                    }
+                },
+                div {
+                    class: "interactive",
+                    Switch {
+                        active: rules.mentions_always_notify,
+                        on_change: move |_| update(&|r| r.mentions_always_notify = !r.mentions_always_notify),
+                    }
+                }
+            },
+            div {
+                class: "item",
+                div {
+                    class: "description",
+                    label {
+                        "Notification Content"
+                    },
+                    p {
+                        "Choose what notifications reveal. Messages from the same chat arriving together are grouped into one notification."
+                    }
+                },
+                div {
+                    class: "interactive",
+                    Select {
+                        value: format!("{:?}", rules.privacy),
+                        options: privacy_options,
+                        on_change: move |value: String| {
+                            let privacy = NotificationPrivacy::all()
+                                .into_iter()
+                                .find(|privacy| format!("{:?}", privacy) == value)
+                                .unwrap_or_default();
+                            update(&|r| r.privacy = privacy);
+                        },
+                    }
+                }
+            },
+            div {
+                class: "item",
+                div {
+                    class: "description",
+                    label {
+                        "Grouping Window"
+                    },
+                    p {
+                        "Messages from a chat arriving within this many seconds of the first one are shown as one notification."
+                    }
+                },
+                div {
+                    class: "interactive",
+                    input {
+                        class: "notification-input",
+                        "type": "number",
+                        min: "0",
+                        value: "{coalesce_seconds}",
+                        onchange: move |e| {
+                            if let Ok(seconds) = e.value.parse::<u64>() {
+                                update(&|r| r.coalesce_seconds = seconds);
+                            }
+                        },
+                    }
+                }
+            },
+            div {
+                class: "item",
+                div {
+                    class: "description",
+                    label {
+                        "Notifications Per Minute"
+                    },
+                    p {
+                        "Notifications over this limit wait, and keep collecting messages, until the next minute."
+                    }
+                },
+                div {
+                    class: "interactive",
+                    input {
+                        class: "notification-input",
+                        "type": "number",
+                        min: "1",
+                        value: "{max_per_minute}",
+                        onchange: move |e| {
+                            if let Ok(max) = e.value.parse::<usize>() {
+                                update(&|r| r.max_per_minute = max.max(1));
+                            }
+                        },
+                    }
+                }
+            },
+            div {
+                class: "item",
+                div {
+                    class: "description",
+                    label {
+                        "Keyword Alerts"
+                    },
+                    p {
+                        "Messages containing one of these comma separated words always notify you, unless the chat is muted."
+                    }
+                },
+                div {
+                    class: "interactive",
+                    input {
+                        class: "notification-input",
+                        value: "{keywords}",
+                        placeholder: "urgent, release",
+                        oninput: move |e| keywords.set(e.value.clone()),
+                        onblur: move |_| {
+                            let words: Vec<String> = keywords
+                                .split(',')
+                                .map(|word| word.trim().to_string())
+                                .filter(|word| !word.is_empty())
+                                .collect();
+                            update(&|r| r.keywords = words.clone());
+                        },
+                    }
+                }
+            },
+            div {
+                class: "item",
+                div {
+                    class: "description",
+                    label {
+                        "Quiet Hours"
+                    },
+                    p {
+                        "Silence notifications on a daily schedule. No days selected means every day."
+                    }
+                },
+                div {
+                    class: "interactive quiet-hours",
+                    Switch {
+                        active: quiet_hours.enabled,
+                        on_change: move |_| update(&|r| r.quiet_hours.enabled = !r.quiet_hours.enabled),
+                    },
+                    input {
+                        class: "notification-input",
+                        "type": "time",
+                        value: "{quiet_hours.start}",
+                        onchange: move |e| {
+                            if parse_time(&e.value).is_some() {
+                                update(&|r| r.quiet_hours.start = e.value.clone());
+                            }
+                        },
+                    },
+                    span { "to" },
+                    input {
+                        class: "notification-input",
+                        "type": "time",
+                        value: "{quiet_hours.end}",
+                        onchange: move |e| {
+                            if parse_time(&e.value).is_some() {
+                                update(&|r| r.quiet_hours.end = e.value.clone());
+                            }
+                        },
+                    },
+                    div {
+                        class: "days",
+                        DAYS.into_iter().map(|day| {
+                            let selected = quiet_hours.days.iter().any(|d| d == day);
+                            let selected_class = if selected { "selected" } else { "" };
+                            rsx!(
+                                span {
+                                    key: "{day}",
+                                    class: "day {selected_class}",
+                                    onclick: move |_| update(&|r| {
+                                        if selected {
+                                            r.quiet_hours.days.retain(|d| d != day);
+                                        } else {
+                                            r.quiet_hours.days.push(day.to_string());
+                                        }
+                                    }),
+                                    "{day}"
+                                }
+                            )
+                        })
+                    }
+                }
+            },
+            Presence::all().into_iter().filter(|presence| *presence != Presence::Online).map(|presence| {
+                let label = presence.label();
+                let active = rules.suppress_while.contains(&presence);
+                rsx!(
+                    div {
+                        key: "{label}",
+                        class: "item",
+                        div {
+                            class: "description",
+                            label {
+                                "Silence While {label}"
+                            },
+                            p {
+                                "Suppress notifications while your status is {label}."
+                            }
+                        },
+                        div {
+                            class: "interactive",
+                            Switch {
+                                active: active,
+                                on_change: move |_| update(&|r| {
+                                    if active {
+                                        r.suppress_while.retain(|p| *p != presence);
+                                    } else {
+                                        r.suppress_while.push(presence);
+                                    }
+                                }),
+                            }
+                        }
+                    }
+                )
+            }),
+            div {
+                class: "item",
+                div {
+                    class: "description",
+                    label {
+                        "Decline Unknown Senders"
+                    },
+                    p {
+                        "Decline friend requests from accounts whose identity can't be found."
+                    }
+                },
+                div {
+                    class: "interactive",
+                    Switch {
+                        active: filters.decline_unknown,
+                        on_change: move |_| update_filters(&|f| f.decline_unknown = !f.decline_unknown),
+                    }
+                }
+            },
+            div {
+                class: "item",
+                div {
+                    class: "description",
+                    label {
+                        "Friend Request Limit"
+                    },
+                    p {
+                        "Decline requests from someone who sent more than this many in a day. 0 turns the limit off."
+                    }
+                },
+                div {
+                    class: "interactive",
+                    input {
+                        class: "notification-input",
+                        "type": "number",
+                        min: "0",
+                        value: "{filters.max_per_sender_per_day}",
+                        onchange: move |e| {
+                            if let Ok(max) = e.value.parse::<u32>() {
+                                update_filters(&|f| f.max_per_sender_per_day = max);
+                            }
+                        },
+                    }
+                }
+            },
+            div {
+                class: "item",
+                div {
+                    class: "description",
+                    label {
+                        "Blocked Words"
+                    },
+                    p {
+                        "Decline friend requests from usernames containing one of these comma separated words."
+                    }
+                },
+                div {
+                    class: "interactive",
+                    input {
+                        class: "notification-input",
+                        value: "{blocked_words}",
+                        placeholder: "crypto, giveaway",
+                        oninput: move |e| blocked_words.set(e.value.clone()),
+                        onblur: move |_| {
+                            let words: Vec<String> = blocked_words
+                                .split(',')
+                                .map(|word| word.trim().to_string())
+                                .filter(|word| !word.is_empty())
+                                .collect();
+                            update_filters(&|f| f.keywords = words.clone());
+                        },
+                    }
+                }
+            },
+            (!conversations.is_empty()).then(|| rsx!(
+                div {
+                    class: "item",
+                    div {
+                        class: "description",
+                        label {
+                            "Chats"
+                        },
+                        p {
+                            "Override the rules above for individual chats."
+                        }
+                    },
+                }
+            )),
+            conversations.into_iter().map(|(id, name)| {
+                let value = format!("{:?}", rules.conversation_rule(id));
+                rsx!(
+                    div {
+                        key: "{id}",
+                        class: "item conversation-rule",
+                        div {
+                            class: "description",
+                            label {
+                                "{name}"
+                            },
+                        },
+                        div {
+                            class: "interactive",
+                            Select {
+                                value: value,
+                                options: rule_options.clone(),
+                                on_change: move |value: String| {
+                                    let rule = ConversationRule::all()
+                                        .into_iter()
+                                        .find(|rule| format!("{:?}", rule) == value)
+                                        .unwrap_or_default();
+                                    update(&|r| r.set_conversation_rule(id, rule));
+                                },
+                            }
+                        }
+                    }
+                )
+            })
+        }
+    })
+}
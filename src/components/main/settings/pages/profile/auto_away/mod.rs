@@ -0,0 +1,44 @@
+use dioxus::prelude::*;
+
+use crate::{iutils::config::Config, CONFIG};
+
+/// how long without input before the presence becomes away
+#[allow(non_snake_case)]
+pub fn AutoAway(cx: Scope) -> Element {
+    let config_atom = use_atom_ref(&cx, CONFIG);
+    let minutes = config_atom.read().presence.auto_away_minutes;
+
+    cx.render(rsx! {
+        div {
+            class: "item",
+            div {
+                class: "description",
+                label {
+                    "Auto Away"
+                },
+                p {
+                    "Show you as away after this many minutes without keyboard or mouse input. 0 turns it off."
+                }
+            },
+            div {
+                class: "interactive",
+                input {
+                    class: "auto-away-input",
+                    "type": "number",
+                    min: "0",
+                    value: "{minutes}",
+                    onchange: move |e| {
+                        if let Ok(minutes) = e.value.parse::<u64>() {
+                            let mut config = Config::load_config_or_default();
+                            config.presence.auto_away_minutes = minutes;
+                            if let Err(e) = config.save() {
+                                log::error!("failed to save the auto away setting: {}", e);
+                            }
+                            *config_atom.write() = config;
+                        }
+                    },
+                }
+            }
+        }
+    })
+}
@@ -1,5 +1,6 @@
 use dioxus::prelude::*;
 
+mod auto_away;
 mod status_msg;
 mod username;
 
@@ -38,6 +39,7 @@ pub fn Profile(cx: Scope<Props>, account: Account) -> Element {
                     account:account.clone(),
                 }
             },
+            auto_away::AutoAway {},
         }
     })
 }
@@ -5,9 +5,10 @@ use ui_kit::{
     input::Input,
 };
 
+use utils::presence;
 use warp::multipass::identity::IdentityUpdate;
 
-use crate::{Account, LANGUAGE};
+use crate::{Account, LANGUAGE, STATE};
 
 #[derive(Props, PartialEq)]
 pub struct Props {
@@ -18,12 +19,14 @@ pub struct Props {
 pub fn StatusMsg(cx: Scope<Props>, account: Account) -> Element {
     let mut account = account.clone();
     let mut account2 = account.clone();
+    let state = use_atom_ref(&cx, STATE);
     let l = use_atom_ref(&cx, LANGUAGE).read();
     let l2 = l.clone();
     let l3 = l.clone();
     let identity = warp::async_block_in_place_uncheck(account.get_own_identity()).unwrap();
+    // the presence tag isn't part of what the user wrote
     let status_msg = match identity.status_message() {
-        Some(msg) => msg,
+        Some(msg) => presence::parse_status(&msg).1.to_string(),
         None => String::new(),
     };
     let status_msg2 = status_msg.clone();
@@ -61,7 +64,7 @@ pub fn StatusMsg(cx: Scope<Props>, account: Account) -> Element {
                                 } else {
                                     if let Err(e) = warp::async_block_in_place_uncheck(account
                                         .update_identity(IdentityUpdate::set_status_message(Some(
-                                            status_msg_state.to_string(),
+                                            presence::tag_status(state.read().presence(), status_msg_state),
                                         ))))
                                     {
                                         println!("Failed in updating status message:{}", e);
@@ -84,7 +87,7 @@ pub fn StatusMsg(cx: Scope<Props>, account: Account) -> Element {
                             } else {
                                 if let Err(e) = warp::async_block_in_place_uncheck(account2
                                     .update_identity(IdentityUpdate::set_status_message(Some(
-                                        status_msg_state.to_string(),
+                                        presence::tag_status(state.read().presence(), status_msg_state),
                                     ))))
                                 {
                                     println!("Failed in updating status message:{}", e);
@@ -2,12 +2,14 @@ use dioxus::prelude::*;
 
 use ui_kit::button::Button;
 
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq, Eq, Clone, Copy)]
 pub enum Route {
     General,
     Privacy,
     AudioVideo,
     Extensions,
+    Keybinds,
+    Notifications,
     Developer,
     Profile,
 }
@@ -49,11 +51,7 @@ pub struct Props<'a> {
 #[allow(non_snake_case)]
 pub fn Nav<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
     log::debug!("rendering settings/sidebar/Nav ");
-    let initial_value = match cx.props.initial_value {
-        Route::Profile => Route::Profile,
-        Route::Developer => Route::Developer,
-        _ => Route::General,
-    };
+    let initial_value = cx.props.initial_value;
     let active_item = use_state(&cx, || initial_value);
 
     cx.render(rsx! {
@@ -104,6 +102,24 @@ pub fn Nav<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
                     cx.props.on_pressed.call(Route::Extensions);
                 }
             },
+            NavButton {
+                text: String::from("Keybinds"),
+                active: Route::Keybinds.eq(active_item),
+                disabled: false,
+                on_pressed: |_| {
+                    active_item.set(Route::Keybinds);
+                    cx.props.on_pressed.call(Route::Keybinds);
+                }
+            },
+            NavButton {
+                text: String::from("Notifications"),
+                active: Route::Notifications.eq(active_item),
+                disabled: false,
+                on_pressed: |_| {
+                    active_item.set(Route::Notifications);
+                    cx.props.on_pressed.call(Route::Notifications);
+                }
+            },
             NavButton {
                 text: String::from("Developer"),
                 active: Route::Developer.eq(active_item),
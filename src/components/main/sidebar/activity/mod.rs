@@ -0,0 +1,126 @@
+use dioxus::prelude::*;
+use dioxus_heroicons::{outline::Shape, Icon};
+use ui_kit::button::{Button, State};
+
+use crate::{
+    iutils,
+    state::{
+        activity::{Activity, ActivityKind},
+        Actions,
+    },
+    STATE,
+};
+
+#[derive(Props)]
+pub struct Props<'a> {
+    on_close: EventHandler<'a, ()>,
+}
+
+fn icon(kind: ActivityKind) -> Shape {
+    match kind {
+        ActivityKind::FriendRequest => Shape::UserPlus,
+        ActivityKind::Mention => Shape::AtSymbol,
+        ActivityKind::MutedMessage => Shape::BellSlash,
+        ActivityKind::Reaction => Shape::FaceSmile,
+        ActivityKind::FileShare => Shape::PaperClip,
+    }
+}
+
+/// the activity feed opened from the bell in the main sidebar
+#[allow(non_snake_case)]
+pub fn ActivityCenter<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
+    log::debug!("rendering main/sidebar/ActivityCenter");
+    let state = use_atom_ref(&cx, STATE);
+    let router = use_router(&cx);
+    let entries: Vec<Activity> = state.read().activity.clone();
+    let has_unread = entries.iter().any(|entry| !entry.read);
+
+    let open = move |entry: &Activity| {
+        state.write().dispatch(Actions::MarkActivityRead(entry.id));
+        match (entry.kind, entry.conversation_id) {
+            (ActivityKind::FriendRequest, _) => {
+                router.replace_route("/main/friends", None, None);
+            }
+            (_, Some(conversation_id)) => {
+                state
+                    .write()
+                    .dispatch(Actions::ShowConversation(conversation_id));
+                router.replace_route("/main", None, None);
+            }
+            _ => {}
+        }
+        cx.props.on_close.call(());
+    };
+
+    cx.render(rsx! {
+        div {
+            id: "activity-center",
+            div {
+                class: "activity-header",
+                label { "Activity" },
+                Button {
+                    text: String::from("Mark all read"),
+                    state: State::Secondary,
+                    disabled: !has_unread,
+                    on_pressed: move |_| state.write().dispatch(Actions::MarkAllActivityRead),
+                },
+                Button {
+                    icon: Shape::Trash,
+                    state: State::Secondary,
+                    disabled: entries.is_empty(),
+                    on_pressed: move |_| state.write().dispatch(Actions::ClearActivity),
+                },
+            },
+            if entries.is_empty() {
+                rsx!(
+                    p {
+                        class: "activity-empty",
+                        "Nothing new."
+                    }
+                )
+            } else {
+                rsx!(
+                    div {
+                        class: "activity-list",
+                        entries.into_iter().map(|entry| {
+                            let id = entry.id;
+                            let read_class = if entry.read { "read" } else { "unread" };
+                            let kind = entry.kind.label();
+                            let time = iutils::display_msg_time(entry.created);
+                            let count = (entry.count > 1).then(|| format!(" ({})", entry.count)).unwrap_or_default();
+                            let title = entry.title.clone();
+                            let body = entry.body.clone();
+                            let shape = icon(entry.kind);
+                            rsx!(
+                                div {
+                                    key: "{id}",
+                                    class: "activity-entry {read_class}",
+                                    title: "{kind}",
+                                    onclick: move |_| open(&entry),
+                                    Icon { icon: shape },
+                                    div {
+                                        class: "activity-text",
+                                        div {
+                                            class: "activity-title",
+                                            "{title}{count}"
+                                        },
+                                        (!body.is_empty()).then(|| rsx!(
+                                            div {
+                                                class: "activity-body",
+                                                "{body}"
+                                            }
+                                        )),
+                                        div {
+                                            class: "activity-time",
+                                            "{time}"
+                                        }
+                                    }
+                                }
+                            )
+                        })
+                    }
+                )
+            }
+        }
+    })
+}
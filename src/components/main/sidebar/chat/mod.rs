@@ -1,6 +1,11 @@
 use crate::{
+    components::reusable::verification::VerifiedBadge,
     iutils,
-    state::{Actions, ConversationInfo, LastMsgSent},
+    state::{
+        activity::{Activity, ActivityKind},
+        retention::ControlMessage,
+        Actions, ConversationInfo, LastMsgSent,
+    },
     Account, Messaging, LANGUAGE, STATE,
 };
 use dioxus::prelude::*;
@@ -11,9 +16,9 @@ use ui_kit::{
     profile_picture::PFP,
     skeletons::{inline::InlineSkeleton, pfp::PFPSkeleton},
 };
+use utils::presence::{self, Presence};
 use uuid::Uuid;
 use warp::crypto::DID;
-use warp::multipass::identity::IdentityStatus;
 use warp::raygun::{Message, MessageEventKind};
 
 #[derive(Props)]
@@ -40,7 +45,8 @@ pub fn Chat<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
     let unread_count = use_state(&cx, || 0_u32).clone();
     // need this one for display
     let unread_count2 = unread_count.clone();
-    let online_status = use_state(&cx, || IdentityStatus::Offline).clone();
+    // None while the friend is offline or invisible
+    let online_status = use_state(&cx, || None::<Presence>).clone();
     let online_status2 = online_status.clone();
     let chat_id = cx.props.conversation_info.conversation.id();
 
@@ -95,15 +101,20 @@ pub fn Chat<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
         .unwrap_or_default();
 
     let show_skeleton = username.is_empty();
+    // the real username is shown on hover when the user has a nickname
+    let display_name = state
+        .read()
+        .contact(&did.to_string())
+        .nickname
+        .unwrap_or_else(|| username.clone());
+    let verified = state.read().is_verified(&did.to_string());
     let active = if cx.props.is_active { "active" } else { "none" };
 
+    // the presence is refreshed by `presence::watch_presence`
     use_future(
         &cx,
-        (
-            &cx.props.conversation_info.clone(),
-            &cx.props.account.clone(),
-        ),
-        |(conversation_info, account)| async move {
+        &cx.props.conversation_info.clone(),
+        |conversation_info| async move {
             let remote_did = conversation_info
                 .conversation
                 .recipients()
@@ -111,17 +122,16 @@ pub fn Chat<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
                 .cloned()
                 .unwrap_or_default();
 
+            let mut presences = presence::watch(&remote_did);
             loop {
-                if let Ok(current_status) =
-                    warp::async_block_in_place_uncheck(account.identity_status(&remote_did))
-                {
-                    if *online_status.current() != current_status {
-                        log::debug!("updating online_status ");
-                        online_status.set(current_status);
-                    }
+                let current_status = presence::cached(&presences.borrow(), &remote_did);
+                if *online_status.current() != current_status {
+                    log::debug!("updating online_status ");
+                    online_status.set(current_status);
+                }
+                if presences.changed().await.is_err() {
+                    break;
                 }
-                // todo: consider making this configurable or longer
-                tokio::time::sleep(std::time::Duration::from_millis(1000)).await;
             }
         },
     );
@@ -169,33 +179,72 @@ pub fn Chat<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
             };
 
             while let Some(event) = stream.next().await {
-                if let MessageEventKind::MessageReceived {
-                    conversation_id,
-                    message_id,
-                } = event
-                {
-                    match rg.get_message(conversation_id, message_id).await {
-                        Ok(msg) => {
-                            log::debug!("sidebar/chat streamed a message");
-                            tx_chan.send(msg.clone());
-                            unread_count.modify(|x| x + 1);
-                            if state.read().selected_chat
-                                != Some(conversation_info.conversation.id())
-                                && conversation_info.num_unread_messages == 0
-                            {
-                                conversation_info.first_unread_message_id = Some(msg.id())
+                match event {
+                    MessageEventKind::MessageReceived {
+                        conversation_id,
+                        message_id,
+                    } => {
+                        match rg.get_message(conversation_id, message_id).await {
+                            Ok(msg) => {
+                                log::debug!("sidebar/chat streamed a message");
+                                // settings announced by the other participants don't count as unread messages
+                                if let Some(ControlMessage::SetRetention(retention)) =
+                                    ControlMessage::decode(&msg.value())
+                                {
+                                    state.write().dispatch(Actions::SetRetention(
+                                        conversation_id,
+                                        retention,
+                                    ));
+                                    continue;
+                                }
+                                tx_chan.send(msg.clone());
+                                unread_count.modify(|x| x + 1);
+                                if state.read().selected_chat
+                                    != Some(conversation_info.conversation.id())
+                                    && conversation_info.num_unread_messages == 0
+                                {
+                                    conversation_info.first_unread_message_id = Some(msg.id())
+                                }
+                                // will silently remain zero if you only use *unread_count
+                                conversation_info.num_unread_messages = *unread_count.current();
+                                conversation_info.last_msg_sent =
+                                    Some(LastMsgSent::new(&msg.value()));
+                                state.write().dispatch(Actions::UpdateConversation(
+                                    conversation_info.clone(),
+                                ));
                             }
-                            // will silently remain zero if you only use *unread_count
-                            conversation_info.num_unread_messages = *unread_count.current();
-                            conversation_info.last_msg_sent = Some(LastMsgSent::new(&msg.value()));
-                            state
-                                .write()
-                                .dispatch(Actions::UpdateConversation(conversation_info.clone()));
+                            Err(_e) => {
+                                // todo: possibly log errorv
+                            }
+                        };
+                    }
+                    MessageEventKind::MessageReactionAdded {
+                        conversation_id,
+                        message_id,
+                        did_key,
+                        reaction,
+                    } => {
+                        // only reactions from others to our own messages are shown in the activity feed
+                        if did_key == ident.did_key() {
+                            continue;
                         }
-                        Err(_e) => {
-                            // todo: possibly log errorv
+                        match rg.get_message(conversation_id, message_id).await {
+                            Ok(msg) if msg.sender() == ident.did_key() => {
+                                let username = iutils::get_username_from_did(did_key, &mp);
+                                state.write().dispatch(Actions::AddActivity(Activity::new(
+                                    ActivityKind::Reaction,
+                                    format!("{} reacted {}", username, reaction),
+                                    // the activity feed is saved to disk, so the message text isn't copied into it
+                                    String::new(),
+                                    Some(conversation_id),
+                                    Some(message_id),
+                                )));
+                            }
+                            Ok(_) => {}
+                            Err(e) => log::error!("failed to get reacted message: {}", e),
                         }
-                    };
+                    }
+                    _ => {}
                 }
             }
         },
@@ -280,7 +329,9 @@ pub fn Chat<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
                     div {
                         class: "top-row",
                         h3 {
-                            "{username}"
+                            title: "{username}",
+                            "{display_name}",
+                            verified.then(|| rsx!(VerifiedBadge {}))
                         },
                         last_msg_time.map(|time| {
                             rsx! (
@@ -324,10 +375,15 @@ pub fn Chat<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
 
 #[inline_props]
 #[allow(non_snake_case)]
-pub fn ChatPfp(cx: Scope, status: UseState<IdentityStatus>, account: Account, did: DID) -> Element {
+pub fn ChatPfp(
+    cx: Scope,
+    status: UseState<Option<Presence>>,
+    account: Account,
+    did: DID,
+) -> Element {
     let is_online = match *status.current() {
-        IdentityStatus::Online => "online",
-        _ => "",
+        Some(presence) => presence.class(),
+        None => "",
     };
     let profile_picture = iutils::get_pfp_from_did(did.clone(), account);
 
@@ -1,7 +1,13 @@
-use std::{borrow::Borrow, cell::RefCell, collections::HashMap};
+use std::{
+    borrow::Borrow,
+    cell::RefCell,
+    collections::HashMap,
+    time::{Duration, Instant},
+};
 
-use dioxus::{events::FormEvent, prelude::*};
+use dioxus::{core::to_owned, desktop::use_window, events::FormEvent, prelude::*};
 use dioxus_heroicons::outline::Shape;
+use emoji_selector::packs;
 use futures::StreamExt;
 use fuzzy_matcher::skim::SkimMatcherV2;
 use fuzzy_matcher::FuzzyMatcher;
@@ -10,22 +16,41 @@ use uuid::Uuid;
 use warp::raygun::Message;
 
 use crate::{
-    components::{main::sidebar::favorites::Favorites, reusable::nav::Nav},
-    iutils::config::Config,
-    state::{Actions, ConversationInfo},
-    Messaging, LANGUAGE, STATE,
+    components::{
+        main::sidebar::{
+            activity::ActivityCenter, favorites::Favorites, new_chat::NewChat, profile::Profile,
+        },
+        reusable::nav::Nav,
+    },
+    iutils::{
+        config::Config,
+        notification_queue::NotificationQueue,
+        notification_rules::{
+            self, ConversationRule, EventKind, NotificationEvent, NotificationRules,
+        },
+    },
+    state::{
+        activity::{Activity, ActivityKind},
+        Actions, ConversationInfo,
+    },
+    Messaging, CONFIG, LANGUAGE, STATE,
 };
 
-use ::utils::{notifications::PushNotification, Account};
+use ::utils::{notifications::push_notification_with_action, Account};
 use ui_kit::{
+    button::{self, Button},
     context_menu::{ContextItem, ContextMenu},
     extension_placeholder::ExtensionPlaceholder,
     input::Input,
+    numeric_indicator::NumericIndicator,
     skeletal_chats::SkeletalChats,
 };
 
+pub mod activity;
 pub mod chat;
 pub mod favorites;
+pub mod new_chat;
+pub mod profile;
 
 #[derive(Props, PartialEq)]
 pub struct Props {
@@ -63,14 +88,83 @@ pub fn Sidebar(cx: Scope<Props>) -> Element {
         ext_enabled,
     );
 
+    // opens the conversation of a clicked notification
+    let window = use_window(&cx).clone();
+    let open_chat_tx = use_coroutine(&cx, |mut rx: UnboundedReceiver<Uuid>| {
+        to_owned![state, router];
+        async move {
+            while let Some(conversation_id) = rx.next().await {
+                state
+                    .write()
+                    .dispatch(Actions::ShowConversation(conversation_id));
+                router.replace_route("/main", None, None);
+                window.focus();
+            }
+        }
+    });
+
+    let open_chat_tx2 = open_chat_tx.clone();
+    let state2 = state.clone();
+    let config_atom = use_atom_ref(&cx, CONFIG).clone();
     let notifications_tx = use_coroutine(&cx, |mut rx: UnboundedReceiver<Message>| async move {
-        while let Some(msg) = rx.next().await {
-            let display_username = crate::iutils::get_username_from_did(msg.sender().clone(), &mp);
-            PushNotification(
-                display_username,
-                msg.value().join("\n"),
-                ::utils::sounds::Sounds::Notification,
-            );
+        let mut queue = NotificationQueue::new(Duration::ZERO, 1);
+        loop {
+            // the settings can change at any time
+            let rules = config_atom.read().notifications.clone();
+            queue.set_limits(Duration::from_secs(rules.coalesce_seconds), rules.max_per_minute);
+            // wait for the next message, or until a grouped notification is due
+            let next = match queue.next_due(Instant::now()) {
+                Some(due) => tokio::time::timeout(due, rx.next()).await.ok(),
+                None => Some(rx.next().await),
+            };
+            match next {
+                Some(None) => break,
+                Some(Some(msg)) => {
+                    let text = msg.value().join("\n");
+                    let username = mp
+                        .get_own_identity()
+                        .await
+                        .map(|identity| identity.username())
+                        .unwrap_or_default();
+                    let mentions_me = notification_rules::mentions(&text, &username);
+                    let display_username =
+                        crate::iutils::get_username_from_did(msg.sender().clone(), &mp);
+                    let rules = config_atom.read().notifications.clone();
+                    for activity in message_activity(&msg, &display_username, mentions_me, &rules) {
+                        state2.write().dispatch(Actions::AddActivity(activity));
+                    }
+
+                    let event = NotificationEvent {
+                        kind: EventKind::Message,
+                        conversation_id: Some(msg.conversation_id()),
+                        text: &text,
+                        mentions_me,
+                        presence: state2.read().presence(),
+                    };
+                    if notification_rules::should_notify(&event, &rules) {
+                        queue.push(
+                            msg.conversation_id(),
+                            display_username,
+                            text,
+                            Instant::now(),
+                        );
+                    }
+                }
+                None => {}
+            }
+
+            let privacy = config_atom.read().notifications.privacy;
+            for group in queue.take_due(Instant::now()) {
+                let (title, body) = group.format(privacy);
+                let open_chat_tx = open_chat_tx2.clone();
+                let conversation_id = group.conversation_id;
+                push_notification_with_action(
+                    title,
+                    body,
+                    ::utils::sounds::Sounds::Notification,
+                    move || open_chat_tx.send(conversation_id),
+                );
+            }
         }
     });
 
@@ -103,7 +197,10 @@ pub fn Sidebar(cx: Scope<Props>) -> Element {
                     warp::async_block_in_place_uncheck(mp.get_identity(did.clone().into())).ok()
                 })
                 .flatten()
-                .map(|i| i.username())
+                .map(|i| {
+                    crate::state::contacts::nickname(&i.did_key().to_string())
+                        .unwrap_or_else(|| i.username())
+                })
                 .last()
                 .unwrap_or_default();
 
@@ -126,6 +223,15 @@ pub fn Sidebar(cx: Scope<Props>) -> Element {
     });
 
     let fav_exist = !state.read().favorites.clone().is_empty();
+    let show_activity = use_state(&cx, || false);
+    let show_new_chat = use_state(&cx, || false);
+    let new_chat_text = l.new_chat.to_string();
+    let unread_activity = state
+        .read()
+        .activity
+        .iter()
+        .filter(|entry| !entry.read)
+        .count();
 
     cx.render(rsx!{
         div {
@@ -152,17 +258,60 @@ pub fn Sidebar(cx: Scope<Props>) -> Element {
                 })
             },
             div {
-                class: "search-input",
-                Input {
-                    icon: Shape::MagnifyingGlass,
-                    placeholder: String::from("Search"),
-                    value: search_value.to_string(),
-                    on_change: move |e: FormEvent| {
-                        search_value.set(e.value.clone());
+                class: "search-row",
+                div {
+                    class: "search-input",
+                    Input {
+                        icon: Shape::MagnifyingGlass,
+                        placeholder: String::from("Search"),
+                        value: search_value.to_string(),
+                        on_change: move |e: FormEvent| {
+                            search_value.set(e.value.clone());
+                        },
+                        on_enter: move |_| {},
                     },
-                    on_enter: move |_| {},
                 },
-            }
+                Button {
+                    icon: Shape::PencilSquare,
+                    text: new_chat_text,
+                    hide_text: true,
+                    state: if **show_new_chat {
+                        button::State::Primary
+                    } else {
+                        button::State::Secondary
+                    },
+                    on_pressed: move |_| show_new_chat.set(!**show_new_chat),
+                },
+                div {
+                    class: "has_indicator",
+                    Button {
+                        icon: Shape::Bell,
+                        state: if **show_activity {
+                            button::State::Primary
+                        } else {
+                            button::State::Secondary
+                        },
+                        on_pressed: move |_| show_activity.set(!**show_activity),
+                    },
+                    (unread_activity > 0).then(|| rsx!(
+                        NumericIndicator {
+                            count: unread_activity
+                        }
+                    )),
+                }
+            },
+            show_new_chat.then(|| rsx!(
+                NewChat {
+                    account: cx.props.account.clone(),
+                    messaging: cx.props.messaging.clone(),
+                    on_close: move |_| show_new_chat.set(false),
+                }
+            )),
+            show_activity.then(|| rsx!(
+                ActivityCenter {
+                    on_close: move |_| show_activity.set(false),
+                }
+            )),
             config.developer.developer_mode.then(|| rsx! {
                 ExtensionPlaceholder {},
             }),
@@ -211,6 +360,9 @@ pub fn Sidebar(cx: Scope<Props>) -> Element {
                 )
             }
             else { rsx!( SkeletalChats{} ) },
+            Profile {
+                account: cx.props.account.clone(),
+            },
             Nav {
                 account: cx.props.account.clone(),
                 messaging: cx.props.messaging.clone(),
@@ -218,3 +370,52 @@ pub fn Sidebar(cx: Scope<Props>) -> Element {
         }
     })
 }
+
+// the activity feed entries for a received message. the feed is saved to disk and outlives disappearing
+// messages, so it never holds the text of the message
+fn message_activity(
+    msg: &Message,
+    sender: &str,
+    mentions_me: bool,
+    rules: &NotificationRules,
+) -> Vec<Activity> {
+    let conversation_id = Some(msg.conversation_id());
+    let message_id = Some(msg.id());
+    let mut entries = vec![];
+    if mentions_me {
+        entries.push(Activity::new(
+            ActivityKind::Mention,
+            format!("{} mentioned you", sender),
+            String::new(),
+            conversation_id,
+            message_id,
+        ));
+    }
+    if !mentions_me && rules.conversation_rule(msg.conversation_id()) == ConversationRule::Muted {
+        entries.push(Activity::new(
+            ActivityKind::MutedMessage,
+            format!("New messages from {}", sender),
+            String::new(),
+            conversation_id,
+            message_id,
+        ));
+    }
+    // the images of custom emoji aren't shared files
+    let text = msg.value().join("\n");
+    let names: Vec<String> = msg
+        .attachments()
+        .iter()
+        .map(|file| file.name())
+        .filter(|name| !packs::is_fallback_attachment(name, &text))
+        .collect();
+    if !names.is_empty() {
+        entries.push(Activity::new(
+            ActivityKind::FileShare,
+            format!("{} shared {} file(s)", sender, names.len()),
+            names.join(", "),
+            conversation_id,
+            message_id,
+        ));
+    }
+    entries
+}
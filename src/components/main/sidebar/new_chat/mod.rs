@@ -0,0 +1,162 @@
+use dioxus::{prelude::*, router::use_router};
+use dioxus_heroicons::{outline::Shape, Icon};
+use ui_kit::button::{Button, State};
+use utils::Account;
+use uuid::Uuid;
+use warp::crypto::DID;
+
+use crate::{
+    iutils,
+    state::{groups, Actions},
+    Messaging, STATE,
+};
+
+#[derive(Props)]
+pub struct Props<'a> {
+    account: Account,
+    messaging: Messaging,
+    on_close: EventHandler<'a, ()>,
+}
+
+/// picks a friend to chat with, opened from the main sidebar. friends can be filtered by group
+#[allow(non_snake_case)]
+pub fn NewChat<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
+    log::debug!("rendering main/sidebar/NewChat");
+    let state = use_atom_ref(&cx, STATE);
+    let router = use_router(&cx);
+    let search_value = use_state(&cx, String::new);
+    // only the friends in this group are listed
+    let group_filter: &UseState<Option<Uuid>> = use_state(&cx, || None);
+    let friends: &UseState<Vec<(DID, String)>> = use_state(&cx, Vec::new);
+
+    // friends are loaded once when the picker opens
+    use_future(
+        &cx,
+        (&cx.props.account.clone(), friends),
+        |(mp, friends)| async move {
+            match mp.list_friends().await {
+                Ok(list) => {
+                    let mut named: Vec<_> = list
+                        .into_iter()
+                        .map(|did| {
+                            let name = iutils::get_username_from_did(did.clone(), &mp);
+                            (did, name)
+                        })
+                        .collect();
+                    named.sort_by_key(|(_, name)| name.to_lowercase());
+                    friends.set(named);
+                }
+                Err(e) => log::error!("failed to list friends for a new chat: {}", e),
+            }
+        },
+    );
+
+    let chat_with = move |did: &DID| {
+        let rg = cx.props.messaging.clone();
+        let conversation = match warp::async_block_in_place_uncheck(rg.create_conversation(did)) {
+            Ok(v) => v,
+            Err(warp::error::Error::ConversationExist { conversation }) => conversation,
+            Err(e) => {
+                log::error!("failed to chat with friend {}: {}", did, e);
+                return;
+            }
+        };
+        state.write().dispatch(Actions::ChatWith(conversation));
+        router.replace_route("/main", None, None);
+        cx.props.on_close.call(());
+    };
+
+    let friend_groups = state.read().friend_groups.clone();
+    // a deleted group no longer filters
+    let group_filter_value =
+        (**group_filter).filter(|id| friend_groups.iter().any(|group| group.id == *id));
+    let search = search_value.trim().to_lowercase();
+    let matching: Vec<(DID, String)> = friends
+        .iter()
+        .filter(|(did, _)| {
+            groups::matches_filter(&friend_groups, group_filter_value, &did.to_string())
+        })
+        .filter(|(_, name)| name.to_lowercase().contains(&search))
+        .cloned()
+        .collect();
+    let all_active = if group_filter_value.is_none() {
+        "active"
+    } else {
+        ""
+    };
+
+    cx.render(rsx! {
+        div {
+            id: "new-chat",
+            div {
+                class: "new-chat-header",
+                label { "New Chat" },
+                Button {
+                    icon: Shape::XMark,
+                    state: State::Secondary,
+                    on_pressed: move |_| cx.props.on_close.call(()),
+                },
+            },
+            input {
+                class: "new-chat-search",
+                placeholder: "Search friends",
+                value: "{search_value}",
+                oninput: move |e| search_value.set(e.value.clone()),
+            },
+            (!friend_groups.is_empty()).then(|| rsx!(
+                div {
+                    class: "new-chat-groups",
+                    div {
+                        class: "new-chat-group {all_active}",
+                        onclick: move |_| group_filter.set(None),
+                        "All"
+                    },
+                    friend_groups.iter().map(|group| {
+                        let id = group.id;
+                        let name = group.name.clone();
+                        let color = group.color.clone();
+                        let active = if group_filter_value == Some(id) { "active" } else { "" };
+                        rsx!(
+                            div {
+                                key: "{id}",
+                                class: "new-chat-group {active}",
+                                onclick: move |_| group_filter.set(Some(id)),
+                                span {
+                                    class: "new-chat-group-color",
+                                    style: "background: {color}",
+                                },
+                                "{name}"
+                            }
+                        )
+                    })
+                }
+            )),
+            if matching.is_empty() {
+                rsx!(
+                    p {
+                        class: "new-chat-empty",
+                        "No friends found."
+                    }
+                )
+            } else {
+                rsx!(
+                    div {
+                        class: "new-chat-list",
+                        matching.into_iter().map(|(did, name)| {
+                            let key = did.to_string();
+                            rsx!(
+                                div {
+                                    key: "{key}",
+                                    class: "new-chat-friend",
+                                    onclick: move |_| chat_with(&did),
+                                    Icon { icon: Shape::ChatBubbleBottomCenterText },
+                                    span { "{name}" }
+                                }
+                            )
+                        })
+                    }
+                )
+            }
+        }
+    })
+}
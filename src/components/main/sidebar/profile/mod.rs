@@ -0,0 +1,97 @@
+use dioxus::prelude::*;
+use ui_kit::profile_picture::PFP;
+use utils::presence::{self, Presence};
+
+use crate::{iutils, state::Actions, Account, STATE};
+
+/// the local user's name and presence at the bottom of the sidebar. clicking it opens the presence selector
+#[inline_props]
+#[allow(non_snake_case)]
+pub fn Profile(cx: Scope, account: Account) -> Element {
+    log::debug!("rendering main/sidebar/Profile");
+    let state = use_atom_ref(&cx, STATE);
+    let show_selector = use_state(&cx, || false);
+
+    let identity = match warp::async_block_in_place_uncheck(account.get_own_identity()) {
+        Ok(identity) => identity,
+        Err(e) => {
+            log::error!("failed to get own identity: {}", e);
+            return None;
+        }
+    };
+    let username = identity.username();
+    let status_message = identity.status_message().unwrap_or_default();
+    let (_, status_message) = presence::parse_status(&status_message);
+    let status_message = status_message.to_string();
+    let profile_picture = iutils::get_pfp_from_did(identity.did_key(), account);
+
+    let current = state.read().presence();
+    let current_class = current.class();
+    // auto away is shown so that the user knows why they are away
+    let current_label = if state.read().idle && state.read().presence == Presence::Online {
+        format!("{} (idle)", current.label())
+    } else {
+        current.label()
+    };
+
+    cx.render(rsx! {
+        div {
+            class: "sidebar-profile",
+            show_selector.then(|| rsx!(
+                div {
+                    class: "presence-selector",
+                    Presence::all().into_iter().map(|option| {
+                        let label = option.label();
+                        let class = option.class();
+                        let selected = if state.read().presence == option { "selected" } else { "" };
+                        rsx!(
+                            div {
+                                key: "{label}",
+                                class: "presence-option {selected}",
+                                onclick: move |_| {
+                                    state.write().dispatch(Actions::SetPresence(option));
+                                    show_selector.set(false);
+                                    let mut account = account.clone();
+                                    let current = state.read().presence();
+                                    if let Err(e) = warp::async_block_in_place_uncheck(presence::publish(&mut account, current)) {
+                                        log::error!("failed to publish the presence: {}", e);
+                                    }
+                                },
+                                span { class: "presence-bubble {class}" },
+                                "{label}"
+                            }
+                        )
+                    })
+                }
+            )),
+            div {
+                class: "sidebar-profile-row",
+                onclick: move |_| show_selector.set(!**show_selector),
+                div {
+                    class: "pfp-container",
+                    PFP {
+                        src: profile_picture,
+                        size: ui_kit::profile_picture::Size::Normal
+                    },
+                    span { class: "presence-bubble {current_class}" },
+                },
+                div {
+                    class: "who",
+                    h3 {
+                        class: "ellipsis",
+                        "{username}"
+                    },
+                    p {
+                        class: "ellipsis",
+                        title: "{status_message}",
+                        if status_message.is_empty() {
+                            rsx!("{current_label}")
+                        } else {
+                            rsx!("{current_label} · {status_message}")
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,437 @@
+use chrono::Utc;
+use dioxus::{
+    events::{FormEvent, KeyboardEvent},
+    prelude::*,
+    router::use_router,
+};
+use dioxus_heroicons::{outline::Shape, Icon};
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use state::palette::{self, PaletteCommand};
+use utils::upload_queue::APP_DIRECTORY;
+use uuid::Uuid;
+use warp::{
+    constellation::{directory::Directory, item::ItemType},
+    crypto::DID,
+};
+
+use crate::{
+    iutils::{self, shortcuts::ShortcutAction},
+    state::{groups::groups_of, Actions, ConversationInfo},
+    Account, Messaging, Storage, CONFIG, DEFAULT_PATH, STATE,
+};
+
+// only this many results are rendered
+const MAX_RESULTS: usize = 50;
+// how deep into the file tree the palette searches
+const MAX_FILE_DEPTH: usize = 4;
+
+#[derive(Clone)]
+enum EntryKind {
+    Conversation(Uuid),
+    Friend(DID),
+    // the names of the directories leading to the item, and whether the item is itself a directory
+    File(Vec<String>, bool),
+    SettingsPage(&'static str),
+    Shortcut(ShortcutAction),
+    OpenCache,
+    Extension(PaletteCommand),
+}
+
+#[derive(Clone)]
+struct Entry {
+    // identifies the entry in `PersistedState::palette_usage`
+    id: String,
+    title: String,
+    subtitle: String,
+    icon: Shape,
+    kind: EntryKind,
+}
+
+#[derive(Props)]
+pub struct Props<'a> {
+    account: Account,
+    messaging: Messaging,
+    storage: Storage,
+    on_action: EventHandler<'a, ShortcutAction>,
+    on_dismiss: EventHandler<'a, ()>,
+}
+
+#[allow(non_snake_case)]
+pub fn CommandPalette<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
+    log::debug!("rendering CommandPalette");
+    let state = use_atom_ref(&cx, STATE);
+    let config = use_atom_ref(&cx, CONFIG);
+    let router = use_router(&cx);
+    let eval = use_eval(&cx);
+    let search_value = use_state(&cx, String::new);
+    let selected_idx = use_state(&cx, || 0_usize);
+    let friends: &UseState<Vec<(DID, String)>> = use_state(&cx, Vec::new);
+    let files: &UseState<Vec<(Vec<String>, String, bool)>> = use_state(&cx, Vec::new);
+
+    // friends and files are loaded once when the palette opens
+    use_future(
+        &cx,
+        (&cx.props.account.clone(), friends),
+        |(mp, friends)| async move {
+            match mp.list_friends().await {
+                Ok(list) => {
+                    let named = list
+                        .into_iter()
+                        .map(|did| {
+                            let name = iutils::get_username_from_did(did.clone(), &mp);
+                            (did, name)
+                        })
+                        .collect();
+                    friends.set(named);
+                }
+                Err(e) => log::error!("failed to list friends for the command palette: {}", e),
+            }
+        },
+    );
+    use_future(
+        &cx,
+        (&cx.props.storage.clone(), files),
+        |(storage, files)| async move {
+            let mut found = vec![];
+            collect_files(&storage.root_directory(), &mut vec![], &mut found);
+            files.set(found);
+        },
+    );
+
+    let focus_eval = eval.clone();
+    use_effect(&cx, (), move |_| {
+        let eval = focus_eval;
+        async move {
+            eval("document.querySelector('#command-palette input')?.focus();".to_string());
+        }
+    });
+
+    let mp = cx.props.account.clone();
+    let mut entries: Vec<Entry> = vec![];
+
+    // most recent conversations first
+    let mut chats: Vec<ConversationInfo> = state.read().all_chats.values().cloned().collect();
+    chats.sort();
+    chats.reverse();
+    entries.extend(chats.iter().map(|info| {
+        let id = info.conversation.id();
+        let (_, username) = iutils::get_username_from_conversation(info, &mp);
+        Entry {
+            id: format!("conversation:{}", id),
+            title: username,
+            subtitle: String::from("Conversation"),
+            icon: Shape::ChatBubbleBottomCenterText,
+            kind: EntryKind::Conversation(id),
+        }
+    }));
+    let friend_groups = state.read().friend_groups.clone();
+    entries.extend(friends.iter().map(|(did, name)| {
+        let group_names: Vec<String> = groups_of(&friend_groups, &did.to_string())
+            .into_iter()
+            .map(|group| group.name.clone())
+            .collect();
+        Entry {
+            id: format!("friend:{}", did),
+            title: name.clone(),
+            subtitle: if group_names.is_empty() {
+                String::from("Friend")
+            } else {
+                format!("Friend · {}", group_names.join(", "))
+            },
+            icon: Shape::User,
+            kind: EntryKind::Friend(did.clone()),
+        }
+    }));
+    entries.extend(files.iter().map(|(path, name, is_dir)| {
+        let location = if path.is_empty() {
+            String::from("Files")
+        } else {
+            format!("Files/{}", path.join("/"))
+        };
+        Entry {
+            id: format!("file:{}/{}", path.join("/"), name),
+            title: name.clone(),
+            subtitle: location,
+            icon: if *is_dir {
+                Shape::Folder
+            } else {
+                Shape::Document
+            },
+            kind: EntryKind::File(path.clone(), *is_dir),
+        }
+    }));
+    entries.extend(
+        [
+            ("General", "/main/settings"),
+            ("Profile", "/main/settings/profile"),
+            ("Audio Video", "/main/settings/audio-video"),
+            ("Extensions", "/main/settings/extensions"),
+            ("Keybinds", "/main/settings/keybinds"),
+            ("Notifications", "/main/settings/notifications"),
+            ("Developer", "/main/settings/developer"),
+        ]
+        .into_iter()
+        .map(|(name, route)| Entry {
+            id: format!("settings:{}", route),
+            title: format!("Settings: {}", name),
+            subtitle: String::from("Settings"),
+            icon: Shape::Cog6Tooth,
+            kind: EntryKind::SettingsPage(route),
+        }),
+    );
+    let keybinds = config.read().keybinds.clone();
+    entries.extend(
+        ShortcutAction::all()
+            .into_iter()
+            .filter(|action| *action != ShortcutAction::CommandPalette)
+            .map(|action| Entry {
+                id: format!("action:{:?}", action),
+                title: action.label(),
+                subtitle: keybinds.get(action).to_string(),
+                icon: Shape::Bolt,
+                kind: EntryKind::Shortcut(action),
+            }),
+    );
+    entries.push(Entry {
+        id: String::from("action:OpenCache"),
+        title: String::from("Open Cache"),
+        subtitle: String::from("Show the cache folder"),
+        icon: Shape::FolderOpen,
+        kind: EntryKind::OpenCache,
+    });
+    // commands from the enabled native extensions
+    let enabled_extensions = state.read().enabled_extensions.clone();
+    entries.extend(
+        palette::registered()
+            .into_iter()
+            .filter(|(extension, _)| enabled_extensions.contains(extension))
+            .map(|(extension, command)| Entry {
+                id: format!("extension:{}:{}", extension, command.name),
+                title: command.name.clone(),
+                subtitle: command.description.clone(),
+                icon: Shape::PuzzlePiece,
+                kind: EntryKind::Extension(command),
+            }),
+    );
+
+    // rank by the fuzzy match score plus a bonus for entries which were chosen often or recently
+    let matcher = SkimMatcherV2::default();
+    let (group_query, search) = split_group_filter(&search_value.get().to_lowercase());
+    // "#team alice" only lists the friends in a group whose name starts with "team"
+    if let Some(group_query) = group_query {
+        entries.retain(|entry| match &entry.kind {
+            EntryKind::Friend(did) => groups_of(&friend_groups, &did.to_string())
+                .iter()
+                .any(|group| group.name.to_lowercase().starts_with(&group_query)),
+            _ => false,
+        });
+    }
+    let now = Utc::now();
+    let mut results: Vec<(i64, Entry)> = {
+        let state = state.read();
+        entries
+            .into_iter()
+            .filter_map(|entry| {
+                let score = if search.is_empty() {
+                    0
+                } else {
+                    matcher.fuzzy_match(&entry.title, &search)?
+                };
+                let bonus = state
+                    .palette_usage
+                    .get(&entry.id)
+                    .map(|u| u.bonus(now))
+                    .unwrap_or(0);
+                Some((score + bonus, entry))
+            })
+            .collect()
+    };
+    // the sort is stable, so entries with the same score keep their order
+    results.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    let results: Vec<Entry> = results
+        .into_iter()
+        .take(MAX_RESULTS)
+        .map(|(_, entry)| entry)
+        .collect();
+    let num_results = results.len();
+    let current_idx = (**selected_idx).min(num_results.saturating_sub(1));
+
+    let run_entry = move |entry: Entry| {
+        state
+            .write()
+            .dispatch(Actions::RecordPaletteUsage(entry.id.clone()));
+        match entry.kind {
+            EntryKind::Conversation(id) => {
+                state.write().dispatch(Actions::ShowConversation(id));
+                router.replace_route("/main", None, None);
+            }
+            EntryKind::Friend(did) => {
+                let rg = cx.props.messaging.clone();
+                let conversation =
+                    match warp::async_block_in_place_uncheck(rg.create_conversation(&did)) {
+                        Ok(v) => v,
+                        Err(warp::error::Error::ConversationExist { conversation }) => conversation,
+                        Err(e) => {
+                            log::error!("failed to chat with friend {}: {}", &did, e);
+                            return;
+                        }
+                    };
+                state.write().dispatch(Actions::ChatWith(conversation));
+                router.replace_route("/main", None, None);
+            }
+            EntryKind::File(path, is_dir) => {
+                let mut storage = cx.props.storage.clone();
+                open_directory(&mut storage, &path);
+                if is_dir {
+                    if let Err(e) = storage.select(&entry.title) {
+                        log::error!("failed to open directory {}: {}", &entry.title, e);
+                    }
+                }
+                router.replace_route("/main/files", None, None);
+            }
+            EntryKind::SettingsPage(route) => router.replace_route(route, None, None),
+            EntryKind::Shortcut(action) => cx.props.on_action.call(action),
+            EntryKind::OpenCache => {
+                let _ = opener::open(DEFAULT_PATH.read().clone());
+            }
+            EntryKind::Extension(command) => {
+                let action = (command.run)(&state.read());
+                state.write().dispatch(action);
+            }
+        }
+        cx.props.on_dismiss.call(());
+    };
+
+    let results2 = results.clone();
+
+    cx.render(rsx! {
+        div {
+            id: "command-palette",
+            onclick: move |_| cx.props.on_dismiss.call(()),
+            div {
+                class: "command-palette-body",
+                onclick: move |e| {
+                    e.cancel_bubble();
+                },
+                div {
+                    class: "command-palette-search",
+                    Icon {
+                        icon: Shape::MagnifyingGlass,
+                    },
+                    input {
+                        placeholder: "Search chats, friends, files and actions. #group filters friends",
+                        value: "{search_value}",
+                        oninput: move |e: FormEvent| {
+                            search_value.set(e.value.clone());
+                            selected_idx.set(0);
+                        },
+                        onkeydown: move |e: KeyboardEvent| {
+                            // keys typed into the palette must not trigger other shortcuts
+                            e.cancel_bubble();
+                            match e.key.as_str() {
+                                "ArrowDown" if num_results > 0 => selected_idx.set((current_idx + 1) % num_results),
+                                "ArrowUp" if num_results > 0 => selected_idx.set((current_idx + num_results - 1) % num_results),
+                                "Enter" => {
+                                    if let Some(entry) = results2.get(current_idx) {
+                                        run_entry(entry.clone());
+                                    }
+                                }
+                                "Escape" => cx.props.on_dismiss.call(()),
+                                _ => {}
+                            }
+                        },
+                    },
+                },
+                div {
+                    class: "command-palette-results",
+                    (num_results == 0).then(|| rsx!(
+                        p {
+                            class: "command-palette-empty",
+                            "No results"
+                        }
+                    )),
+                    results.into_iter().enumerate().map(|(idx, entry)| {
+                        let selected_class = if idx == current_idx { "selected" } else { "" };
+                        let key = entry.id.clone();
+                        let title = entry.title.clone();
+                        let subtitle = entry.subtitle.clone();
+                        let icon = entry.icon;
+                        rsx!(
+                            div {
+                                key: "{key}",
+                                class: "command-palette-entry {selected_class}",
+                                onmouseover: move |_| selected_idx.set(idx),
+                                onclick: move |_| run_entry(entry.clone()),
+                                Icon {
+                                    icon: icon,
+                                },
+                                p {
+                                    class: "ellipsis",
+                                    "{title}"
+                                },
+                                span {
+                                    class: "command-palette-subtitle",
+                                    "{subtitle}"
+                                }
+                            }
+                        )
+                    })
+                }
+            }
+        }
+    })
+}
+
+// splits "#group rest" into the group filter and the rest of the search
+fn split_group_filter(search: &str) -> (Option<String>, String) {
+    match search.strip_prefix('#') {
+        Some(rest) => {
+            let (group, rest) = rest.split_once(' ').unwrap_or((rest, ""));
+            (Some(group.to_string()), rest.trim().to_string())
+        }
+        None => (None, search.to_string()),
+    }
+}
+
+fn collect_files(
+    dir: &Directory,
+    path: &mut Vec<String>,
+    found: &mut Vec<(Vec<String>, String, bool)>,
+) {
+    for item in dir.get_items() {
+        if path.is_empty() && item.name() == APP_DIRECTORY {
+            continue;
+        }
+        let is_dir = item.item_type() == ItemType::DirectoryItem;
+        found.push((path.clone(), item.name(), is_dir));
+        if is_dir && path.len() < MAX_FILE_DEPTH {
+            if let Ok(sub_dir) = item.get_directory() {
+                path.push(item.name());
+                collect_files(&sub_dir, path, found);
+                path.pop();
+            }
+        }
+    }
+}
+
+// makes the directory at `path` the current directory of the file browser
+fn open_directory(storage: &mut Storage, path: &[String]) {
+    let root_id = storage.root_directory().id();
+    while storage
+        .current_directory()
+        .map(|dir| dir.id() != root_id)
+        .unwrap_or(false)
+    {
+        if let Err(e) = storage.go_back() {
+            log::error!("Error on go back a directory: {e}");
+            return;
+        }
+    }
+    for name in path {
+        if let Err(e) = storage.select(name) {
+            log::error!("Error selecting new current directory folder: {e}");
+            return;
+        }
+    }
+}
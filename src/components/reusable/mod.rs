@@ -1,6 +1,13 @@
+pub mod command_palette;
 pub mod nav;
 pub mod page_header;
 pub mod popout;
+pub mod presence;
+pub mod profile;
+pub mod requests;
+pub mod shortcuts;
 pub mod sidebar;
 pub mod textarea;
 pub mod toolbar;
+pub mod transfers;
+pub mod verification;
@@ -1,4 +1,3 @@
-use ::utils::notifications::PushNotification;
 use dioxus::prelude::*;
 use dioxus_heroicons::outline::Shape;
 use futures::StreamExt;
@@ -9,7 +8,7 @@ use ui_kit::{
     numeric_indicator::NumericIndicator,
 };
 
-use crate::{Account, Messaging, LANGUAGE};
+use crate::{Account, Messaging};
 use warp::{
     multipass::MultiPassEventKind,
     raygun::{Conversation, ConversationType},
@@ -34,16 +33,14 @@ pub struct Props {
 pub fn Nav(cx: Scope<Props>) -> Element {
     log::debug!("rendering reusable Nav");
     let state = use_atom_ref(&cx, STATE).clone();
-    let l = use_atom_ref(&cx, LANGUAGE).read().clone();
     let router = use_router(&cx).clone();
     let router2 = router.clone();
     let router3 = router.clone();
     let router4 = router.clone();
     let rg = cx.props.messaging.clone();
     let multipass = cx.props.account.clone();
-    let reqCount = use_state(&cx, || {
-        warp::async_block_in_place_uncheck(multipass.list_incoming_request()).unwrap_or_default().len()
-    });
+    // ignored requests aren't counted
+    let reqCount = state.read().requests.pending_count();
 
     let route = use_route(&cx).last_segment();
 
@@ -60,11 +57,8 @@ pub fn Nav(cx: Scope<Props>) -> Element {
 
     use_future(
         &cx,
-        (reqCount, &multipass, &rg),
-        |(reqCount, mut multipass, mut rg)| async move {
-            // Used to make sure everything is initialized before proceeding.
-            let new_friend_request_notification = l.new_friend_request.to_string().to_owned();
-
+        (&multipass, &rg),
+        |(mut multipass, mut rg)| async move {
             let mut stream = loop {
                 match multipass.subscribe().await {
                     Ok(stream) => break stream,
@@ -80,49 +74,11 @@ pub fn Nav(cx: Scope<Props>) -> Element {
                 };
             };
 
-            // one can process other events such as event sent, closed, accepted, etc
-            // Note: should only use events related to `FriendRequest` here
+            // friend requests are filtered and announced once for the whole app by `RequestTracker`
             while let Some(event) = stream.next().await {
                 match event {
-                    MultiPassEventKind::FriendRequestReceived { from } => {
-                        // Use to show the name or did of who its from
-                        let name_or_did = warp::async_block_in_place_uncheck(multipass
-                            .get_identity(from.clone().into()))
-                            .ok()
-                            .and_then(|list| list.first().cloned())
-                            .map(|id| id.username())
-                            .unwrap_or_else(|| from.to_string());
-
-                        PushNotification(
-                            new_friend_request_notification.clone(),
-                            // "New Friend Request".to_owned(),
-                            format!("{} sent a friend request", name_or_did),
-                            // "Come see who it is!".to_owned(),
-                            ::utils::sounds::Sounds::FriendReq,
-                        );
-                        log::debug!("updating friend request count");
-                        // Note, this will increase the counter. Maybe use a separate task to check the list or use other events to decrease it
-                        reqCount.with_mut(|count| *count += 1);
-                    }
-                    MultiPassEventKind::IncomingFriendRequestRejected { .. } => {
-                        log::debug!("friend request rejected");
-                        if *(reqCount.get()) != 0 {
-                            log::debug!("close friend request");
-                            reqCount.with_mut(|count| *count -= 1);
-                        }
-                    }
-                    MultiPassEventKind::IncomingFriendRequestClosed { .. } => {
-                        log::debug!("friend reqeust cancelled");
-                        if *(reqCount.get()) != 0 {
-                            log::debug!("close friend request");
-                            reqCount.with_mut(|count| *count -= 1);
-                        }
-                    }
                     MultiPassEventKind::FriendAdded { did } => {
                         log::debug!("added friend: {}", &did);
-                        if *(reqCount.get()) != 0 {
-                            reqCount.with_mut(|count| *count -= 1);
-                        }
                         log::debug!("creating chat");
                         let _result = rg.create_conversation(&did).await;
                     }
@@ -197,9 +153,9 @@ pub fn Nav(cx: Scope<Props>) -> Element {
                     }
                     icon: Shape::Users
                 },
-                (*reqCount.get() > 0).then(|| rsx!(
+                (reqCount > 0).then(|| rsx!(
                     NumericIndicator {
-                        count: *reqCount.get()
+                        count: reqCount
                     }
                 )),
             }
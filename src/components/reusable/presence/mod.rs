@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+use dioxus::{fermi::UseAtomRef, prelude::*};
+use utils::presence;
+
+use crate::{
+    state::{self, Actions, PersistedState},
+    Account, CONFIG, STATE,
+};
+
+/// ends auto away. called on keyboard and mouse input
+pub fn record_input(state: &UseAtomRef<PersistedState>) {
+    state::presence::touch();
+    if state.read().idle {
+        state.write().dispatch(Actions::SetIdle(false));
+    }
+}
+
+/// turns on auto away after a while without input, shares the presence with friends and keeps track of theirs. it
+/// is placed around the routes so that it runs on every page
+#[inline_props]
+#[allow(non_snake_case)]
+pub fn PresenceTracker(cx: Scope, account: Account) -> Element {
+    let state = use_atom_ref(&cx, STATE).clone();
+    let config = use_atom_ref(&cx, CONFIG).clone();
+
+    use_future(&cx, account, presence::watch_presence);
+
+    use_future(&cx, account, |mut account| async move {
+        loop {
+            let minutes = config.read().presence.auto_away_minutes;
+            let idle = state::presence::is_idle(minutes);
+            if state.read().idle != idle {
+                state.write().dispatch(Actions::SetIdle(idle));
+            }
+            // fails until the account is unlocked
+            let current = state.read().presence();
+            if let Err(e) = presence::publish(&mut account, current).await {
+                log::debug!("failed to publish the presence: {}", e);
+            }
+            tokio::time::sleep(Duration::from_secs(15)).await;
+        }
+    });
+
+    None
+}
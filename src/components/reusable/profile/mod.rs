@@ -0,0 +1,266 @@
+use arboard::Clipboard;
+use chrono::{DateTime, Utc};
+use dioxus::prelude::*;
+use dioxus_heroicons::{outline::Shape, Icon};
+use ui_kit::{
+    activity_indicator::ActivityIndicator,
+    button::{self, Button},
+    profile_picture::PFP,
+};
+use utils::{files_functions, presence};
+use uuid::Uuid;
+use warp::{
+    crypto::DID,
+    raygun::{ConversationType, MessageOptions},
+};
+
+use crate::{
+    components::reusable::{
+        popout::Popout,
+        verification::{KeyChangeWarning, VerifiedBadge},
+    },
+    iutils,
+    state::verification,
+    Account, Messaging, STATE,
+};
+
+// the number of recently shared files shown
+const RECENT_FILES: usize = 8;
+const MEDIA_EXTENSIONS: [&str; 12] = [
+    "png", "jpg", "jpeg", "gif", "webp", "svg", "mp4", "webm", "mov", "mp3", "wav", "ogg",
+];
+
+#[derive(Clone, PartialEq)]
+struct SharedFile {
+    name: String,
+    size: String,
+    date: String,
+    media: bool,
+}
+
+/// what was exchanged in the direct conversation with the friend
+#[derive(Clone, PartialEq, Default)]
+struct SharedHistory {
+    message_count: usize,
+    first_contact: Option<DateTime<Utc>>,
+    // most recent first
+    files: Vec<SharedFile>,
+}
+
+/// a friend's profile. mutual friends aren't shown because MultiPass only exposes our own friend list
+#[inline_props]
+#[allow(non_snake_case)]
+pub fn FriendProfile(
+    cx: Scope,
+    account: Account,
+    messaging: Messaging,
+    did: DID,
+    is_visible: UseState<bool>,
+) -> Element {
+    log::debug!("rendering reusable/FriendProfile");
+    let state = use_atom_ref(&cx, STATE);
+    let copied = use_state(&cx, || false);
+
+    let did_string = did.to_string();
+    let conversation = state
+        .read()
+        .all_chats
+        .values()
+        .find(|info| {
+            info.conversation.conversation_type() == ConversationType::Direct
+                && info.conversation.recipients().contains(did)
+        })
+        .map(|info| (info.conversation.id(), info.creation_time));
+
+    let history = use_future(
+        &cx,
+        (messaging, &conversation),
+        |(mut rg, conversation)| async move {
+            let (conversation_id, creation_time): (Uuid, DateTime<Utc>) = match conversation {
+                Some(conversation) => conversation,
+                None => return SharedHistory::default(),
+            };
+            let messages = match rg
+                .get_messages(conversation_id, MessageOptions::default())
+                .await
+            {
+                Ok(messages) => messages,
+                Err(e) => {
+                    log::error!("failed to get messages for the profile: {}", e);
+                    vec![]
+                }
+            };
+            let first_message = messages.iter().map(|message| message.date()).min();
+            let mut files: Vec<SharedFile> = messages
+                .iter()
+                .rev()
+                .flat_map(|message| {
+                    let date = iutils::display_msg_time(message.date());
+                    message.attachments().into_iter().map(move |file| {
+                        let name = file.name();
+                        let media = name
+                            .rsplit_once('.')
+                            .map(|(_, extension)| {
+                                MEDIA_EXTENSIONS.contains(&extension.to_lowercase().as_str())
+                            })
+                            .unwrap_or(false);
+                        SharedFile {
+                            size: files_functions::format_item_size(file.size()),
+                            date: date.clone(),
+                            name,
+                            media,
+                        }
+                    })
+                })
+                .collect();
+            files.truncate(RECENT_FILES);
+            SharedHistory {
+                message_count: messages.len(),
+                first_contact: Some(
+                    first_message.map_or(creation_time, |first| first.min(creation_time)),
+                ),
+                files,
+            }
+        },
+    );
+
+    let identity = warp::async_block_in_place_uncheck(account.get_identity(did.clone().into()))
+        .ok()
+        .and_then(|identities| identities.first().cloned());
+    let username = iutils::get_identity_username(did.clone(), account);
+    let display_name = state
+        .read()
+        .contact(&did_string)
+        .nickname
+        .unwrap_or_else(|| username.clone());
+    let status_message = identity
+        .and_then(|identity| identity.status_message())
+        .map(|message| presence::parse_status(&message).1.to_string())
+        .unwrap_or_default();
+    let profile_picture = iutils::get_pfp_from_did(did.clone(), account);
+    let verified = state.read().is_verified(&did_string);
+    let key_changed =
+        verification::key_change(&state.read().identities, &did_string, &username).is_some();
+    let copy_text = if **copied { "Copied" } else { "Copy" };
+    let verification_text = if verified {
+        "You compared safety numbers with this friend."
+    } else {
+        "Not verified. Compare safety numbers from the friends list."
+    };
+    let did_string2 = did_string.clone();
+
+    cx.render(rsx! {
+        Popout {
+            is_visible: is_visible.clone(),
+            remote: String::new(),
+            div {
+                class: "friend-profile",
+                onclick: move |e| e.cancel_bubble(),
+                div {
+                    class: "friend-profile-header",
+                    PFP {
+                        src: profile_picture,
+                        size: ui_kit::profile_picture::Size::Large
+                    },
+                    div {
+                        class: "friend-profile-name",
+                        h3 {
+                            title: "{username}",
+                            "{display_name}",
+                            verified.then(|| rsx!(VerifiedBadge {}))
+                        },
+                        ActivityIndicator {
+                            inline: true,
+                            remote_did: did.clone(),
+                        },
+                        (!status_message.is_empty()).then(|| rsx!(
+                            p {
+                                class: "friend-profile-status",
+                                "{status_message}"
+                            }
+                        )),
+                    }
+                },
+                key_changed.then(|| rsx!(
+                    KeyChangeWarning { username: username.clone() }
+                )),
+                div {
+                    class: "friend-profile-did",
+                    span {
+                        class: "ellipsis",
+                        title: "{did_string}",
+                        "{did_string}"
+                    },
+                    Button {
+                        text: copy_text.to_string(),
+                        icon: Shape::ClipboardDocument,
+                        state: button::State::Secondary,
+                        on_pressed: move |_| {
+                            match Clipboard::new().and_then(|mut clipboard| clipboard.set_text(did_string2.clone())) {
+                                Ok(_) => copied.set(true),
+                                Err(e) => log::error!("failed to copy the DID: {}", e),
+                            }
+                        },
+                    },
+                },
+                p {
+                    class: "friend-profile-verification",
+                    "{verification_text}"
+                },
+                match history.value() {
+                    Some(history) => {
+                        let message_count = history.message_count;
+                        let first_contact = history
+                            .first_contact
+                            .map(|date| date.format("%B %-d, %Y").to_string())
+                            .unwrap_or_else(|| String::from("Never"));
+                        rsx!(
+                            div {
+                                class: "friend-profile-stats",
+                                div {
+                                    label { "First Contact" },
+                                    span { "{first_contact}" }
+                                },
+                                div {
+                                    label { "Messages" },
+                                    span { "{message_count}" }
+                                },
+                            },
+                            (!history.files.is_empty()).then(|| rsx!(
+                                label {
+                                    "Recent Files & Media"
+                                },
+                                div {
+                                    class: "friend-profile-files",
+                                    history.files.iter().map(|file| {
+                                        let icon = if file.media { Shape::Photo } else { Shape::Document };
+                                        let name = &file.name;
+                                        let details = format!("{} · {}", file.size, file.date);
+                                        rsx!(
+                                            div {
+                                                class: "friend-profile-file",
+                                                Icon { icon: icon },
+                                                span {
+                                                    class: "ellipsis",
+                                                    title: "{name}",
+                                                    "{name}"
+                                                },
+                                                span {
+                                                    class: "friend-profile-file-meta",
+                                                    "{details}"
+                                                }
+                                            }
+                                        )
+                                    })
+                                }
+                            ))
+                        )
+                    }
+                    None => rsx!(
+                        p { "Loading shared history…" }
+                    ),
+                }
+            }
+        }
+    })
+}
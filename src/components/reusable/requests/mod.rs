@@ -0,0 +1,176 @@
+use std::{collections::HashSet, time::Duration};
+
+use ::utils::notifications::PushNotification;
+use chrono::Utc;
+use dioxus::{fermi::UseAtomRef, prelude::*};
+use futures::StreamExt;
+use warp::{crypto::DID, multipass::MultiPassEventKind};
+
+use crate::{
+    iutils::{
+        config::Config,
+        notification_rules::{self, EventKind, NotificationEvent},
+        request_filter::{self, IncomingRequest, RequestDecision},
+    },
+    state::{
+        activity::{Activity, ActivityKind},
+        Actions, PersistedState,
+    },
+    Account, Messaging, CONFIG, LANGUAGE, STATE,
+};
+
+/// filters incoming friend requests, announces the allowed ones and sends the intro notes of accepted requests. every
+/// page has its own nav, so this is placed around the routes to handle each event once
+#[inline_props]
+#[allow(non_snake_case)]
+pub fn RequestTracker(cx: Scope, account: Account, messaging: Messaging) -> Element {
+    let state = use_atom_ref(&cx, STATE).clone();
+    let config = use_atom_ref(&cx, CONFIG).clone();
+    let l = use_atom_ref(&cx, LANGUAGE).read().clone();
+
+    use_future(
+        &cx,
+        (account, messaging),
+        |(mut account, mut messaging)| async move {
+            // fails until the account is unlocked
+            let mut stream = loop {
+                match account.subscribe().await {
+                    Ok(stream) => break stream,
+                    Err(e) => {
+                        log::debug!("failed to subscribe to friend requests: {}", e);
+                        tokio::time::sleep(Duration::from_millis(500)).await;
+                    }
+                }
+            };
+            update_incoming(&account, &state).await;
+
+            while let Some(event) = stream.next().await {
+                match event {
+                    MultiPassEventKind::FriendRequestReceived { from } => {
+                        let from_string = from.to_string();
+                        let now = Utc::now();
+                        state
+                            .write()
+                            .dispatch(Actions::RecordFriendRequest(from_string.clone()));
+
+                        let username = account
+                            .get_identity(from.clone().into())
+                            .await
+                            .ok()
+                            .and_then(|list| list.first().cloned())
+                            .map(|id| id.username());
+                        let request = IncomingRequest {
+                            username: username.as_deref(),
+                            recent_requests: state.read().requests.recent_count(&from_string, now),
+                            ignored: state.read().requests.is_ignored(&from_string),
+                        };
+                        let decision =
+                            request_filter::evaluate(&request, &config.read().friend_requests);
+                        match decision {
+                            RequestDecision::Allow => {
+                                // Use to show the name or did of who its from
+                                let name_or_did = username
+                                    .filter(|username| !username.is_empty())
+                                    .unwrap_or(from_string);
+                                announce(&state, &config, &l.new_friend_request, name_or_did);
+                            }
+                            RequestDecision::Ignore => {
+                                log::debug!("ignoring friend request");
+                                state.write().dispatch(Actions::CountFilteredRequest);
+                            }
+                            RequestDecision::Decline(reason) => {
+                                log::info!("declining friend request: {:?}", reason);
+                                if let Err(e) = account.deny_request(&from).await {
+                                    log::error!("failed to decline friend request: {}", e);
+                                }
+                                state.write().dispatch(Actions::CountFilteredRequest);
+                            }
+                        }
+                    }
+                    MultiPassEventKind::FriendAdded { did } => {
+                        send_intro(&mut messaging, &state, &did).await;
+                    }
+                    _ => {}
+                }
+                // the navs count the pending requests
+                update_incoming(&account, &state).await;
+            }
+        },
+    );
+
+    None
+}
+
+// adds the request to the activity feed and notifies the user, unless the notification rules say otherwise
+fn announce(
+    state: &UseAtomRef<PersistedState>,
+    config: &UseAtomRef<Config>,
+    title: &str,
+    name_or_did: String,
+) {
+    state.write().dispatch(Actions::AddActivity(Activity::new(
+        ActivityKind::FriendRequest,
+        name_or_did.clone(),
+        String::from("sent you a friend request"),
+        None,
+        None,
+    )));
+    let event = NotificationEvent {
+        kind: EventKind::FriendRequest,
+        conversation_id: None,
+        text: "",
+        mentions_me: false,
+        presence: state.read().presence(),
+    };
+    let rules = config.read().notifications.clone();
+    if notification_rules::should_notify(&event, &rules) {
+        PushNotification(
+            title.to_string(),
+            format!("{} sent a friend request", name_or_did),
+            ::utils::sounds::Sounds::FriendReq,
+        );
+    }
+}
+
+async fn update_incoming(account: &Account, state: &UseAtomRef<PersistedState>) {
+    let incoming: HashSet<String> = match account.list_incoming_request().await {
+        Ok(requests) => requests
+            .iter()
+            .map(|request| request.from().to_string())
+            .collect(),
+        Err(e) => {
+            log::error!("failed to list incoming friend requests: {}", e);
+            return;
+        }
+    };
+    if state.read().requests.incoming != incoming {
+        state
+            .write()
+            .dispatch(Actions::SetIncomingRequests(incoming));
+    }
+}
+
+// the note attached to our request is sent as the first message once the request is accepted
+async fn send_intro(messaging: &mut Messaging, state: &UseAtomRef<PersistedState>, did: &DID) {
+    let did_string = did.to_string();
+    let intro = state.read().requests.intros.get(&did_string).cloned();
+    let note = match intro {
+        Some(note) => note,
+        None => return,
+    };
+    let conversation = match messaging.create_conversation(did).await {
+        Ok(conversation) => conversation,
+        Err(warp::error::Error::ConversationExist { conversation }) => conversation,
+        Err(e) => {
+            log::error!("failed to create a chat for the intro note: {}", e);
+            return;
+        }
+    };
+    state
+        .write()
+        .dispatch(Actions::SetIntroNote(did_string, None));
+    let lines = note.lines().map(String::from).collect();
+    if let Err(e) = messaging.send(conversation.id(), None, lines).await {
+        log::error!("failed to send the intro note: {}", e);
+    }
+}
@@ -0,0 +1,166 @@
+use dioxus::{events::KeyboardEvent, prelude::*, router::use_router};
+use uuid::Uuid;
+use warp::tesseract::Tesseract;
+
+use crate::{
+    components::reusable::{
+        command_palette::CommandPalette,
+        presence::{self, PresenceTracker},
+        requests::RequestTracker,
+        transfers::TransferPanel,
+    },
+    iutils::shortcuts::ShortcutAction,
+    state::{Actions, ConversationInfo},
+    Account, Messaging, Storage, CONFIG, STATE,
+};
+
+// key events are only delivered to the focused element. keeps the focus inside the shortcut handler
+const FOCUS_SCRIPT: &str = "
+if (!window.keyboardShortcutsFocus) {
+    window.keyboardShortcutsFocus = true;
+    document.addEventListener('focusout', () => {
+        setTimeout(() => {
+            if (document.activeElement === document.body) {
+                document.getElementById('keyboard-shortcuts')?.focus();
+            }
+        }, 0);
+    });
+}
+";
+
+#[derive(Props)]
+pub struct Props<'a> {
+    tesseract: Tesseract,
+    account: Account,
+    messaging: Messaging,
+    storage: Storage,
+    children: Element<'a>,
+}
+
+/// handles the global keyboard shortcuts. must be placed inside the `Router`.
+#[allow(non_snake_case)]
+pub fn KeyboardShortcuts<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
+    let state = use_atom_ref(&cx, STATE);
+    let config = use_atom_ref(&cx, CONFIG);
+    let router = use_router(&cx);
+    let eval = use_eval(&cx);
+    let show_palette = use_state(&cx, || false);
+
+    let focus_eval = eval.clone();
+    use_effect(&cx, (), move |_| {
+        let eval = focus_eval;
+        async move {
+            eval(FOCUS_SCRIPT.to_string());
+        }
+    });
+
+    let handle_action = move |action: ShortcutAction| {
+        log::debug!("keyboard shortcut: {:?}", action);
+        match action {
+            ShortcutAction::NextChat | ShortcutAction::PreviousChat => {
+                let chats = sorted_chats(&state.read().active_chats);
+                if chats.is_empty() {
+                    return;
+                }
+                let current = state
+                    .read()
+                    .selected_chat
+                    .and_then(|id| chats.iter().position(|chat| *chat == id));
+                let next = match (current, action) {
+                    (None, _) => 0,
+                    (Some(idx), ShortcutAction::NextChat) => (idx + 1) % chats.len(),
+                    (Some(idx), _) => (idx + chats.len() - 1) % chats.len(),
+                };
+                state
+                    .write()
+                    .dispatch(Actions::ShowConversation(chats[next]));
+                router.replace_route("/main", None, None);
+            }
+            ShortcutAction::JumpToUnread => {
+                let unread = {
+                    let state = state.read();
+                    let mut chats: Vec<&ConversationInfo> = state
+                        .active_chats
+                        .values()
+                        .filter(|info| info.num_unread_messages > 0)
+                        .collect();
+                    chats.sort();
+                    chats.last().map(|info| info.conversation.id())
+                };
+                if let Some(id) = unread {
+                    state.write().dispatch(Actions::ShowConversation(id));
+                    router.replace_route("/main", None, None);
+                }
+            }
+            ShortcutAction::FocusComposer => {
+                eval("document.querySelector('#write #TODO-textarea-input .dynamic-input')?.focus();".to_string());
+            }
+            ShortcutAction::OpenFiles => router.replace_route("/main/files", None, None),
+            ShortcutAction::OpenFriends => router.replace_route("/main/friends", None, None),
+            ShortcutAction::OpenSettings => router.replace_route("/main/settings", None, None),
+            ShortcutAction::MarkAllRead => state.write().dispatch(Actions::MarkAllRead),
+            ShortcutAction::Search => {
+                eval(
+                    "document.querySelector('#main-sidebar .search-input input')?.focus();"
+                        .to_string(),
+                );
+            }
+            ShortcutAction::LockAccount => {
+                cx.props.tesseract.lock();
+                router.replace_route("/", None, None);
+            }
+            ShortcutAction::CommandPalette => show_palette.set(!**show_palette),
+        }
+    };
+
+    cx.render(rsx! {
+        div {
+            id: "keyboard-shortcuts",
+            tabindex: "-1",
+            onmousemove: move |_| presence::record_input(state),
+            onkeydown: move |e: KeyboardEvent| {
+                presence::record_input(state);
+                // the shortcuts stay disabled until the account is unlocked
+                if !cx.props.tesseract.is_unlock() {
+                    return;
+                }
+                // the Keybinds page updates the config, so changes apply right away
+                let action = config.read().keybinds.action_for(e.ctrl_key, e.alt_key, e.shift_key, e.meta_key, &e.key);
+                if let Some(action) = action {
+                    handle_action(action);
+                }
+            },
+            PresenceTracker {
+                account: cx.props.account.clone(),
+            },
+            RequestTracker {
+                account: cx.props.account.clone(),
+                messaging: cx.props.messaging.clone(),
+            },
+            TransferPanel {
+                storage: cx.props.storage.clone(),
+            },
+            (**show_palette).then(|| rsx!(
+                CommandPalette {
+                    account: cx.props.account.clone(),
+                    messaging: cx.props.messaging.clone(),
+                    storage: cx.props.storage.clone(),
+                    on_action: move |action| handle_action(action),
+                    on_dismiss: move |_| show_palette.set(false),
+                }
+            )),
+            &cx.props.children,
+        }
+    })
+}
+
+// most recent conversations first, the same order as the chats sidebar
+fn sorted_chats(chats: &std::collections::HashMap<Uuid, ConversationInfo>) -> Vec<Uuid> {
+    let mut chats: Vec<&ConversationInfo> = chats.values().collect();
+    chats.sort();
+    chats.reverse();
+    chats
+        .into_iter()
+        .map(|info| info.conversation.id())
+        .collect()
+}
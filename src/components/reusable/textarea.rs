@@ -1,12 +1,16 @@
 use std::time::{Duration, Instant};
 
-use dioxus::prelude::*;
+use dioxus::{events::KeyboardEvent, prelude::*};
+use emoji_selector::shortcodes;
 use futures::StreamExt;
 use uuid::Uuid;
 use warp::raygun::MessageEvent;
 
 use crate::{Messaging, STATE};
 
+// the number of emojis suggested while typing a shortcode
+const MAX_SUGGESTIONS: usize = 8;
+
 // the local side will send an event to indicate typing and refresh it periodically
 // the remote side will disable the typing indicator if a refresh isn't received in time
 // the remote side will also disable the typing indicator if a new message is received
@@ -85,28 +89,137 @@ pub fn TextArea<'a>(cx: Scope<'a, Props<'a>>) -> Element<'a> {
     });
     let chan2 = chan.clone();
     let chan3 = chan.clone();
-    cx.render(rsx!(ui_kit::textarea::TextArea {
-        on_input: move |val: String| {
-            let chat_id = match current_chat {
-                Some(c) => c,
-                None => {
-                    cx.props.on_input.call(val);
+
+    // emoji autocomplete for a shortcode typed at the end of the text
+    let eval = use_eval(&cx);
+    let suggestion_idx = use_state(&cx, || 0_usize);
+    // the query for which the suggestions were closed with Escape
+    let dismissed_query = use_state(&cx, String::new);
+    let skin_tone = state.read().emoji_skin_tone;
+    let query = shortcodes::trailing_query(cx.props.text.trim_end_matches('\n'))
+        .filter(|query| *query != dismissed_query.as_str())
+        .map(str::to_string);
+    let suggestions = match &query {
+        Some(query) => shortcodes::suggest(query, skin_tone, MAX_SUGGESTIONS),
+        None => vec![],
+    };
+    let num_suggestions = suggestions.len();
+    let current_idx = (**suggestion_idx).min(num_suggestions.saturating_sub(1));
+    let query_len = query
+        .as_ref()
+        .map(|q| q.chars().count())
+        .unwrap_or_default();
+
+    // replaces the shortcode with the emoji the same way a user would type it, so the composer's state stays in sync
+    let accept = move |emoji: String| {
+        eval(format!(
+            "
+            const input = document.querySelector('#TODO-textarea-input .dynamic-input');
+            input.focus();
+            const range = document.createRange();
+            range.selectNodeContents(input);
+            range.collapse(false);
+            const selection = window.getSelection();
+            selection.removeAllRanges();
+            selection.addRange(range);
+            for (let i = 0; i < {}; i++) {{
+                document.execCommand('delete');
+            }}
+            document.execCommand('insertText', false, {});
+        ",
+            // the query and its leading colon
+            query_len + 1,
+            serde_json::to_string(&format!("{} ", emoji)).unwrap_or_default()
+        ));
+        suggestion_idx.set(0);
+    };
+    let suggestions2 = suggestions.clone();
+
+    cx.render(rsx!(
+        div {
+            class: "emoji-autocomplete",
+            onkeydown: move |e: KeyboardEvent| {
+                if num_suggestions == 0 {
                     return;
                 }
-            };
-            if send_typing {
-                chan2.send(ChanCmd::Typing {
-                    chat_id,
-                    rg: cx.props.messaging.clone(),
-                });
+                match e.key.as_str() {
+                    "Tab" => {
+                        if let Some((_, emoji)) = suggestions2.get(current_idx) {
+                            accept(emoji.clone());
+                        }
+                    }
+                    "ArrowDown" => suggestion_idx.set((current_idx + 1) % num_suggestions),
+                    "ArrowUp" => suggestion_idx.set((current_idx + num_suggestions - 1) % num_suggestions),
+                    "Escape" => {
+                        if let Some(query) = &query {
+                            dismissed_query.set(query.clone());
+                        }
+                    }
+                    _ => {}
+                }
+            },
+            (num_suggestions > 0).then(|| rsx!(
+                div {
+                    class: "emoji-suggestions",
+                    suggestions.into_iter().enumerate().map(|(idx, (shortcode, emoji))| {
+                        let selected_class = if idx == current_idx { "selected" } else { "" };
+                        let emoji2 = emoji.clone();
+                        rsx!(
+                            div {
+                                key: "{shortcode}",
+                                class: "emoji-suggestion {selected_class}",
+                                onmouseover: move |_| suggestion_idx.set(idx),
+                                onclick: move |_| accept(emoji2.clone()),
+                                span {
+                                    class: "emoji",
+                                    "{emoji}"
+                                },
+                                span {
+                                    class: "shortcode",
+                                    ":{shortcode}:"
+                                }
+                            }
+                        )
+                    })
+                }
+            )),
+            script {
+                "
+                // Tab and the arrow keys pick a suggestion instead of moving the focus or the cursor
+                if (!window.emojiAutocompleteKeys) {{
+                    window.emojiAutocompleteKeys = true;
+                    document.addEventListener('keydown', (e) => {{
+                        if (['Tab', 'ArrowUp', 'ArrowDown'].includes(e.key) && document.querySelector('.emoji-suggestions')) {{
+                            e.preventDefault();
+                        }}
+                    }}, true);
+                }}
+                "
+            },
+            ui_kit::textarea::TextArea {
+                on_input: move |val: String| {
+                    let chat_id = match current_chat {
+                        Some(c) => c,
+                        None => {
+                            cx.props.on_input.call(val);
+                            return;
+                        }
+                    };
+                    if send_typing {
+                        chan2.send(ChanCmd::Typing {
+                            chat_id,
+                            rg: cx.props.messaging.clone(),
+                        });
+                    }
+                    cx.props.on_input.call(val);
+                },
+                on_submit: move |val: String| {
+                    chan3.send(ChanCmd::NotTyping);
+                    cx.props.on_submit.call(val);
+                },
+                text: cx.props.text.clone(),
+                placeholder: cx.props.placeholder.clone(),
             }
-            cx.props.on_input.call(val);
-        },
-        on_submit: move |val: String| {
-            chan3.send(ChanCmd::NotTyping);
-            cx.props.on_submit.call(val);
-        },
-        text: cx.props.text.clone(),
-        placeholder: cx.props.placeholder.clone(),
-    }))
+        }
+    ))
 }
@@ -0,0 +1,167 @@
+use dioxus::prelude::*;
+use dioxus_heroicons::outline::Shape;
+use tokio::sync::broadcast::error::RecvError;
+use ui_kit::button::{self, Button};
+use utils::{
+    files_functions,
+    transfers::TRANSFERS,
+    upload_queue::{self, Upload, UploadStatus, UPLOAD_QUEUE},
+};
+
+use crate::Storage;
+
+/// runs the upload queue and shows its progress. it is placed around the routes so that uploads continue on every
+/// page
+#[inline_props]
+#[allow(non_snake_case)]
+pub fn TransferPanel(cx: Scope, storage: Storage) -> Element {
+    let uploads = use_state(&cx, Vec::<Upload>::new);
+    let collapsed = use_state(&cx, || false);
+
+    use_future(&cx, storage, upload_queue::run);
+
+    use_future(&cx, uploads, |uploads| async move {
+        let mut events = TRANSFERS.subscribe();
+        loop {
+            // the queue has the state after the event. missed events are caught up the same way
+            if let Err(RecvError::Closed) = events.recv().await {
+                break;
+            }
+            let current = UPLOAD_QUEUE.read().uploads.clone();
+            if *uploads.current() != current {
+                uploads.set(current);
+            }
+        }
+    });
+    // pausing doesn't send an event, so the panel reads the queue after its own changes
+    let refresh = move || uploads.set(UPLOAD_QUEUE.read().uploads.clone());
+
+    if uploads.is_empty() {
+        return None;
+    }
+
+    let pending = uploads
+        .iter()
+        .filter(|upload| !upload.is_finished())
+        .count();
+    let title = if pending == 0 {
+        String::from("Uploads finished")
+    } else {
+        format!("{} of {} uploads left", pending, uploads.len())
+    };
+    let collapse_icon = if **collapsed {
+        Shape::ChevronUp
+    } else {
+        Shape::ChevronDown
+    };
+
+    cx.render(rsx! {
+        div {
+            id: "transfer-panel",
+            div {
+                class: "transfer-panel-header",
+                span {
+                    class: "ellipsis",
+                    "{title}"
+                },
+                Button {
+                    icon: collapse_icon,
+                    state: button::State::Transparent,
+                    on_pressed: move |_| collapsed.set(!**collapsed),
+                },
+                (pending == 0).then(|| rsx!(
+                    Button {
+                        icon: Shape::XMark,
+                        state: button::State::Transparent,
+                        on_pressed: move |_| {
+                            upload_queue::clear_finished();
+                            refresh();
+                        },
+                    }
+                )),
+            },
+            (!**collapsed).then(|| rsx!(
+                div {
+                    class: "transfer-list",
+                    uploads.iter().map(|upload| {
+                        let id = upload.id;
+                        let name = upload.name();
+                        let percentage = upload.percentage();
+                        let paused = upload.paused;
+                        let detail = describe(upload);
+                        let can_pause = !upload.is_finished();
+                        let can_retry = matches!(upload.status, UploadStatus::Failed(_) | UploadStatus::Cancelled);
+                        let failed_class = if matches!(upload.status, UploadStatus::Failed(_)) { "failed" } else { "" };
+                        rsx!(
+                            div {
+                                key: "{id}",
+                                class: "transfer {failed_class}",
+                                div {
+                                    class: "transfer-info",
+                                    span {
+                                        class: "ellipsis",
+                                        title: "{name}",
+                                        "{name}"
+                                    },
+                                    span {
+                                        class: "transfer-detail ellipsis",
+                                        title: "{detail}",
+                                        "{detail}"
+                                    },
+                                    div {
+                                        class: "transfer-progress",
+                                        div {
+                                            class: "transfer-progress-bar",
+                                            width: "{percentage}%",
+                                        }
+                                    }
+                                },
+                                can_pause.then(|| rsx!(
+                                    Button {
+                                        icon: if paused { Shape::Play } else { Shape::Pause },
+                                        state: button::State::Transparent,
+                                        on_pressed: move |_| {
+                                            upload_queue::set_paused(id, !paused);
+                                            refresh();
+                                        },
+                                    },
+                                    Button {
+                                        icon: Shape::XMark,
+                                        state: button::State::Transparent,
+                                        on_pressed: move |_| upload_queue::cancel(id),
+                                    }
+                                )),
+                                can_retry.then(|| rsx!(
+                                    Button {
+                                        icon: Shape::ArrowPath,
+                                        state: button::State::Transparent,
+                                        on_pressed: move |_| upload_queue::retry(id),
+                                    }
+                                )),
+                            }
+                        )
+                    })
+                }
+            )),
+        }
+    })
+}
+
+fn describe(upload: &Upload) -> String {
+    match &upload.status {
+        UploadStatus::Queued if upload.paused => String::from("Paused"),
+        UploadStatus::Queued => String::from("Waiting"),
+        UploadStatus::Uploading { .. } if upload.paused => String::from("Paused"),
+        UploadStatus::Uploading { current, total } => match total {
+            Some(total) => format!(
+                "{} of {}",
+                files_functions::format_item_size(*current),
+                files_functions::format_item_size(*total)
+            ),
+            None => files_functions::format_item_size(*current),
+        },
+        UploadStatus::Done => String::from("Uploaded"),
+        UploadStatus::Failed(e) => format!("Failed: {}", e),
+        UploadStatus::Cancelled => String::from("Cancelled"),
+    }
+}
@@ -0,0 +1,149 @@
+use dioxus::prelude::*;
+use dioxus_heroicons::{outline::Shape, Icon};
+use ui_kit::button::{self, Button};
+use warp::crypto::DID;
+
+use crate::{
+    components::reusable::popout::Popout,
+    iutils,
+    state::{
+        verification::{self, fingerprint},
+        Actions,
+    },
+    Account, STATE,
+};
+
+/// shown next to the name of a friend whose fingerprint was compared
+#[allow(non_snake_case)]
+pub fn VerifiedBadge(cx: Scope) -> Element {
+    cx.render(rsx! {
+        span {
+            class: "verified-badge",
+            title: "Verified",
+            Icon {
+                icon: Shape::ShieldCheck,
+            }
+        }
+    })
+}
+
+#[inline_props]
+#[allow(non_snake_case)]
+pub fn KeyChangeWarning(cx: Scope, username: String) -> Element {
+    cx.render(rsx! {
+        div {
+            class: "key-change-warning",
+            Icon {
+                icon: Shape::ExclamationTriangle,
+            },
+            span {
+                "Another key was seen with the username {username}. Their key changed or someone is impersonating \
+                 them. Verify their identity before trusting them."
+            }
+        }
+    })
+}
+
+#[derive(Props, PartialEq)]
+pub struct Props {
+    account: Account,
+    did: DID,
+    is_visible: UseState<bool>,
+}
+
+/// shows the safety fingerprint for the local user and `did`, which both users should see the same
+#[allow(non_snake_case)]
+pub fn VerifyIdentity(cx: Scope<Props>) -> Element {
+    log::debug!("rendering VerifyIdentity");
+    let state = use_atom_ref(&cx, STATE);
+    if !*cx.props.is_visible.get() {
+        return None;
+    }
+
+    let mp = &cx.props.account;
+    let own_did = match warp::async_block_in_place_uncheck(mp.get_own_identity()) {
+        Ok(identity) => identity.did_key().to_string(),
+        Err(e) => {
+            log::error!("failed to get own identity: {}", e);
+            return None;
+        }
+    };
+    let did = cx.props.did.to_string();
+    let username = iutils::get_identity_username(cx.props.did.clone(), mp);
+    let fingerprint = fingerprint(&own_did, &did);
+    let verified = state.read().is_verified(&did);
+    let key_changed = verification::key_change(&state.read().identities, &did, &username).is_some();
+    let did2 = did.clone();
+    let did3 = did.clone();
+
+    cx.render(rsx! {
+        Popout {
+            is_visible: cx.props.is_visible.clone(),
+            remote: String::new(),
+            div {
+                class: "verify-identity",
+                onclick: move |e| e.cancel_bubble(),
+                h3 {
+                    "Verify {username}"
+                },
+                p {
+                    "Compare these numbers or emojis with {username} in person or over a call. If they match, you are \
+                     talking to the right person."
+                },
+                key_changed.then(|| rsx!(
+                    KeyChangeWarning { username: username.clone() }
+                )),
+                div {
+                    class: "fingerprint-numbers",
+                    fingerprint.numbers.iter().map(|group| rsx!(
+                        span { "{group}" }
+                    ))
+                },
+                div {
+                    class: "fingerprint-emojis",
+                    fingerprint.emojis.iter().map(|(emoji, word)| rsx!(
+                        div {
+                            class: "fingerprint-emoji",
+                            span { class: "emoji", "{emoji}" },
+                            span { "{word}" }
+                        }
+                    ))
+                },
+                div {
+                    class: "verify-controls",
+                    key_changed.then(|| rsx!(
+                        Button {
+                            text: "Accept New Key".to_string(),
+                            icon: Shape::Key,
+                            state: button::State::Secondary,
+                            on_pressed: move |_| {
+                                state.write().dispatch(Actions::AcknowledgeKeyChange(did3.clone()));
+                            },
+                        }
+                    )),
+                    if verified {rsx!(
+                        Button {
+                            text: "Remove Verification".to_string(),
+                            icon: Shape::ShieldExclamation,
+                            state: button::State::Secondary,
+                            on_pressed: move |_| {
+                                state.write().dispatch(Actions::SetVerified(did2.clone(), false));
+                            },
+                        }
+                    )} else {rsx!(
+                        Button {
+                            text: "Mark as Verified".to_string(),
+                            icon: Shape::ShieldCheck,
+                            on_pressed: move |_| {
+                                let mut state = state.write();
+                                // verifying the new key also accepts it
+                                state.dispatch(Actions::AcknowledgeKeyChange(did2.clone()));
+                                state.dispatch(Actions::SetVerified(did2.clone(), true));
+                            },
+                        }
+                    )}
+                }
+            }
+        }
+    })
+}
@@ -1,8 +1,15 @@
 use std::fs;
 use std::io::{Error, Write};
 
-use crate::DEFAULT_PATH;
+use crate::{
+    iutils::{
+        notification_rules::NotificationRules, request_filter::RequestFilterRules,
+        shortcuts::Keybinds,
+    },
+    DEFAULT_PATH,
+};
 use serde::{Deserialize, Serialize};
+use utils::sounds::SoundSettings;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Config {
@@ -11,6 +18,16 @@ pub struct Config {
     pub audiovideo: AudioVideo,
     pub extensions: Extensions,
     pub developer: Developer,
+    #[serde(default)]
+    pub keybinds: Keybinds,
+    #[serde(default)]
+    pub notifications: NotificationRules,
+    #[serde(default)]
+    pub sounds: SoundSettings,
+    #[serde(default)]
+    pub friend_requests: RequestFilterRules,
+    #[serde(default)]
+    pub presence: PresenceSettings,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -42,6 +59,21 @@ pub struct Developer {
     pub cache_dir: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, Copy, Clone)]
+#[serde(default)]
+pub struct PresenceSettings {
+    /// the presence becomes away after this many minutes without input. 0 turns it off
+    pub auto_away_minutes: u64,
+}
+
+impl Default for PresenceSettings {
+    fn default() -> Self {
+        Self {
+            auto_away_minutes: 10,
+        }
+    }
+}
+
 // Implementation to create, load and save the config
 impl Config {
     fn default() -> Self {
@@ -63,6 +95,11 @@ impl Config {
                 developer_mode: false,
                 cache_dir: String::from(".warp"),
             },
+            keybinds: Keybinds::default(),
+            notifications: NotificationRules::default(),
+            sounds: SoundSettings::default(),
+            friend_requests: RequestFilterRules::default(),
+            presence: PresenceSettings::default(),
         }
     }
 
@@ -110,6 +147,7 @@ impl Config {
         let mut file = std::fs::OpenOptions::new()
             .write(true)
             .create(true)
+            .truncate(true)
             .open(DEFAULT_PATH.read().join("Config.toml"))?;
         self.save_to_writer(&mut file)
     }
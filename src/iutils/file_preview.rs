@@ -0,0 +1,199 @@
+//! what the file preview shows for a file. the HTML is rendered with `dangerous_inner_html`, so HTML written in a
+//! file is escaped or dropped, never passed through, and links are shown as text.
+
+use std::{ffi::OsStr, path::Path};
+
+use once_cell::sync::Lazy;
+use pulldown_cmark::{escape::escape_html, html, Event, LinkType, Options, Parser, Tag};
+use syntect::{highlighting::ThemeSet, html::highlighted_html_for_string, parsing::SyntaxSet};
+
+/// the most bytes read for a text preview. longer files are cut off
+pub const MAX_TEXT_BYTES: usize = 512 * 1024;
+/// larger images aren't previewed
+pub const MAX_IMAGE_BYTES: usize = 32 * 1024 * 1024;
+const HIGHLIGHT_THEME: &str = "base16-ocean.dark";
+// shown as plain text, without highlighting
+const TEXT_EXTENSIONS: [&str; 4] = ["txt", "log", "csv", "tsv"];
+
+static SYNTAXES: Lazy<SyntaxSet> = Lazy::new(SyntaxSet::load_defaults_newlines);
+static THEMES: Lazy<ThemeSet> = Lazy::new(ThemeSet::load_defaults);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PreviewKind {
+    /// the mime type of the data URL
+    Image(&'static str),
+    Markdown,
+    /// the extension, which picks the highlighting
+    Text(String),
+    /// no pure Rust PDF renderer is available, so PDFs can only be downloaded
+    Pdf,
+    Unsupported,
+}
+
+impl PreviewKind {
+    pub fn of(filename: &str) -> Self {
+        let extension = Path::new(filename)
+            .extension()
+            .and_then(OsStr::to_str)
+            .map(str::to_lowercase)
+            .unwrap_or_default();
+        match extension.as_str() {
+            "png" => PreviewKind::Image("image/png"),
+            "jpg" | "jpeg" => PreviewKind::Image("image/jpeg"),
+            "gif" => PreviewKind::Image("image/gif"),
+            "webp" => PreviewKind::Image("image/webp"),
+            "bmp" => PreviewKind::Image("image/bmp"),
+            // an SVG shown as an image can't run scripts
+            "svg" => PreviewKind::Image("image/svg+xml"),
+            "md" | "markdown" => PreviewKind::Markdown,
+            "pdf" => PreviewKind::Pdf,
+            _ if TEXT_EXTENSIONS.contains(&extension.as_str())
+                || SYNTAXES.find_syntax_by_extension(&extension).is_some() =>
+            {
+                PreviewKind::Text(extension)
+            }
+            _ => PreviewKind::Unsupported,
+        }
+    }
+
+    /// the most bytes of the file the preview needs
+    pub fn max_bytes(&self) -> usize {
+        match self {
+            PreviewKind::Image(_) => MAX_IMAGE_BYTES,
+            PreviewKind::Markdown | PreviewKind::Text(_) => MAX_TEXT_BYTES,
+            PreviewKind::Pdf | PreviewKind::Unsupported => 0,
+        }
+    }
+}
+
+pub fn image_data_url(mime: &str, bytes: &[u8]) -> String {
+    format!("data:{};base64,{}", mime, base64::encode(bytes))
+}
+
+/// the rendered Markdown, without the HTML blocks and tags written in it. images are shown as their alt text, so
+/// a file can't load anything remote. links are shown as their text, because following one would take the app
+/// window to the site. http(s) addresses are written after the text so they can be copied.
+pub fn markdown_html(text: &str) -> String {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_TASKLISTS);
+    // links can't be nested, so the address belongs at the next link end
+    let mut address: Option<String> = None;
+    let parser = Parser::new_ext(text, options).filter_map(move |event| match event {
+        Event::Html(_) => None,
+        Event::Start(Tag::Image(..)) | Event::End(Tag::Image(..)) => None,
+        Event::Start(Tag::Link(kind, destination, _)) => {
+            // the text of an autolink is the address already
+            address = (kind != LinkType::Autolink && is_web_link(&destination))
+                .then(|| format!(" ({})", destination.trim()));
+            None
+        }
+        Event::End(Tag::Link(..)) => address.take().map(|address| Event::Text(address.into())),
+        event => Some(event),
+    });
+    let mut html_output = String::with_capacity(text.len() * 3 / 2);
+    html::push_html(&mut html_output, parser);
+    html_output
+}
+
+fn is_web_link(destination: &str) -> bool {
+    let destination = destination.trim().to_lowercase();
+    destination.starts_with("https://") || destination.starts_with("http://")
+}
+
+/// the text in a `pre` block, highlighted by the syntax of `extension` when it is known
+pub fn highlighted_html(text: &str, extension: &str) -> String {
+    let syntax = SYNTAXES
+        .find_syntax_by_extension(extension)
+        .unwrap_or_else(|| SYNTAXES.find_syntax_plain_text());
+    match highlighted_html_for_string(text, &SYNTAXES, syntax, &THEMES.themes[HIGHLIGHT_THEME]) {
+        Ok(html) => html,
+        Err(e) => {
+            log::error!("failed to highlight the file: {}", e);
+            let mut escaped = String::from("<pre>");
+            // writing to a String can't fail
+            let _ = escape_html(&mut escaped, text);
+            escaped.push_str("</pre>");
+            escaped
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preview_kinds() {
+        assert_eq!(
+            PreviewKind::of("photo.JPG"),
+            PreviewKind::Image("image/jpeg")
+        );
+        assert_eq!(PreviewKind::of("README.md"), PreviewKind::Markdown);
+        assert_eq!(
+            PreviewKind::of("main.rs"),
+            PreviewKind::Text(String::from("rs"))
+        );
+        assert_eq!(
+            PreviewKind::of("notes.txt"),
+            PreviewKind::Text(String::from("txt"))
+        );
+        assert_eq!(PreviewKind::of("paper.pdf"), PreviewKind::Pdf);
+        assert_eq!(PreviewKind::of("archive"), PreviewKind::Unsupported);
+        assert_eq!(PreviewKind::Pdf.max_bytes(), 0);
+    }
+
+    #[test]
+    fn web_links_become_text_with_their_address() {
+        assert_eq!(
+            markdown_html("[site](https://satellite.im)"),
+            "<p>site (https://satellite.im)</p>\n"
+        );
+        assert_eq!(
+            markdown_html("<HTTP://satellite.im>"),
+            "<p>HTTP://satellite.im</p>\n"
+        );
+        assert_eq!(
+            markdown_html("[**bold** site](https://satellite.im \"title\")"),
+            "<p><strong>bold</strong> site (https://satellite.im)</p>\n"
+        );
+    }
+
+    #[test]
+    fn other_links_become_text() {
+        for link in [
+            "[click](javascript:alert(1))",
+            "[click]( JavaScript:alert(1))",
+            "[click](data:text/html;base64,PHNjcmlwdD4=)",
+            "[click](file:///etc/passwd)",
+            "[click](other.md)",
+        ] {
+            assert_eq!(markdown_html(link), "<p>click</p>\n", "{}", link);
+        }
+        let html = markdown_html(
+            "[click][x] and [site](https://satellite.im)\n\n[x]: javascript:alert(1)",
+        );
+        assert_eq!(html, "<p>click and site (https://satellite.im)</p>\n");
+    }
+
+    #[test]
+    fn images_become_text() {
+        assert_eq!(
+            markdown_html("![a cat](https://example.com/cat.png)"),
+            "<p>a cat</p>\n"
+        );
+        assert_eq!(
+            markdown_html("[![badge](https://example.com/b.svg)](https://satellite.im)"),
+            "<p>badge (https://satellite.im)</p>\n"
+        );
+    }
+
+    #[test]
+    fn html_is_dropped() {
+        let html = markdown_html("<script>alert(1)</script>\n\ntext <img src=x onerror=alert(1)>");
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("<img"));
+        assert!(html.contains("text"));
+    }
+}
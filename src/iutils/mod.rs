@@ -1,7 +1,15 @@
 pub mod config;
+pub mod file_preview;
 pub mod get_meta;
+pub mod notification_queue;
+pub mod notification_rules;
+pub mod request_filter;
+pub mod shortcuts;
 
-use crate::{state::ConversationInfo, Account};
+use crate::{
+    state::{contacts, ConversationInfo},
+    Account,
+};
 
 use chrono::{prelude::*, Duration};
 use regex::Regex;
@@ -26,7 +34,16 @@ pub fn get_username_from_conversation(
     (display_did, display_username)
 }
 
+/// the nickname given to `did`, or else their username
 pub fn get_username_from_did(did: DID, mp: &Account) -> String {
+    match contacts::nickname(&did.to_string()) {
+        Some(nickname) => nickname,
+        None => get_identity_username(did, mp),
+    }
+}
+
+/// the username chosen by the owner of `did`, ignoring nicknames. shown when hovering over a nickname
+pub fn get_identity_username(did: DID, mp: &Account) -> String {
     let display_user = warp::async_block_in_place_uncheck(mp.get_identity(did.into())).unwrap_or_default();
     display_user
         .first()
@@ -0,0 +1,320 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// the rate limit applies to this period
+const RATE_PERIOD: Duration = Duration::from_secs(60);
+
+/// how much of a message is shown in a notification
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationPrivacy {
+    /// the sender and the message
+    Full,
+    /// the sender but not the message
+    SenderOnly,
+    /// only "New message"
+    Hidden,
+}
+
+impl Default for NotificationPrivacy {
+    fn default() -> Self {
+        NotificationPrivacy::Full
+    }
+}
+
+impl NotificationPrivacy {
+    pub fn all() -> Vec<NotificationPrivacy> {
+        vec![
+            NotificationPrivacy::Full,
+            NotificationPrivacy::SenderOnly,
+            NotificationPrivacy::Hidden,
+        ]
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            NotificationPrivacy::Full => "Sender and Message",
+            NotificationPrivacy::SenderOnly => "Sender Only",
+            NotificationPrivacy::Hidden => "Nothing",
+        }
+        .to_string()
+    }
+}
+
+/// the messages received in a conversation which are shown as a single notification
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupedNotification {
+    pub conversation_id: Uuid,
+    /// in the order they first sent a message
+    pub senders: Vec<String>,
+    pub count: usize,
+    pub last_text: String,
+    first_at: Instant,
+}
+
+impl GroupedNotification {
+    /// returns (title, body)
+    pub fn format(&self, privacy: NotificationPrivacy) -> (String, String) {
+        let senders = join_names(&self.senders);
+        let new_messages = match self.count {
+            1 => String::from("New message"),
+            count => format!("{} new messages", count),
+        };
+        match (privacy, self.count) {
+            (NotificationPrivacy::Full, 1) => (senders, self.last_text.clone()),
+            (NotificationPrivacy::Full, _) => (
+                format!("{} from {}", new_messages, senders),
+                self.last_text.clone(),
+            ),
+            (NotificationPrivacy::SenderOnly, _) => (senders, new_messages),
+            (NotificationPrivacy::Hidden, _) => (new_messages, String::new()),
+        }
+    }
+}
+
+// "Alice", "Alice and Bob", "Alice, Bob and Carol"
+fn join_names(names: &[String]) -> String {
+    match names {
+        [] => String::new(),
+        [name] => name.clone(),
+        [rest @ .., last] => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+/// groups the messages of each conversation which arrive within `window` of the first one, and limits the number of
+/// notifications shown per minute. notifications held back by the limit keep collecting messages.
+pub struct NotificationQueue {
+    window: Duration,
+    max_per_minute: usize,
+    pending: Vec<GroupedNotification>,
+    sent: VecDeque<Instant>,
+}
+
+impl NotificationQueue {
+    pub fn new(window: Duration, max_per_minute: usize) -> Self {
+        Self {
+            window,
+            max_per_minute: max_per_minute.max(1),
+            pending: vec![],
+            sent: VecDeque::new(),
+        }
+    }
+
+    /// applies changed settings. groups already pending use the new window as well
+    pub fn set_limits(&mut self, window: Duration, max_per_minute: usize) {
+        self.window = window;
+        self.max_per_minute = max_per_minute.max(1);
+    }
+
+    pub fn push(&mut self, conversation_id: Uuid, sender: String, text: String, now: Instant) {
+        match self
+            .pending
+            .iter_mut()
+            .find(|group| group.conversation_id == conversation_id)
+        {
+            Some(group) => {
+                if !group.senders.contains(&sender) {
+                    group.senders.push(sender);
+                }
+                group.count += 1;
+                group.last_text = text;
+            }
+            None => self.pending.push(GroupedNotification {
+                conversation_id,
+                senders: vec![sender],
+                count: 1,
+                last_text: text,
+                first_at: now,
+            }),
+        }
+    }
+
+    fn forget_old(&mut self, now: Instant) {
+        while let Some(sent_at) = self.sent.front() {
+            if now.duration_since(*sent_at) < RATE_PERIOD {
+                break;
+            }
+            self.sent.pop_front();
+        }
+    }
+
+    /// how long until `take_due` returns something, or None if nothing is pending
+    pub fn next_due(&mut self, now: Instant) -> Option<Duration> {
+        self.forget_old(now);
+        let group_due = self
+            .pending
+            .iter()
+            .map(|group| (group.first_at + self.window).saturating_duration_since(now))
+            .min()?;
+        let slot_free = match self.sent.front() {
+            Some(oldest) if self.sent.len() >= self.max_per_minute => {
+                (*oldest + RATE_PERIOD).saturating_duration_since(now)
+            }
+            _ => Duration::ZERO,
+        };
+        Some(group_due.max(slot_free))
+    }
+
+    /// removes the groups whose window has passed, as far as the rate limit allows
+    pub fn take_due(&mut self, now: Instant) -> Vec<GroupedNotification> {
+        self.forget_old(now);
+        let mut due = vec![];
+        let mut idx = 0;
+        while idx < self.pending.len() && self.sent.len() < self.max_per_minute {
+            if now.duration_since(self.pending[idx].first_at) >= self.window {
+                due.push(self.pending.remove(idx));
+                self.sent.push_back(now);
+            } else {
+                idx += 1;
+            }
+        }
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: Duration = Duration::from_secs(5);
+
+    fn push(
+        queue: &mut NotificationQueue,
+        conversation_id: Uuid,
+        sender: &str,
+        text: &str,
+        now: Instant,
+    ) {
+        queue.push(conversation_id, sender.to_string(), text.to_string(), now);
+    }
+
+    #[test]
+    fn messages_are_grouped_by_conversation() {
+        let start = Instant::now();
+        let mut queue = NotificationQueue::new(WINDOW, 6);
+        let (chat, other) = (Uuid::new_v4(), Uuid::new_v4());
+        push(&mut queue, chat, "Alice", "hi", start);
+        push(
+            &mut queue,
+            chat,
+            "Bob",
+            "hey",
+            start + Duration::from_secs(1),
+        );
+        push(
+            &mut queue,
+            chat,
+            "Alice",
+            "there?",
+            start + Duration::from_secs(2),
+        );
+        push(
+            &mut queue,
+            other,
+            "Carol",
+            "yo",
+            start + Duration::from_secs(3),
+        );
+
+        assert!(queue.take_due(start + Duration::from_secs(4)).is_empty());
+        let due = queue.take_due(start + WINDOW);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].conversation_id, chat);
+        assert_eq!(due[0].senders, vec!["Alice", "Bob"]);
+        assert_eq!(due[0].count, 3);
+        assert_eq!(due[0].last_text, "there?");
+
+        // the other conversation's window started later
+        assert_eq!(queue.next_due(start + WINDOW), Some(Duration::from_secs(3)));
+        let due = queue.take_due(start + Duration::from_secs(8));
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].conversation_id, other);
+        assert_eq!(queue.next_due(start + Duration::from_secs(8)), None);
+    }
+
+    #[test]
+    fn the_rate_limit_holds_notifications_back() {
+        let start = Instant::now();
+        let mut queue = NotificationQueue::new(Duration::ZERO, 2);
+        for _ in 0..3 {
+            push(&mut queue, Uuid::new_v4(), "Alice", "hi", start);
+        }
+        assert_eq!(queue.take_due(start).len(), 2);
+        // the third waits until the first was sent a minute ago
+        assert_eq!(queue.next_due(start), Some(RATE_PERIOD));
+        assert!(queue.take_due(start + Duration::from_secs(59)).is_empty());
+
+        // and keeps collecting messages meanwhile
+        let held = queue.pending[0].conversation_id;
+        push(
+            &mut queue,
+            held,
+            "Bob",
+            "hello",
+            start + Duration::from_secs(30),
+        );
+        let due = queue.take_due(start + RATE_PERIOD);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].count, 2);
+    }
+
+    #[test]
+    fn changed_limits_apply_to_pending_groups() {
+        let start = Instant::now();
+        let mut queue = NotificationQueue::new(Duration::from_secs(30), 1);
+        push(&mut queue, Uuid::new_v4(), "Alice", "hi", start);
+        push(&mut queue, Uuid::new_v4(), "Bob", "hi", start);
+        assert!(queue.take_due(start + WINDOW).is_empty());
+
+        queue.set_limits(WINDOW, 2);
+        assert_eq!(queue.take_due(start + WINDOW).len(), 2);
+    }
+
+    #[test]
+    fn at_least_one_notification_per_minute() {
+        let start = Instant::now();
+        let mut queue = NotificationQueue::new(Duration::ZERO, 0);
+        push(&mut queue, Uuid::new_v4(), "Alice", "hi", start);
+        assert_eq!(queue.take_due(start).len(), 1);
+    }
+
+    #[test]
+    fn format_follows_the_privacy_setting() {
+        let start = Instant::now();
+        let mut queue = NotificationQueue::new(Duration::ZERO, 6);
+        let chat = Uuid::new_v4();
+        push(&mut queue, chat, "Alice", "hi", start);
+        let single = queue.take_due(start).remove(0);
+        assert_eq!(
+            single.format(NotificationPrivacy::Full),
+            (String::from("Alice"), String::from("hi"))
+        );
+
+        for sender in ["Alice", "Bob", "Carol"] {
+            push(&mut queue, chat, sender, "bye", start);
+        }
+        let grouped = queue.take_due(start).remove(0);
+        assert_eq!(
+            grouped.format(NotificationPrivacy::Full),
+            (
+                String::from("3 new messages from Alice, Bob and Carol"),
+                String::from("bye")
+            )
+        );
+        assert_eq!(
+            grouped.format(NotificationPrivacy::SenderOnly),
+            (
+                String::from("Alice, Bob and Carol"),
+                String::from("3 new messages")
+            )
+        );
+        assert_eq!(
+            grouped.format(NotificationPrivacy::Hidden),
+            (String::from("3 new messages"), String::new())
+        );
+    }
+}
@@ -0,0 +1,537 @@
+use std::collections::HashMap;
+
+use chrono::{prelude::*, NaiveTime};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::notification_queue::NotificationPrivacy;
+use crate::state::presence::Presence;
+
+/// how notifications from a single conversation are handled
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ConversationRule {
+    /// follow the global rules
+    Default,
+    /// notify for every message, even during quiet hours or while busy
+    All,
+    /// only notify when mentioned or when a keyword matches
+    MentionsOnly,
+    /// never notify
+    Muted,
+}
+
+impl Default for ConversationRule {
+    fn default() -> Self {
+        ConversationRule::Default
+    }
+}
+
+impl ConversationRule {
+    pub fn all() -> Vec<ConversationRule> {
+        vec![
+            ConversationRule::Default,
+            ConversationRule::All,
+            ConversationRule::MentionsOnly,
+            ConversationRule::Muted,
+        ]
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            ConversationRule::Default => "Default",
+            ConversationRule::All => "All Messages",
+            ConversationRule::MentionsOnly => "Mentions Only",
+            ConversationRule::Muted => "Muted",
+        }
+        .to_string()
+    }
+}
+
+/// a daily window during which notifications are suppressed. times are stored as "HH:MM" in local time.
+/// the window wraps around midnight when `end` is before `start`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct QuietHours {
+    pub enabled: bool,
+    pub start: String,
+    pub end: String,
+    /// the days on which the window starts, such as "Mon". empty means every day
+    #[serde(default)]
+    pub days: Vec<String>,
+}
+
+impl Default for QuietHours {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            start: String::from("22:00"),
+            end: String::from("07:00"),
+            days: vec![],
+        }
+    }
+}
+
+pub fn parse_time(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M").ok()
+}
+
+impl QuietHours {
+    pub fn is_valid(&self) -> bool {
+        parse_time(&self.start).is_some() && parse_time(&self.end).is_some()
+    }
+
+    /// an invalid schedule is treated as disabled so that a typo can't silence everything
+    pub fn contains(&self, now: NaiveDateTime) -> bool {
+        let (start, end) = match (parse_time(&self.start), parse_time(&self.end)) {
+            (Some(start), Some(end)) if self.enabled && start != end => (start, end),
+            _ => return false,
+        };
+        let time = now.time();
+        let date = now.date();
+        // the day on which the current window started
+        let window_day = if start < end {
+            if time < start || time >= end {
+                return false;
+            }
+            date
+        } else if time >= start {
+            date
+        } else if time < end {
+            match date.pred_opt() {
+                Some(day) => day,
+                None => return false,
+            }
+        } else {
+            return false;
+        };
+        self.days.is_empty()
+            || self
+                .days
+                .iter()
+                .filter_map(|day| day.parse::<Weekday>().ok())
+                .any(|day| day == window_day.weekday())
+    }
+}
+
+/// the notification rules, stored in the config
+// missing fields fall back to their default so that new rules don't invalidate existing configs
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct NotificationRules {
+    /// suppresses everything except mentions and keywords (when they are allowed to break through)
+    pub do_not_disturb: bool,
+    /// messages containing one of these words always notify, unless the conversation is muted
+    pub keywords: Vec<String>,
+    /// mentions break through do not disturb, quiet hours and the presence policy
+    pub mentions_always_notify: bool,
+    /// notifications are suppressed while the presence is one of these
+    pub suppress_while: Vec<Presence>,
+    /// how much of a message is shown
+    pub privacy: NotificationPrivacy,
+    /// messages from a conversation arriving within this many seconds of each other are shown as one notification
+    pub coalesce_seconds: u64,
+    pub max_per_minute: usize,
+    // TOML needs the tables after the plain values
+    pub quiet_hours: QuietHours,
+    pub conversations: HashMap<Uuid, ConversationRule>,
+}
+
+impl Default for NotificationRules {
+    fn default() -> Self {
+        Self {
+            do_not_disturb: false,
+            keywords: vec![],
+            mentions_always_notify: true,
+            suppress_while: vec![Presence::Busy],
+            privacy: NotificationPrivacy::default(),
+            coalesce_seconds: 5,
+            max_per_minute: 6,
+            quiet_hours: QuietHours::default(),
+            conversations: HashMap::new(),
+        }
+    }
+}
+
+impl NotificationRules {
+    pub fn conversation_rule(&self, conversation_id: Uuid) -> ConversationRule {
+        self.conversations
+            .get(&conversation_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// `ConversationRule::Default` is not stored
+    pub fn set_conversation_rule(&mut self, conversation_id: Uuid, rule: ConversationRule) {
+        if rule == ConversationRule::Default {
+            self.conversations.remove(&conversation_id);
+        } else {
+            self.conversations.insert(conversation_id, rule);
+        }
+    }
+
+    /// the first keyword found in `text`, ignoring case
+    pub fn matching_keyword(&self, text: &str) -> Option<&str> {
+        let text = text.to_lowercase();
+        self.keywords
+            .iter()
+            .map(|keyword| keyword.trim())
+            .filter(|keyword| !keyword.is_empty())
+            .find(|keyword| text.contains(&keyword.to_lowercase()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    Message,
+    FriendRequest,
+}
+
+/// something which may produce a notification
+#[derive(Debug, Clone)]
+pub struct NotificationEvent<'a> {
+    pub kind: EventKind,
+    /// the conversation a message belongs to
+    pub conversation_id: Option<Uuid>,
+    pub text: &'a str,
+    /// whether the text mentions the local user
+    pub mentions_me: bool,
+    /// the local user's presence when the event arrived
+    pub presence: Presence,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SuppressReason {
+    ConversationMuted,
+    DoNotDisturb,
+    MentionsOnly,
+    QuietHours,
+    Presence(Presence),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Notify,
+    Suppress(SuppressReason),
+}
+
+impl Decision {
+    pub fn should_notify(&self) -> bool {
+        *self == Decision::Notify
+    }
+}
+
+/// decides whether `event` produces a notification. the rules are applied in this order:
+/// 1. a muted conversation never notifies
+/// 2. mentions (if `mentions_always_notify` is set) and keywords always notify
+/// 3. do not disturb suppresses everything else
+/// 4. a conversation set to all messages notifies
+/// 5. a conversation set to mentions only is suppressed unless the user is mentioned
+/// 6. quiet hours and the presence policy suppress the rest
+pub fn evaluate(
+    event: &NotificationEvent,
+    rules: &NotificationRules,
+    now: NaiveDateTime,
+) -> Decision {
+    let conversation_rule = event
+        .conversation_id
+        .map(|id| rules.conversation_rule(id))
+        .unwrap_or_default();
+
+    if conversation_rule == ConversationRule::Muted {
+        return Decision::Suppress(SuppressReason::ConversationMuted);
+    }
+
+    let is_message = event.kind == EventKind::Message;
+    let mentioned = is_message && event.mentions_me && rules.mentions_always_notify;
+    let keyword = is_message && rules.matching_keyword(event.text).is_some();
+    if mentioned || keyword {
+        return Decision::Notify;
+    }
+
+    if rules.do_not_disturb {
+        return Decision::Suppress(SuppressReason::DoNotDisturb);
+    }
+
+    match conversation_rule {
+        ConversationRule::All => return Decision::Notify,
+        ConversationRule::MentionsOnly if !event.mentions_me => {
+            return Decision::Suppress(SuppressReason::MentionsOnly)
+        }
+        _ => {}
+    }
+
+    if rules.quiet_hours.contains(now) {
+        return Decision::Suppress(SuppressReason::QuietHours);
+    }
+
+    if rules.suppress_while.contains(&event.presence) {
+        return Decision::Suppress(SuppressReason::Presence(event.presence));
+    }
+
+    Decision::Notify
+}
+
+/// whether `text` contains `@username`, ignoring case
+pub fn mentions(text: &str, username: &str) -> bool {
+    if username.is_empty() {
+        return false;
+    }
+    let text = text.to_lowercase();
+    let mention = format!("@{}", username.to_lowercase());
+    text.match_indices(&mention).any(|(idx, _)| {
+        // "@bob" should not match "@bobby"
+        !text[idx + mention.len()..]
+            .chars()
+            .next()
+            .map(|c| c.is_alphanumeric() || c == '_')
+            .unwrap_or(false)
+    })
+}
+
+/// evaluates `event` against `rules` at the current local time
+pub fn should_notify(event: &NotificationEvent, rules: &NotificationRules) -> bool {
+    let decision = evaluate(event, rules, Local::now().naive_local());
+    if let Decision::Suppress(reason) = decision {
+        log::debug!("notification suppressed: {:?}", reason);
+    }
+    decision.should_notify()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Monday 7 November 2022
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2022, 11, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    fn message(conversation_id: Uuid, text: &str, mentions_me: bool) -> NotificationEvent {
+        NotificationEvent {
+            kind: EventKind::Message,
+            conversation_id: Some(conversation_id),
+            text,
+            mentions_me,
+            presence: Presence::Online,
+        }
+    }
+
+    fn quiet(start: &str, end: &str, days: &[&str]) -> QuietHours {
+        QuietHours {
+            enabled: true,
+            start: start.to_string(),
+            end: end.to_string(),
+            days: days.iter().map(|day| day.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn quiet_hours() {
+        let cases = [
+            // (window, now, expected)
+            (quiet("09:00", "17:00", &[]), at(7, 12, 0), true),
+            (quiet("09:00", "17:00", &[]), at(7, 17, 0), false),
+            (quiet("09:00", "17:00", &[]), at(7, 8, 59), false),
+            (quiet("22:00", "07:00", &[]), at(7, 23, 0), true),
+            (quiet("22:00", "07:00", &[]), at(8, 6, 59), true),
+            (quiet("22:00", "07:00", &[]), at(8, 7, 0), false),
+            (quiet("22:00", "07:00", &[]), at(8, 12, 0), false),
+            // Tuesday morning belongs to the window which started on Monday
+            (quiet("22:00", "07:00", &["Mon"]), at(8, 3, 0), true),
+            (quiet("22:00", "07:00", &["Tue"]), at(8, 3, 0), false),
+            (quiet("22:00", "07:00", &["Tue"]), at(8, 23, 0), true),
+            // a window from the 1st of the month starts on the last day of the month before
+            (quiet("22:00", "07:00", &["Mon"]), at(1, 3, 0), true),
+            (quiet("22:00", "07:00", &["Tue"]), at(1, 3, 0), false),
+            (quiet("22:00", "22:00", &[]), at(7, 22, 0), false),
+            (quiet("25:00", "07:00", &[]), at(7, 3, 0), false),
+        ];
+        for (idx, (window, now, expected)) in cases.into_iter().enumerate() {
+            assert_eq!(window.contains(now), expected, "case {}", idx);
+        }
+        let mut disabled = quiet("00:00", "23:59", &[]);
+        disabled.enabled = false;
+        assert!(!disabled.contains(at(7, 12, 0)));
+    }
+
+    #[test]
+    fn evaluate_rules() {
+        let chat = Uuid::new_v4();
+        let default = NotificationRules::default();
+        let dnd = NotificationRules {
+            do_not_disturb: true,
+            ..NotificationRules::default()
+        };
+        let dnd_without_mentions = NotificationRules {
+            do_not_disturb: true,
+            mentions_always_notify: false,
+            ..NotificationRules::default()
+        };
+        let keywords = NotificationRules {
+            do_not_disturb: true,
+            keywords: vec![String::from(" Deploy "), String::new()],
+            ..NotificationRules::default()
+        };
+        let night = NotificationRules {
+            quiet_hours: quiet("22:00", "07:00", &[]),
+            ..NotificationRules::default()
+        };
+        let with_rule = |rule| {
+            let mut rules = night.clone();
+            rules.set_conversation_rule(chat, rule);
+            rules
+        };
+        let (muted, all, mentions_only) = (
+            with_rule(ConversationRule::Muted),
+            with_rule(ConversationRule::All),
+            with_rule(ConversationRule::MentionsOnly),
+        );
+        let day = at(7, 12, 0);
+        let midnight = at(8, 0, 30);
+        let notify = Decision::Notify;
+        let suppress = Decision::Suppress;
+
+        let cases = [
+            // (rules, text, mentions me, now, expected)
+            (&default, "hi", false, day, notify),
+            (
+                &dnd,
+                "hi",
+                false,
+                day,
+                suppress(SuppressReason::DoNotDisturb),
+            ),
+            (&dnd, "hi @me", true, day, notify),
+            (
+                &dnd_without_mentions,
+                "hi @me",
+                true,
+                day,
+                suppress(SuppressReason::DoNotDisturb),
+            ),
+            (&keywords, "the deploy failed", false, day, notify),
+            (
+                &keywords,
+                "the build failed",
+                false,
+                day,
+                suppress(SuppressReason::DoNotDisturb),
+            ),
+            (
+                &night,
+                "hi",
+                false,
+                midnight,
+                suppress(SuppressReason::QuietHours),
+            ),
+            (&night, "hi", false, day, notify),
+            (&night, "hi @me", true, midnight, notify),
+            (
+                &muted,
+                "hi @me",
+                true,
+                day,
+                suppress(SuppressReason::ConversationMuted),
+            ),
+            (&all, "hi", false, midnight, notify),
+            (
+                &mentions_only,
+                "hi",
+                false,
+                day,
+                suppress(SuppressReason::MentionsOnly),
+            ),
+            (&mentions_only, "hi @me", true, day, notify),
+        ];
+        for (idx, (rules, text, mentions_me, now, expected)) in cases.into_iter().enumerate() {
+            let event = message(chat, text, mentions_me);
+            assert_eq!(evaluate(&event, rules, now), expected, "case {}", idx);
+        }
+    }
+
+    #[test]
+    fn conversation_rules_only_apply_to_their_conversation() {
+        let mut rules = NotificationRules::default();
+        rules.set_conversation_rule(Uuid::new_v4(), ConversationRule::Muted);
+        let event = message(Uuid::new_v4(), "hi", false);
+        assert_eq!(evaluate(&event, &rules, at(7, 12, 0)), Decision::Notify);
+    }
+
+    #[test]
+    fn muted_keywords_are_suppressed() {
+        let chat = Uuid::new_v4();
+        let mut rules = NotificationRules {
+            keywords: vec![String::from("deploy")],
+            ..NotificationRules::default()
+        };
+        rules.set_conversation_rule(chat, ConversationRule::Muted);
+        assert_eq!(
+            evaluate(&message(chat, "deploy", false), &rules, at(7, 12, 0)),
+            Decision::Suppress(SuppressReason::ConversationMuted)
+        );
+    }
+
+    #[test]
+    fn presence() {
+        let rules = NotificationRules {
+            suppress_while: vec![Presence::Busy, Presence::Away],
+            ..NotificationRules::default()
+        };
+        let cases = [
+            (Presence::Online, false, Decision::Notify),
+            (
+                Presence::Busy,
+                false,
+                Decision::Suppress(SuppressReason::Presence(Presence::Busy)),
+            ),
+            (
+                Presence::Away,
+                false,
+                Decision::Suppress(SuppressReason::Presence(Presence::Away)),
+            ),
+            (Presence::Invisible, false, Decision::Notify),
+            (Presence::Busy, true, Decision::Notify),
+        ];
+        for (presence, mentions_me, expected) in cases {
+            let event = NotificationEvent {
+                presence,
+                ..message(Uuid::new_v4(), "hi", mentions_me)
+            };
+            assert_eq!(
+                evaluate(&event, &rules, at(7, 12, 0)),
+                expected,
+                "{:?}",
+                presence
+            );
+        }
+    }
+
+    #[test]
+    fn friend_requests_ignore_message_rules() {
+        let rules = NotificationRules {
+            do_not_disturb: true,
+            keywords: vec![String::from("deploy")],
+            ..NotificationRules::default()
+        };
+        let event = NotificationEvent {
+            kind: EventKind::FriendRequest,
+            conversation_id: None,
+            text: "deploy",
+            mentions_me: true,
+            presence: Presence::Online,
+        };
+        assert_eq!(
+            evaluate(&event, &rules, at(7, 12, 0)),
+            Decision::Suppress(SuppressReason::DoNotDisturb)
+        );
+    }
+
+    #[test]
+    fn mentions_match_whole_names() {
+        assert!(mentions("hey @Bob!", "bob"));
+        assert!(!mentions("hey @bobby", "bob"));
+        assert!(!mentions("hey @bob", ""));
+    }
+}
@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+
+/// rules applied to incoming friend requests, stored in the config
+// missing fields fall back to their default so that new rules don't invalidate existing configs
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct RequestFilterRules {
+    /// decline requests from DIDs whose identity can't be looked up
+    pub decline_unknown: bool,
+    /// decline a sender's requests after this many within a day, such as when they are denied and sent again.
+    /// 0 turns the limit off
+    pub max_per_sender_per_day: u32,
+    /// decline requests from usernames containing one of these words, ignoring case
+    pub keywords: Vec<String>,
+}
+
+impl Default for RequestFilterRules {
+    fn default() -> Self {
+        Self {
+            decline_unknown: false,
+            max_per_sender_per_day: 3,
+            keywords: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterReason {
+    UnknownUsername,
+    RateLimited,
+    Keyword(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RequestDecision {
+    Allow,
+    /// hide the request without telling the sender
+    Ignore,
+    Decline(FilterReason),
+}
+
+/// an incoming friend request
+pub struct IncomingRequest<'a> {
+    /// None if the sender's identity couldn't be looked up
+    pub username: Option<&'a str>,
+    /// the number of requests from the sender in the last day, including this one
+    pub recent_requests: usize,
+    /// whether the user chose to silently ignore the sender
+    pub ignored: bool,
+}
+
+/// ignoring a sender takes precedence over the filters, since a declined sender learns that they were declined
+pub fn evaluate(request: &IncomingRequest, rules: &RequestFilterRules) -> RequestDecision {
+    if request.ignored {
+        return RequestDecision::Ignore;
+    }
+    let username = match request.username.filter(|username| !username.is_empty()) {
+        Some(username) => username.to_lowercase(),
+        None if rules.decline_unknown => {
+            return RequestDecision::Decline(FilterReason::UnknownUsername)
+        }
+        None => String::new(),
+    };
+    if rules.max_per_sender_per_day > 0
+        && request.recent_requests > rules.max_per_sender_per_day as usize
+    {
+        return RequestDecision::Decline(FilterReason::RateLimited);
+    }
+    let keyword = rules
+        .keywords
+        .iter()
+        .map(|keyword| keyword.trim())
+        .filter(|keyword| !keyword.is_empty())
+        .find(|keyword| username.contains(&keyword.to_lowercase()));
+    match keyword {
+        Some(keyword) => RequestDecision::Decline(FilterReason::Keyword(keyword.to_string())),
+        None => RequestDecision::Allow,
+    }
+}
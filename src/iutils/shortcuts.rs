@@ -0,0 +1,386 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// everything which can be triggered from the keyboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShortcutAction {
+    NextChat,
+    PreviousChat,
+    JumpToUnread,
+    FocusComposer,
+    OpenFiles,
+    OpenFriends,
+    OpenSettings,
+    MarkAllRead,
+    Search,
+    LockAccount,
+    CommandPalette,
+}
+
+impl ShortcutAction {
+    pub fn all() -> Vec<ShortcutAction> {
+        vec![
+            ShortcutAction::NextChat,
+            ShortcutAction::PreviousChat,
+            ShortcutAction::JumpToUnread,
+            ShortcutAction::FocusComposer,
+            ShortcutAction::OpenFiles,
+            ShortcutAction::OpenFriends,
+            ShortcutAction::OpenSettings,
+            ShortcutAction::MarkAllRead,
+            ShortcutAction::Search,
+            ShortcutAction::LockAccount,
+            ShortcutAction::CommandPalette,
+        ]
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            ShortcutAction::NextChat => "Next Chat",
+            ShortcutAction::PreviousChat => "Previous Chat",
+            ShortcutAction::JumpToUnread => "Jump to Unread",
+            ShortcutAction::FocusComposer => "Focus Composer",
+            ShortcutAction::OpenFiles => "Open Files",
+            ShortcutAction::OpenFriends => "Open Friends",
+            ShortcutAction::OpenSettings => "Open Settings",
+            ShortcutAction::MarkAllRead => "Mark All Read",
+            ShortcutAction::Search => "Search Chats",
+            ShortcutAction::LockAccount => "Lock Account",
+            ShortcutAction::CommandPalette => "Command Palette",
+        }
+        .to_string()
+    }
+}
+
+/// a key combined with modifiers. stored in the config as a string such as "Ctrl+Shift+L"
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct KeyBinding {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub meta: bool,
+    pub key: String,
+}
+
+impl KeyBinding {
+    pub fn new(ctrl: bool, alt: bool, shift: bool, meta: bool, key: &str) -> Self {
+        Self {
+            ctrl,
+            alt,
+            shift,
+            meta,
+            key: normalize_key(key),
+        }
+    }
+
+    /// returns None if only a modifier was pressed
+    pub fn from_key_event(
+        ctrl: bool,
+        alt: bool,
+        shift: bool,
+        meta: bool,
+        key: &str,
+    ) -> Option<Self> {
+        match key {
+            "Control" | "Alt" | "Shift" | "Meta" | "" => None,
+            _ => Some(Self::new(ctrl, alt, shift, meta, key)),
+        }
+    }
+
+    pub fn matches(&self, ctrl: bool, alt: bool, shift: bool, meta: bool, key: &str) -> bool {
+        self.ctrl == ctrl
+            && self.alt == alt
+            && self.shift == shift
+            && self.meta == meta
+            && self.key == normalize_key(key)
+    }
+
+    /// shortcuts are handled while the user is typing, so plain keys would get in the way.
+    pub fn is_valid(&self) -> bool {
+        let is_special_key = self.key == "Escape"
+            || (self.key.starts_with('F') && self.key[1..].parse::<u8>().is_ok());
+        self.ctrl || self.alt || self.meta || is_special_key
+    }
+}
+
+// letters are stored in upper case since holding shift changes the reported key
+fn normalize_key(key: &str) -> String {
+    if key.chars().count() == 1 {
+        key.to_uppercase()
+    } else {
+        key.to_string()
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = vec![];
+        if self.ctrl {
+            parts.push("Ctrl");
+        }
+        if self.alt {
+            parts.push("Alt");
+        }
+        if self.shift {
+            parts.push("Shift");
+        }
+        if self.meta {
+            parts.push("Meta");
+        }
+        parts.push(&self.key);
+        write!(f, "{}", parts.join("+"))
+    }
+}
+
+impl TryFrom<String> for KeyBinding {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let mut parts: Vec<&str> = value.split('+').collect();
+        let key = match parts.pop() {
+            Some(key) if !key.is_empty() => key,
+            _ => return Err(format!("invalid key binding: {}", value)),
+        };
+        let mut binding = KeyBinding::new(false, false, false, false, key);
+        for modifier in parts {
+            match modifier {
+                "Ctrl" => binding.ctrl = true,
+                "Alt" => binding.alt = true,
+                "Shift" => binding.shift = true,
+                "Meta" => binding.meta = true,
+                _ => return Err(format!("invalid modifier in key binding: {}", value)),
+            }
+        }
+        Ok(binding)
+    }
+}
+
+impl From<KeyBinding> for String {
+    fn from(binding: KeyBinding) -> Self {
+        binding.to_string()
+    }
+}
+
+/// the key binding for every `ShortcutAction`
+// missing bindings fall back to their default so that new actions don't invalidate existing configs
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct Keybinds {
+    pub next_chat: KeyBinding,
+    pub previous_chat: KeyBinding,
+    pub jump_to_unread: KeyBinding,
+    pub focus_composer: KeyBinding,
+    pub open_files: KeyBinding,
+    pub open_friends: KeyBinding,
+    pub open_settings: KeyBinding,
+    pub mark_all_read: KeyBinding,
+    pub search: KeyBinding,
+    pub lock_account: KeyBinding,
+    pub command_palette: KeyBinding,
+}
+
+impl Default for Keybinds {
+    fn default() -> Self {
+        Self {
+            next_chat: KeyBinding::new(false, true, false, false, "ArrowDown"),
+            previous_chat: KeyBinding::new(false, true, false, false, "ArrowUp"),
+            jump_to_unread: KeyBinding::new(false, true, true, false, "ArrowDown"),
+            focus_composer: KeyBinding::new(true, false, false, false, "E"),
+            open_files: KeyBinding::new(true, false, true, false, "F"),
+            open_friends: KeyBinding::new(true, false, true, false, "U"),
+            open_settings: KeyBinding::new(true, false, false, false, ","),
+            mark_all_read: KeyBinding::new(false, false, true, false, "Escape"),
+            search: KeyBinding::new(true, false, false, false, "F"),
+            lock_account: KeyBinding::new(true, false, true, false, "L"),
+            command_palette: KeyBinding::new(true, false, false, false, "K"),
+        }
+    }
+}
+
+impl Keybinds {
+    pub fn get(&self, action: ShortcutAction) -> &KeyBinding {
+        match action {
+            ShortcutAction::NextChat => &self.next_chat,
+            ShortcutAction::PreviousChat => &self.previous_chat,
+            ShortcutAction::JumpToUnread => &self.jump_to_unread,
+            ShortcutAction::FocusComposer => &self.focus_composer,
+            ShortcutAction::OpenFiles => &self.open_files,
+            ShortcutAction::OpenFriends => &self.open_friends,
+            ShortcutAction::OpenSettings => &self.open_settings,
+            ShortcutAction::MarkAllRead => &self.mark_all_read,
+            ShortcutAction::Search => &self.search,
+            ShortcutAction::LockAccount => &self.lock_account,
+            ShortcutAction::CommandPalette => &self.command_palette,
+        }
+    }
+
+    fn get_mut(&mut self, action: ShortcutAction) -> &mut KeyBinding {
+        match action {
+            ShortcutAction::NextChat => &mut self.next_chat,
+            ShortcutAction::PreviousChat => &mut self.previous_chat,
+            ShortcutAction::JumpToUnread => &mut self.jump_to_unread,
+            ShortcutAction::FocusComposer => &mut self.focus_composer,
+            ShortcutAction::OpenFiles => &mut self.open_files,
+            ShortcutAction::OpenFriends => &mut self.open_friends,
+            ShortcutAction::OpenSettings => &mut self.open_settings,
+            ShortcutAction::MarkAllRead => &mut self.mark_all_read,
+            ShortcutAction::Search => &mut self.search,
+            ShortcutAction::LockAccount => &mut self.lock_account,
+            ShortcutAction::CommandPalette => &mut self.command_palette,
+        }
+    }
+
+    /// returns the action which is already bound to `binding`, if any
+    pub fn conflict(&self, action: ShortcutAction, binding: &KeyBinding) -> Option<ShortcutAction> {
+        ShortcutAction::all()
+            .into_iter()
+            .filter(|other| *other != action)
+            .find(|other| self.get(*other) == binding)
+    }
+
+    pub fn set(&mut self, action: ShortcutAction, binding: KeyBinding) -> Result<(), String> {
+        if !binding.is_valid() {
+            return Err(String::from(
+                "Shortcuts need a modifier key (Ctrl, Alt or Meta) unless they use Escape or a function key.",
+            ));
+        }
+        if let Some(other) = self.conflict(action, &binding) {
+            return Err(format!("{} is already used by {}.", binding, other.label()));
+        }
+        *self.get_mut(action) = binding;
+        Ok(())
+    }
+
+    /// finds the action bound to a key event
+    pub fn action_for(
+        &self,
+        ctrl: bool,
+        alt: bool,
+        shift: bool,
+        meta: bool,
+        key: &str,
+    ) -> Option<ShortcutAction> {
+        ShortcutAction::all()
+            .into_iter()
+            .find(|action| self.get(*action).matches(ctrl, alt, shift, meta, key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(value: &str) -> Result<KeyBinding, String> {
+        KeyBinding::try_from(value.to_string())
+    }
+
+    #[test]
+    fn parse_bindings() {
+        assert_eq!(
+            parse("Ctrl+Shift+l"),
+            Ok(KeyBinding::new(true, false, true, false, "L"))
+        );
+        assert_eq!(
+            parse("Alt+ArrowDown"),
+            Ok(KeyBinding::new(false, true, false, false, "ArrowDown"))
+        );
+        assert_eq!(
+            parse("Meta+,"),
+            Ok(KeyBinding::new(false, false, false, true, ","))
+        );
+        assert_eq!(
+            parse("Escape"),
+            Ok(KeyBinding::new(false, false, false, false, "Escape"))
+        );
+        assert!(parse("").is_err());
+        assert!(parse("Ctrl+").is_err());
+        assert!(parse("Hyper+K").is_err());
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for action in ShortcutAction::all() {
+            let binding = Keybinds::default().get(action).clone();
+            assert_eq!(parse(&binding.to_string()), Ok(binding));
+        }
+    }
+
+    #[test]
+    fn matches_ignore_letter_case() {
+        let binding = KeyBinding::new(true, false, true, false, "L");
+        assert!(binding.matches(true, false, true, false, "l"));
+        assert!(!binding.matches(true, false, false, false, "L"));
+    }
+
+    #[test]
+    fn modifiers_alone_are_not_bindings() {
+        assert_eq!(
+            KeyBinding::from_key_event(true, false, false, false, "Control"),
+            None
+        );
+        assert!(KeyBinding::from_key_event(true, false, false, false, "K").is_some());
+    }
+
+    #[test]
+    fn valid_bindings() {
+        assert!(KeyBinding::new(true, false, false, false, "K").is_valid());
+        assert!(KeyBinding::new(false, false, false, false, "F5").is_valid());
+        assert!(KeyBinding::new(false, false, true, false, "Escape").is_valid());
+        assert!(!KeyBinding::new(false, false, true, false, "K").is_valid());
+        assert!(!KeyBinding::new(false, false, false, false, "Fn").is_valid());
+    }
+
+    #[test]
+    fn default_bindings_do_not_conflict() {
+        let keybinds = Keybinds::default();
+        for action in ShortcutAction::all() {
+            assert_eq!(keybinds.conflict(action, keybinds.get(action)), None);
+        }
+    }
+
+    #[test]
+    fn conflicting_binding_is_rejected() {
+        let mut keybinds = Keybinds::default();
+        let search = keybinds.search.clone();
+        assert_eq!(
+            keybinds.conflict(ShortcutAction::OpenFiles, &search),
+            Some(ShortcutAction::Search)
+        );
+        assert!(keybinds
+            .set(ShortcutAction::OpenFiles, search.clone())
+            .is_err());
+        assert_eq!(keybinds, Keybinds::default());
+        // rebinding an action to its own binding is not a conflict
+        assert!(keybinds.set(ShortcutAction::Search, search).is_ok());
+    }
+
+    #[test]
+    fn rebinding() {
+        let mut keybinds = Keybinds::default();
+        let binding = KeyBinding::new(true, true, false, false, "N");
+        keybinds
+            .set(ShortcutAction::NextChat, binding.clone())
+            .unwrap();
+        assert_eq!(
+            keybinds.action_for(true, true, false, false, "n"),
+            Some(ShortcutAction::NextChat)
+        );
+        assert_eq!(
+            keybinds.action_for(false, true, false, false, "ArrowDown"),
+            None
+        );
+    }
+
+    // configs written before an action existed don't have a binding for it
+    #[test]
+    fn missing_bindings_use_the_default() {
+        let keybinds: Keybinds = toml::from_str("next_chat = \"Ctrl+N\"").unwrap();
+        assert_eq!(
+            keybinds.next_chat,
+            KeyBinding::new(true, false, false, false, "N")
+        );
+        assert_eq!(keybinds.search, Keybinds::default().search);
+    }
+}
@@ -33,6 +33,14 @@ pub fn make() -> Language {
         code_copied: String::from("Copied your code!"),
         your_friends: String::from("Your Friends"),
         copied_code: String::from("Friend code copied!"),
+        save_qr_code: String::from("Save QR Code"),
+        add_from_image: String::from("Add From Image"),
+        paste_qr_image: String::from("Paste QR Image"),
+        no_qr_code: String::from("No friend code found in the image"),
+        intro_note_placeholder: String::from("Add a note, sent when they accept (optional)"),
+        intro_note_pending: String::from("Sent after acceptance"),
+        filtered_requests: String::from("requests filtered"),
+        ignored_requests: String::from("Ignored requests"),
         add_someone: String::from("Add Someone"),
         add_placeholder: String::from("Warp#a3fdc6.."),
         request_sent: String::from("Friend request sent!"),
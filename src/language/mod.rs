@@ -33,6 +33,14 @@ pub struct Language {
     pub copy_code: String,
     pub code_copied: String,
     pub copied_code: String,
+    pub save_qr_code: String,
+    pub add_from_image: String,
+    pub paste_qr_image: String,
+    pub no_qr_code: String,
+    pub intro_note_placeholder: String,
+    pub intro_note_pending: String,
+    pub filtered_requests: String,
+    pub ignored_requests: String,
     pub add_someone: String,
     pub add_placeholder: String,
     pub invalid_code: String,
@@ -35,6 +35,7 @@ use warp_rg_ipfs::Persistent;
 
 use crate::components::main;
 use crate::components::prelude::{auth, loading, unlock};
+use crate::components::reusable::shortcuts::KeyboardShortcuts;
 
 pub mod components;
 pub mod iutils;
@@ -48,6 +49,8 @@ use tao::menu::{MenuBar as Menu, MenuItem};
 
 static TOAST_MANAGER: AtomRef<ToastManager> = |_| ToastManager::default();
 static LANGUAGE: AtomRef<Language> = |_| Language::by_locale(AvailableLanguages::EnUS);
+// the settings pages keep this in sync with Config.toml, so it doesn't have to be read again for every event
+static CONFIG: AtomRef<Config> = |_| Config::load_config_or_default();
 
 pub const WINDOW_SUFFIX_NAME: &str = "Uplink";
 
@@ -247,6 +250,9 @@ fn App(cx: Scope<State>) -> Element {
 
     cx.use_hook(|_| {
         cx.provide_context(cx.props.messaging.clone());
+        cx.provide_context(cx.props.storage.clone());
+        utils::sounds::configure(Config::load_config_or_default().sounds);
+        state::palette::register::<incognito_typing::ExtIncognitoTyping>();
     });
     // Loads the styles for all of our UIKit elements.
     let theme_colors = Theme::load_or_default().rosetta();
@@ -280,22 +286,53 @@ fn App(cx: Scope<State>) -> Element {
                 })
             },
             Router {
-                Route { to: "/", unlock::Unlock { tesseract: cx.props.tesseract.clone() } }
-                Route { to: "/loading", loading::Loading { account: cx.props.account.clone() } },
-                Route { to: "/auth", auth::Auth { account: cx.props.account.clone() } },
-                Route { to: "/main/files", main::files::Files { account: cx.props.account.clone(), storage: cx.props.storage.clone(), messaging: cx.props.messaging.clone() } },
-                Route { to: "/main/friends", main::friends::Friends { account: cx.props.account.clone(), messaging: cx.props.messaging.clone() } },
-                Route { to: "/main/settings", main::settings::Settings {
+                KeyboardShortcuts {
+                    tesseract: cx.props.tesseract.clone(),
                     account: cx.props.account.clone(),
-                    page_to_open: main::settings::sidebar::nav::Route::General,
-                    messaging: cx.props.messaging.clone()
-                }},
-                Route { to: "/main/settings/profile", main::settings::Settings {
-                    account: cx.props.account.clone(),
-                    page_to_open: main::settings::sidebar::nav::Route::Profile,
-                    messaging: cx.props.messaging.clone()
-                }},
-                Route { to: "/main", main::Main { account: cx.props.account.clone(), messaging: cx.props.messaging.clone() } },
+                    messaging: cx.props.messaging.clone(),
+                    storage: cx.props.storage.clone(),
+                    Route { to: "/", unlock::Unlock { tesseract: cx.props.tesseract.clone() } }
+                    Route { to: "/loading", loading::Loading { account: cx.props.account.clone() } },
+                    Route { to: "/auth", auth::Auth { account: cx.props.account.clone() } },
+                    Route { to: "/main/files", main::files::Files { account: cx.props.account.clone(), storage: cx.props.storage.clone(), messaging: cx.props.messaging.clone() } },
+                    Route { to: "/main/friends", main::friends::Friends { account: cx.props.account.clone(), messaging: cx.props.messaging.clone() } },
+                    Route { to: "/main/settings", main::settings::Settings {
+                        account: cx.props.account.clone(),
+                        page_to_open: main::settings::sidebar::nav::Route::General,
+                        messaging: cx.props.messaging.clone()
+                    }},
+                    Route { to: "/main/settings/profile", main::settings::Settings {
+                        account: cx.props.account.clone(),
+                        page_to_open: main::settings::sidebar::nav::Route::Profile,
+                        messaging: cx.props.messaging.clone()
+                    }},
+                    Route { to: "/main/settings/audio-video", main::settings::Settings {
+                        account: cx.props.account.clone(),
+                        page_to_open: main::settings::sidebar::nav::Route::AudioVideo,
+                        messaging: cx.props.messaging.clone()
+                    }},
+                    Route { to: "/main/settings/extensions", main::settings::Settings {
+                        account: cx.props.account.clone(),
+                        page_to_open: main::settings::sidebar::nav::Route::Extensions,
+                        messaging: cx.props.messaging.clone()
+                    }},
+                    Route { to: "/main/settings/keybinds", main::settings::Settings {
+                        account: cx.props.account.clone(),
+                        page_to_open: main::settings::sidebar::nav::Route::Keybinds,
+                        messaging: cx.props.messaging.clone()
+                    }},
+                    Route { to: "/main/settings/notifications", main::settings::Settings {
+                        account: cx.props.account.clone(),
+                        page_to_open: main::settings::sidebar::nav::Route::Notifications,
+                        messaging: cx.props.messaging.clone()
+                    }},
+                    Route { to: "/main/settings/developer", main::settings::Settings {
+                        account: cx.props.account.clone(),
+                        page_to_open: main::settings::sidebar::nav::Route::Developer,
+                        messaging: cx.props.messaging.clone()
+                    }},
+                    Route { to: "/main", main::Main { account: cx.props.account.clone(), messaging: cx.props.messaging.clone() } },
+                }
             }
         }
     ))
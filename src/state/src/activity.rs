@@ -0,0 +1,95 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// the number of entries kept in the activity feed. older entries are dropped.
+pub const MAX_ACTIVITY: usize = 200;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActivityKind {
+    FriendRequest,
+    Mention,
+    /// messages in a muted chat, grouped until they are read
+    MutedMessage,
+    /// someone reacted to one of our messages
+    Reaction,
+    FileShare,
+}
+
+impl ActivityKind {
+    pub fn label(&self) -> String {
+        match self {
+            ActivityKind::FriendRequest => "Friend Request",
+            ActivityKind::Mention => "Mention",
+            ActivityKind::MutedMessage => "Muted Chat",
+            ActivityKind::Reaction => "Reaction",
+            ActivityKind::FileShare => "File Shared",
+        }
+        .to_string()
+    }
+}
+
+/// an entry in the activity feed
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Activity {
+    pub id: Uuid,
+    pub kind: ActivityKind,
+    pub title: String,
+    pub body: String,
+    /// the conversation opened when the entry is clicked
+    pub conversation_id: Option<Uuid>,
+    pub message_id: Option<Uuid>,
+    pub created: DateTime<Utc>,
+    pub read: bool,
+    /// the number of events grouped into this entry
+    #[serde(default = "default_count")]
+    pub count: u32,
+}
+
+fn default_count() -> u32 {
+    1
+}
+
+impl Activity {
+    pub fn new(
+        kind: ActivityKind,
+        title: String,
+        body: String,
+        conversation_id: Option<Uuid>,
+        message_id: Option<Uuid>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            kind,
+            title,
+            body,
+            conversation_id,
+            message_id,
+            created: Utc::now(),
+            read: false,
+            count: 1,
+        }
+    }
+}
+
+/// adds an entry at the front of the feed. messages from a muted chat are added to its unread entry, if any.
+pub fn add(feed: &mut Vec<Activity>, activity: Activity) {
+    if activity.kind == ActivityKind::MutedMessage {
+        let existing = feed.iter().position(|entry| {
+            entry.kind == ActivityKind::MutedMessage
+                && !entry.read
+                && entry.conversation_id == activity.conversation_id
+        });
+        if let Some(idx) = existing {
+            let mut entry = feed.remove(idx);
+            entry.count += 1;
+            entry.body = activity.body;
+            entry.message_id = activity.message_id;
+            entry.created = activity.created;
+            feed.insert(0, entry);
+            return;
+        }
+    }
+    feed.insert(0, activity);
+    feed.truncate(MAX_ACTIVITY);
+}
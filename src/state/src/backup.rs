@@ -0,0 +1,436 @@
+//! contact backups. friends, blocked users, nicknames, notes and groups are exported to a JSON file signed with the
+//! account's key, so that they can be restored on another machine.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utils::Account;
+use warp::crypto::{did_key::CoreSign, DID};
+
+use crate::{contacts::Contact, groups::FriendGroup};
+
+const BACKUP_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ContactBackup {
+    pub version: u32,
+    pub created: DateTime<Utc>,
+    pub friends: Vec<String>,
+    pub blocked: Vec<String>,
+    /// nicknames and notes, keyed by DID
+    pub contacts: HashMap<String, Contact>,
+    pub groups: Vec<FriendGroup>,
+}
+
+impl ContactBackup {
+    pub fn new(
+        friends: Vec<String>,
+        blocked: Vec<String>,
+        contacts: HashMap<String, Contact>,
+        groups: Vec<FriendGroup>,
+    ) -> Self {
+        Self {
+            version: BACKUP_VERSION,
+            created: Utc::now(),
+            friends,
+            blocked,
+            contacts,
+            groups,
+        }
+    }
+}
+
+// the file. the backup is stored as the exact text which was signed
+#[derive(Serialize, Deserialize)]
+struct SignedBackup {
+    signer: String,
+    signature: String,
+    backup: String,
+}
+
+#[derive(Debug)]
+pub enum BackupError {
+    Json(serde_json::Error),
+    Warp(warp::error::Error),
+    InvalidSignature,
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for BackupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BackupError::Json(e) => write!(f, "the file isn't a contact backup: {}", e),
+            BackupError::Warp(e) => write!(f, "{}", e),
+            BackupError::InvalidSignature => {
+                write!(f, "the backup was changed after it was signed")
+            }
+            BackupError::UnsupportedVersion(version) => {
+                write!(
+                    f,
+                    "the backup was made by a newer version (version {})",
+                    version
+                )
+            }
+        }
+    }
+}
+
+impl From<serde_json::Error> for BackupError {
+    fn from(e: serde_json::Error) -> Self {
+        BackupError::Json(e)
+    }
+}
+
+impl From<warp::error::Error> for BackupError {
+    fn from(e: warp::error::Error) -> Self {
+        BackupError::Warp(e)
+    }
+}
+
+/// the signed file contents
+pub async fn export(account: &Account, backup: &ContactBackup) -> Result<String, BackupError> {
+    let signer = account.get_own_identity().await?.did_key();
+    let key = account.decrypt_private_key(None)?;
+    sign(&signer, &key, backup)
+}
+
+fn sign(signer: &DID, key: &DID, backup: &ContactBackup) -> Result<String, BackupError> {
+    let backup = serde_json::to_string(backup)?;
+    let signature = base64::encode(key.sign(backup.as_bytes()));
+    Ok(serde_json::to_string_pretty(&SignedBackup {
+        signer: signer.to_string(),
+        signature,
+        backup,
+    })?)
+}
+
+/// reads a file written by `export` and checks the signature. returns the DID which signed it
+pub fn import(json: &str) -> Result<(String, ContactBackup), BackupError> {
+    let signed: SignedBackup = serde_json::from_str(json)?;
+    let signer = DID::try_from(signed.signer.clone()).map_err(|_| BackupError::InvalidSignature)?;
+    let signature = base64::decode(&signed.signature).map_err(|_| BackupError::InvalidSignature)?;
+    signer
+        .verify(signed.backup.as_bytes(), &signature)
+        .map_err(|_| BackupError::InvalidSignature)?;
+    let backup: ContactBackup = serde_json::from_str(&signed.backup)?;
+    if backup.version > BACKUP_VERSION {
+        return Err(BackupError::UnsupportedVersion(backup.version));
+    }
+    Ok((signed.signer, backup))
+}
+
+/// what this machine knows, to compare the backup against. DIDs are strings
+pub struct LocalContacts<'a> {
+    pub own_did: &'a str,
+    pub friends: &'a HashSet<String>,
+    pub blocked: &'a HashSet<String>,
+    /// the DIDs of outgoing friend requests
+    pub pending: &'a HashSet<String>,
+    pub contacts: &'a HashMap<String, Contact>,
+}
+
+/// differences which need a decision before the import is applied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Conflict {
+    Nickname {
+        did: String,
+        local: String,
+        imported: String,
+    },
+    Notes {
+        did: String,
+        local: String,
+        imported: String,
+    },
+    /// a friend in the backup who is blocked here. taking the backup unblocks them and sends a request
+    BlockedHere(String),
+    /// blocked in the backup but a friend here. taking the backup blocks them
+    FriendHere(String),
+}
+
+impl Conflict {
+    pub fn did(&self) -> &str {
+        match self {
+            Conflict::Nickname { did, .. } | Conflict::Notes { did, .. } => did,
+            Conflict::BlockedHere(did) | Conflict::FriendHere(did) => did,
+        }
+    }
+}
+
+/// what importing a backup changes
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct ImportPlan {
+    pub send_requests: Vec<String>,
+    pub blocks: Vec<String>,
+    pub unblocks: Vec<String>,
+    /// the merged nicknames and notes. conflicting fields keep the local value
+    pub contacts: HashMap<String, Contact>,
+    pub groups: Vec<FriendGroup>,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl ImportPlan {
+    /// resolves `conflict` in favor of the backup
+    pub fn take_imported(&mut self, conflict: &Conflict) {
+        match conflict {
+            Conflict::Nickname { did, imported, .. } => {
+                self.contacts.entry(did.clone()).or_default().nickname = Some(imported.clone());
+            }
+            Conflict::Notes { did, imported, .. } => {
+                self.contacts.entry(did.clone()).or_default().notes = imported.clone();
+            }
+            Conflict::BlockedHere(did) => {
+                self.unblocks.push(did.clone());
+                self.send_requests.push(did.clone());
+            }
+            Conflict::FriendHere(did) => self.blocks.push(did.clone()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.send_requests.is_empty()
+            && self.blocks.is_empty()
+            && self.contacts.is_empty()
+            && self.groups.is_empty()
+            && self.conflicts.is_empty()
+    }
+}
+
+/// compares the backup with the local contacts. nothing is applied
+pub fn plan(backup: &ContactBackup, local: &LocalContacts) -> ImportPlan {
+    let mut plan = ImportPlan {
+        groups: backup.groups.clone(),
+        ..Default::default()
+    };
+
+    for did in backup.friends.iter().filter(|did| *did != local.own_did) {
+        if local.blocked.contains(did) {
+            plan.conflicts.push(Conflict::BlockedHere(did.clone()));
+        } else if !local.friends.contains(did) && !local.pending.contains(did) {
+            plan.send_requests.push(did.clone());
+        }
+    }
+
+    for did in backup.blocked.iter().filter(|did| *did != local.own_did) {
+        if local.friends.contains(did) {
+            plan.conflicts.push(Conflict::FriendHere(did.clone()));
+        } else if !local.blocked.contains(did) {
+            plan.blocks.push(did.clone());
+        }
+    }
+
+    for (did, imported) in backup.contacts.iter() {
+        let local_contact = local.contacts.get(did).cloned().unwrap_or_default();
+        let mut merged = local_contact.clone();
+        match (&local_contact.nickname, &imported.nickname) {
+            (None, Some(nickname)) => merged.nickname = Some(nickname.clone()),
+            (Some(local_nickname), Some(nickname)) if local_nickname != nickname => {
+                plan.conflicts.push(Conflict::Nickname {
+                    did: did.clone(),
+                    local: local_nickname.clone(),
+                    imported: nickname.clone(),
+                })
+            }
+            _ => {}
+        }
+        if local_contact.notes.is_empty() {
+            merged.notes = imported.notes.clone();
+        } else if !imported.notes.is_empty() && imported.notes != local_contact.notes {
+            plan.conflicts.push(Conflict::Notes {
+                did: did.clone(),
+                local: local_contact.notes.clone(),
+                imported: imported.notes.clone(),
+            });
+        }
+        if merged != local_contact {
+            plan.contacts.insert(did.clone(), merged);
+        }
+    }
+
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OWN: &str = "did:key:own";
+    const ALICE: &str = "did:key:alice";
+    const BOB: &str = "did:key:bob";
+
+    fn contact(nickname: Option<&str>, notes: &str) -> Contact {
+        Contact {
+            nickname: nickname.map(String::from),
+            notes: notes.to_string(),
+        }
+    }
+
+    fn set(dids: &[&str]) -> HashSet<String> {
+        dids.iter().map(|did| did.to_string()).collect()
+    }
+
+    fn backup(friends: &[&str], blocked: &[&str], contacts: &[(&str, Contact)]) -> ContactBackup {
+        ContactBackup::new(
+            friends.iter().map(|did| did.to_string()).collect(),
+            blocked.iter().map(|did| did.to_string()).collect(),
+            contacts
+                .iter()
+                .map(|(did, contact)| (did.to_string(), contact.clone()))
+                .collect(),
+            Vec::new(),
+        )
+    }
+
+    fn signed(backup: &ContactBackup) -> (DID, String) {
+        let key = DID::default();
+        let json = sign(&key, &key, backup).unwrap();
+        (key, json)
+    }
+
+    #[test]
+    fn import_checks_the_signature() {
+        let backup = backup(&[ALICE], &[BOB], &[(ALICE, contact(Some("Al"), ""))]);
+        let (key, json) = signed(&backup);
+        let (signer, imported) = import(&json).unwrap();
+        assert_eq!(signer, key.to_string());
+        assert!(imported == backup);
+    }
+
+    #[test]
+    fn import_rejects_a_changed_backup() {
+        let (_, json) = signed(&backup(&[ALICE], &[], &[]));
+        let mut file: SignedBackup = serde_json::from_str(&json).unwrap();
+        file.backup = file.backup.replace(ALICE, BOB);
+        let tampered = serde_json::to_string(&file).unwrap();
+        assert!(matches!(
+            import(&tampered),
+            Err(BackupError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn import_rejects_another_signer() {
+        let (_, json) = signed(&backup(&[ALICE], &[], &[]));
+        let mut file: SignedBackup = serde_json::from_str(&json).unwrap();
+        file.signer = DID::default().to_string();
+        let forged = serde_json::to_string(&file).unwrap();
+        assert!(matches!(
+            import(&forged),
+            Err(BackupError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn import_rejects_newer_versions() {
+        let mut newer = backup(&[ALICE], &[], &[]);
+        newer.version = BACKUP_VERSION + 1;
+        let (_, json) = signed(&newer);
+        assert!(matches!(
+            import(&json),
+            Err(BackupError::UnsupportedVersion(version)) if version == BACKUP_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn plan_sends_requests_and_blocks_without_conflicts() {
+        let backup = backup(
+            &[OWN, ALICE, BOB, "did:key:pending"],
+            &["did:key:spam"],
+            &[],
+        );
+        let friends = set(&[BOB]);
+        let pending = set(&["did:key:pending"]);
+        let plan = plan(
+            &backup,
+            &LocalContacts {
+                own_did: OWN,
+                friends: &friends,
+                blocked: &HashSet::new(),
+                pending: &pending,
+                contacts: &HashMap::new(),
+            },
+        );
+        assert_eq!(plan.send_requests, vec![ALICE.to_string()]);
+        assert_eq!(plan.blocks, vec!["did:key:spam".to_string()]);
+        assert!(plan.conflicts.is_empty());
+    }
+
+    #[test]
+    fn plan_reports_each_conflict() {
+        let backup = backup(
+            &[ALICE],
+            &[BOB],
+            &[
+                (ALICE, contact(Some("Al"), "met at work")),
+                ("did:key:carol", contact(Some("Caz"), "")),
+            ],
+        );
+        let friends = set(&[BOB]);
+        let blocked = set(&[ALICE]);
+        let contacts: HashMap<String, Contact> = [
+            (ALICE.to_string(), contact(Some("Alice"), "from school")),
+            ("did:key:carol".to_string(), contact(None, "neighbour")),
+        ]
+        .into_iter()
+        .collect();
+        let plan = plan(
+            &backup,
+            &LocalContacts {
+                own_did: OWN,
+                friends: &friends,
+                blocked: &blocked,
+                pending: &HashSet::new(),
+                contacts: &contacts,
+            },
+        );
+
+        assert_eq!(
+            plan.conflicts,
+            vec![
+                Conflict::BlockedHere(ALICE.to_string()),
+                Conflict::FriendHere(BOB.to_string()),
+                Conflict::Nickname {
+                    did: ALICE.to_string(),
+                    local: "Alice".to_string(),
+                    imported: "Al".to_string(),
+                },
+                Conflict::Notes {
+                    did: ALICE.to_string(),
+                    local: "from school".to_string(),
+                    imported: "met at work".to_string(),
+                },
+            ]
+        );
+        assert!(plan.send_requests.is_empty());
+        assert!(plan.blocks.is_empty());
+        // the fields without a conflict are merged
+        assert!(plan.contacts.get("did:key:carol") == Some(&contact(Some("Caz"), "neighbour")));
+        assert!(!plan.contacts.contains_key(ALICE));
+    }
+
+    #[test]
+    fn take_imported_applies_the_backup() {
+        let mut plan = ImportPlan::default();
+        plan.take_imported(&Conflict::Nickname {
+            did: ALICE.to_string(),
+            local: "Alice".to_string(),
+            imported: "Al".to_string(),
+        });
+        plan.take_imported(&Conflict::Notes {
+            did: ALICE.to_string(),
+            local: "from school".to_string(),
+            imported: "met at work".to_string(),
+        });
+        plan.take_imported(&Conflict::BlockedHere(BOB.to_string()));
+        plan.take_imported(&Conflict::FriendHere("did:key:carol".to_string()));
+
+        assert!(plan.contacts.get(ALICE) == Some(&contact(Some("Al"), "met at work")));
+        assert_eq!(plan.unblocks, vec![BOB.to_string()]);
+        assert_eq!(plan.send_requests, vec![BOB.to_string()]);
+        assert_eq!(plan.blocks, vec!["did:key:carol".to_string()]);
+    }
+}
@@ -0,0 +1,51 @@
+use std::{collections::HashMap, sync::RwLock};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+// a copy of the nicknames for code which doesn't have access to the STATE atom, such as
+// `iutils::get_username_from_did`. updated whenever the contacts in PersistedState change.
+static NICKNAMES: Lazy<RwLock<HashMap<String, String>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// what the local user knows about someone. never shared with anyone
+#[derive(Serialize, Deserialize, Default, Clone, Eq, PartialEq)]
+pub struct Contact {
+    /// shown instead of the username
+    pub nickname: Option<String>,
+    pub notes: String,
+}
+
+impl Contact {
+    pub fn is_empty(&self) -> bool {
+        self.nickname.is_none() && self.notes.is_empty()
+    }
+}
+
+/// an empty or whitespace-only nickname removes it
+pub fn normalize_nickname(nickname: Option<String>) -> Option<String> {
+    nickname
+        .map(|nickname| nickname.trim().to_string())
+        .filter(|nickname| !nickname.is_empty())
+}
+
+/// the nickname given to the DID, which is passed as a string
+pub fn nickname(did: &str) -> Option<String> {
+    match NICKNAMES.read() {
+        Ok(nicknames) => nicknames.get(did).cloned(),
+        Err(e) => {
+            log::error!("failed to read nicknames: {}", e);
+            None
+        }
+    }
+}
+
+pub(crate) fn sync(contacts: &HashMap<String, Contact>) {
+    let nicknames = contacts
+        .iter()
+        .filter_map(|(did, contact)| Some((did.clone(), contact.nickname.clone()?)))
+        .collect();
+    match NICKNAMES.write() {
+        Ok(mut lock) => *lock = nicknames,
+        Err(e) => log::error!("failed to update nicknames: {}", e),
+    }
+}
@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// the skin tone used when inserting or rendering emojis which support one
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SkinTone {
+    Default,
+    Light,
+    MediumLight,
+    Medium,
+    MediumDark,
+    Dark,
+}
+
+impl Default for SkinTone {
+    fn default() -> Self {
+        SkinTone::Default
+    }
+}
+
+impl SkinTone {
+    pub fn all() -> Vec<SkinTone> {
+        vec![
+            SkinTone::Default,
+            SkinTone::Light,
+            SkinTone::MediumLight,
+            SkinTone::Medium,
+            SkinTone::MediumDark,
+            SkinTone::Dark,
+        ]
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            SkinTone::Default => "Default",
+            SkinTone::Light => "Light",
+            SkinTone::MediumLight => "Medium Light",
+            SkinTone::Medium => "Medium",
+            SkinTone::MediumDark => "Medium Dark",
+            SkinTone::Dark => "Dark",
+        }
+        .to_string()
+    }
+}
+
+/// the emojis inserted from the emoji selector, used for the "Recently used" and "Frequently used" rows
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct EmojiUsage {
+    /// most recent first, without duplicates
+    pub recent: Vec<String>,
+    pub counts: HashMap<String, u32>,
+}
+
+// more than enough for the rows shown in the selector
+const MAX_RECENT: usize = 64;
+
+impl EmojiUsage {
+    pub fn record(&mut self, emoji: &str) {
+        self.recent.retain(|e| e != emoji);
+        self.recent.insert(0, emoji.to_string());
+        self.recent.truncate(MAX_RECENT);
+        *self.counts.entry(emoji.to_string()).or_default() += 1;
+    }
+
+    pub fn recent(&self, limit: usize) -> Vec<String> {
+        self.recent.iter().take(limit).cloned().collect()
+    }
+
+    /// the most used emojis, ties are broken by how recently they were used
+    pub fn frequent(&self, limit: usize) -> Vec<String> {
+        let position = |emoji: &String| {
+            self.recent
+                .iter()
+                .position(|e| e == emoji)
+                .unwrap_or(usize::MAX)
+        };
+        let mut frequent: Vec<(&String, &u32)> = self.counts.iter().collect();
+        frequent.sort_by(|(a, a_count), (b, b_count)| {
+            b_count
+                .cmp(a_count)
+                .then_with(|| position(a).cmp(&position(b)))
+        });
+        frequent
+            .into_iter()
+            .take(limit)
+            .map(|(emoji, _)| emoji.clone())
+            .collect()
+    }
+}
@@ -0,0 +1,207 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// the colors offered for a group. clicking the color of a group moves to the next one
+pub const GROUP_COLORS: [&str; 8] = [
+    "#4d9fff", "#2ecc71", "#f1c40f", "#e67e22", "#e74c3c", "#9b59b6", "#1abc9c", "#95a5a6",
+];
+
+// written at the top of an exported file so that the format can change later
+const EXPORT_VERSION: u32 = 1;
+
+/// a user-defined label for friends, such as "Team" or "Family". a friend can be in several groups
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq)]
+pub struct FriendGroup {
+    pub id: Uuid,
+    pub name: String,
+    pub color: String,
+    /// DIDs, as strings
+    pub members: Vec<String>,
+    /// whether the section is collapsed in the friends list
+    #[serde(default)]
+    pub collapsed: bool,
+}
+
+impl FriendGroup {
+    pub fn new(name: &str, color: &str) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: name.trim().to_string(),
+            color: color.to_string(),
+            members: vec![],
+            collapsed: false,
+        }
+    }
+
+    pub fn contains(&self, did: &str) -> bool {
+        self.members.iter().any(|member| member == did)
+    }
+
+    pub fn add(&mut self, did: &str) {
+        if !self.contains(did) {
+            self.members.push(did.to_string());
+        }
+    }
+
+    pub fn remove(&mut self, did: &str) {
+        self.members.retain(|member| member != did);
+    }
+
+    /// the color after this one in `GROUP_COLORS`
+    pub fn next_color(&self) -> String {
+        let idx = GROUP_COLORS
+            .iter()
+            .position(|color| *color == self.color)
+            .map(|idx| idx + 1)
+            .unwrap_or(0);
+        GROUP_COLORS[idx % GROUP_COLORS.len()].to_string()
+    }
+}
+
+/// the color for a new group, chosen so that the first groups all look different
+pub fn default_color(groups: &[FriendGroup]) -> String {
+    GROUP_COLORS[groups.len() % GROUP_COLORS.len()].to_string()
+}
+
+/// the groups which `did` belongs to
+pub fn groups_of<'a>(groups: &'a [FriendGroup], did: &str) -> Vec<&'a FriendGroup> {
+    groups.iter().filter(|group| group.contains(did)).collect()
+}
+
+/// whether `did` is shown when the list is filtered by `group_id`. everyone is shown without a filter
+pub fn matches_filter(groups: &[FriendGroup], group_id: Option<Uuid>, did: &str) -> bool {
+    match group_id {
+        Some(id) => groups
+            .iter()
+            .any(|group| group.id == id && group.contains(did)),
+        None => true,
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct GroupsExport {
+    version: u32,
+    groups: Vec<FriendGroup>,
+}
+
+/// the groups as JSON, for backups or moving to another device
+pub fn export(groups: &[FriendGroup]) -> Result<String, serde_json::Error> {
+    serde_json::to_string_pretty(&GroupsExport {
+        version: EXPORT_VERSION,
+        groups: groups.to_vec(),
+    })
+}
+
+/// reads groups written by `export`
+pub fn import(json: &str) -> Result<Vec<FriendGroup>, serde_json::Error> {
+    serde_json::from_str::<GroupsExport>(json).map(|export| export.groups)
+}
+
+/// adds imported groups. a group with the same name (ignoring case) as an existing one is merged into it
+pub fn merge(groups: &mut Vec<FriendGroup>, imported: Vec<FriendGroup>) {
+    for group in imported {
+        match groups
+            .iter_mut()
+            .find(|existing| existing.name.to_lowercase() == group.name.to_lowercase())
+        {
+            Some(existing) => {
+                for member in group.members.iter() {
+                    existing.add(member);
+                }
+            }
+            None => groups.push(FriendGroup {
+                // the id may already be used by a group with a different name
+                id: Uuid::new_v4(),
+                ..group
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(name: &str, members: &[&str]) -> FriendGroup {
+        let mut group = FriendGroup::new(name, GROUP_COLORS[0]);
+        for member in members {
+            group.add(member);
+        }
+        group
+    }
+
+    #[test]
+    fn export_round_trips() {
+        let groups = vec![
+            group("Team", &["did:key:a", "did:key:b"]),
+            group("Family", &[]),
+        ];
+        let imported = import(&export(&groups).unwrap()).unwrap();
+        assert!(imported == groups);
+    }
+
+    #[test]
+    fn import_rejects_other_files() {
+        assert!(import("").is_err());
+        assert!(import("[]").is_err());
+        assert!(import(r#"{"version": 1}"#).is_err());
+    }
+
+    #[test]
+    fn import_defaults_collapsed() {
+        let id = Uuid::new_v4();
+        let json = format!(
+            r##"{{"version": 1, "groups": [{{"id": "{}", "name": "Team", "color": "#4d9fff", "members": []}}]}}"##,
+            id
+        );
+        let imported = import(&json).unwrap();
+        assert_eq!(imported.len(), 1);
+        assert_eq!(imported[0].id, id);
+        assert!(!imported[0].collapsed);
+    }
+
+    #[test]
+    fn merge_joins_groups_with_the_same_name() {
+        let mut groups = vec![group("Team", &["did:key:a"])];
+        let id = groups[0].id;
+        merge(
+            &mut groups,
+            vec![group("team", &["did:key:a", "did:key:b"])],
+        );
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].id, id);
+        assert_eq!(groups[0].name, "Team");
+        assert_eq!(groups[0].members, vec!["did:key:a", "did:key:b"]);
+    }
+
+    #[test]
+    fn merge_adds_new_groups_with_new_ids() {
+        let mut groups = vec![group("Team", &[])];
+        let family = group("Family", &["did:key:c"]);
+        // the same id as an existing group, from another device
+        let clashing = FriendGroup {
+            id: groups[0].id,
+            ..group("Vendors", &[])
+        };
+        merge(&mut groups, vec![family.clone(), clashing]);
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[1].name, "Family");
+        assert_eq!(groups[1].members, family.members);
+        assert_ne!(groups[1].id, family.id);
+        assert_ne!(groups[2].id, groups[0].id);
+    }
+
+    #[test]
+    fn filter_by_group() {
+        let groups = vec![
+            group("Team", &["did:key:a"]),
+            group("Family", &["did:key:b"]),
+        ];
+        let team = Some(groups[0].id);
+        assert!(matches_filter(&groups, None, "did:key:b"));
+        assert!(matches_filter(&groups, team, "did:key:a"));
+        assert!(!matches_filter(&groups, team, "did:key:b"));
+        // a group which was deleted
+        assert!(!matches_filter(&groups, Some(Uuid::new_v4()), "did:key:a"));
+    }
+}
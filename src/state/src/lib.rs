@@ -10,6 +10,26 @@ use warp::raygun::Conversation;
 
 use utils::DEFAULT_PATH;
 
+pub mod activity;
+pub mod backup;
+pub mod contacts;
+pub mod emoji;
+pub mod groups;
+pub mod palette;
+pub mod presence;
+pub mod requests;
+pub mod retention;
+pub mod verification;
+use activity::Activity;
+use contacts::Contact;
+use emoji::{EmojiUsage, SkinTone};
+use groups::FriendGroup;
+use palette::PaletteUsage;
+use presence::Presence;
+use requests::RequestLog;
+use retention::Retention;
+use verification::KnownIdentities;
+
 pub static STATE: AtomRef<PersistedState> = |_| PersistedState::load_or_initial();
 
 pub enum Actions {
@@ -29,7 +49,62 @@ pub enum Actions {
     HideSidebar(bool),
     //DeselectChat,
     SetShowPrerelaseNotice(bool),
+    // whether others see that we are typing
+    SetSendTyping(bool),
     SetExtensionEnabled(String, bool),
+    // triggered locally from the topbar or by a control message from another participant
+    SetRetention(Uuid, Retention),
+    // triggered by a keyboard shortcut
+    MarkAllRead,
+    // an entry was chosen in the command palette
+    RecordPaletteUsage(String),
+    SetSkinTone(SkinTone),
+    // an emoji was inserted from the emoji selector
+    RecordEmojiUsage(String),
+    // triggered by friend requests, mentions, reactions and file shares
+    AddActivity(Activity),
+    // an entry in the activity feed was opened
+    MarkActivityRead(Uuid),
+    MarkAllActivityRead,
+    ClearActivity,
+    // the message was deleted, e.g. because it expired
+    RemoveMessageActivity(Uuid),
+    // the DID as a string and the new nickname. None removes it
+    SetNickname(String, Option<String>),
+    // the DID as a string and the notes
+    SetContactNotes(String, String),
+    CreateFriendGroup(FriendGroup),
+    DeleteFriendGroup(Uuid),
+    SetFriendGroupColor(Uuid, String),
+    ToggleFriendGroupCollapsed(Uuid),
+    // the group and the DID as a string
+    AddToFriendGroup(Uuid, String),
+    RemoveFromFriendGroup(Uuid, String),
+    // groups read from an exported file
+    ImportFriendGroups(Vec<FriendGroup>),
+    // nicknames and notes merged from a contact backup, keyed by DID
+    ImportContacts(HashMap<String, Contact>),
+    // the DID as a string and the username it was seen with
+    RecordIdentity(String, String),
+    // the fingerprints were compared, or the verification was removed
+    SetVerified(String, bool),
+    // the user accepts that the DID replaced the verified DID which had its username
+    AcknowledgeKeyChange(String),
+    // an incoming friend request arrived from the DID
+    RecordFriendRequest(String),
+    // hide the requests from the DID without declining them, or show them again
+    SetRequestsIgnored(String, bool),
+    // the senders of the pending incoming requests, as strings
+    SetIncomingRequests(HashSet<String>),
+    // a request was ignored or declined by a filter
+    CountFilteredRequest,
+    ResetFilteredRequests,
+    // the DID of an outgoing request and the note to send when it's accepted. None removes the note
+    SetIntroNote(String, Option<String>),
+    // chosen from the sidebar
+    SetPresence(Presence),
+    // set when there was no input for a while, and cleared on the next input
+    SetIdle(bool),
     // SendNotification(String, String, Sounds),
 }
 
@@ -53,6 +128,31 @@ pub struct PersistedState {
     pub show_prerelease_notice: bool,
     pub send_typing: bool,
     pub enabled_extensions: Vec<String>,
+    /// keyed by the id of the command palette entry
+    #[serde(default)]
+    pub palette_usage: HashMap<String, PaletteUsage>,
+    #[serde(default)]
+    pub emoji_skin_tone: SkinTone,
+    #[serde(default)]
+    pub emoji_usage: EmojiUsage,
+    /// the activity feed, most recent first
+    #[serde(default)]
+    pub activity: Vec<Activity>,
+    /// local nicknames and notes, keyed by DID
+    #[serde(default)]
+    pub contacts: HashMap<String, Contact>,
+    #[serde(default)]
+    pub friend_groups: Vec<FriendGroup>,
+    /// the username each friend was last seen with and whether they are verified, keyed by DID
+    #[serde(default)]
+    pub identities: KnownIdentities,
+    #[serde(default)]
+    pub requests: RequestLog,
+    /// the presence chosen by the user
+    #[serde(default)]
+    pub presence: Presence,
+    #[serde(skip)]
+    pub idle: bool,
 }
 
 #[derive(Serialize, Deserialize, Default, Clone, Eq, PartialEq)]
@@ -74,6 +174,9 @@ pub struct ConversationInfo {
     pub first_unread_message_id: Option<Uuid>,
     /// the time the conversation was created. used to sort the chats
     pub creation_time: DateTime<Utc>,
+    /// how long messages are kept before they are deleted locally
+    #[serde(default)]
+    pub retention: Retention,
 }
 
 impl Ord for ConversationInfo {
@@ -115,6 +218,7 @@ impl PersistedState {
             // if a field is added to the state, parsing will fail. in that case, want to return the same struct that is created by default.
             // todo: add versioning to PersistedState
             if let Ok(c) = serde_json::from_slice::<PersistedState>(&b) {
+                contacts::sync(&c.contacts);
                 return c;
             }
         }
@@ -137,6 +241,39 @@ impl PersistedState {
         }
     }
 
+    /// the locally stored nickname and notes for the DID, which is passed as a string
+    pub fn contact(&self, did: &str) -> Contact {
+        self.contacts.get(did).cloned().unwrap_or_default()
+    }
+
+    pub fn is_verified(&self, did: &str) -> bool {
+        verification::is_verified(&self.identities, did)
+    }
+
+    /// whether `RecordIdentity` would change anything, so that callers can skip the dispatch and the save
+    pub fn needs_identity_update(&self, did: &str, username: &str) -> bool {
+        !username.is_empty()
+            && self
+                .identities
+                .get(did)
+                .map_or(true, |identity| identity.username != username)
+    }
+
+    /// the presence shown to friends, taking auto away into account
+    pub fn presence(&self) -> Presence {
+        presence::effective(self.presence, self.idle)
+    }
+
+    fn friend_group_mut(&mut self, id: Uuid) -> Option<&mut FriendGroup> {
+        self.friend_groups.iter_mut().find(|group| group.id == id)
+    }
+
+    fn remove_empty_contact(&mut self, did: &str) {
+        if self.contacts.get(did).map(Contact::is_empty).unwrap_or(false) {
+            self.contacts.remove(did);
+        }
+    }
+
     pub fn dispatch(&mut self, action: Actions) {
         match action {
             Actions::AddConversation(conversation) => {
@@ -233,8 +370,12 @@ impl PersistedState {
                     .entry(conversation.id())
                     .or_insert_with(|| ci.clone());
             }
-            Actions::UpdateConversation(info) => {
+            Actions::UpdateConversation(mut info) => {
                 log::debug!("PersistedState: UpdateConversation");
+                // callers often hold an old copy of the ConversationInfo. the retention is only changed via SetRetention
+                if let Some(prev) = self.active_chats.get(&info.conversation.id()) {
+                    info.retention = prev.retention;
+                }
                 self.active_chats.insert(info.conversation.id(), info);
             }
             Actions::UpdateFavorites(favorites) => {
@@ -258,9 +399,183 @@ impl PersistedState {
                     }
                 }
             }
+            Actions::SetRetention(conversation_id, retention) => {
+                log::debug!("PersistedState: SetRetention");
+                if let Some(info) = self.active_chats.get_mut(&conversation_id) {
+                    info.retention = retention;
+                }
+                if let Some(info) = self.all_chats.get_mut(&conversation_id) {
+                    info.retention = retention;
+                }
+            }
+            Actions::MarkAllRead => {
+                log::debug!("PersistedState: MarkAllRead");
+                for info in self
+                    .active_chats
+                    .values_mut()
+                    .chain(self.all_chats.values_mut())
+                {
+                    info.num_unread_messages = 0;
+                    info.first_unread_message_id = None;
+                }
+            }
+            Actions::RecordPaletteUsage(key) => {
+                log::debug!("PersistedState: RecordPaletteUsage");
+                self.palette_usage.entry(key).or_default().record();
+            }
+            Actions::SetSkinTone(skin_tone) => {
+                log::debug!("PersistedState: SetSkinTone");
+                self.emoji_skin_tone = skin_tone;
+            }
+            Actions::RecordEmojiUsage(emoji) => {
+                log::debug!("PersistedState: RecordEmojiUsage");
+                self.emoji_usage.record(&emoji);
+            }
+            Actions::AddActivity(entry) => {
+                log::debug!("PersistedState: AddActivity");
+                activity::add(&mut self.activity, entry);
+            }
+            Actions::MarkActivityRead(id) => {
+                log::debug!("PersistedState: MarkActivityRead");
+                if let Some(entry) = self.activity.iter_mut().find(|entry| entry.id == id) {
+                    entry.read = true;
+                }
+            }
+            Actions::MarkAllActivityRead => {
+                log::debug!("PersistedState: MarkAllActivityRead");
+                for entry in self.activity.iter_mut() {
+                    entry.read = true;
+                }
+            }
+            Actions::ClearActivity => {
+                log::debug!("PersistedState: ClearActivity");
+                self.activity.clear();
+            }
+            Actions::RemoveMessageActivity(message_id) => {
+                log::debug!("PersistedState: RemoveMessageActivity");
+                self.activity.retain(|entry| entry.message_id != Some(message_id));
+            }
+            Actions::SetNickname(did, nickname) => {
+                log::debug!("PersistedState: SetNickname");
+                self.contacts.entry(did.clone()).or_default().nickname =
+                    contacts::normalize_nickname(nickname);
+                self.remove_empty_contact(&did);
+                contacts::sync(&self.contacts);
+            }
+            Actions::SetContactNotes(did, notes) => {
+                log::debug!("PersistedState: SetContactNotes");
+                self.contacts.entry(did.clone()).or_default().notes = notes;
+                self.remove_empty_contact(&did);
+            }
+            Actions::CreateFriendGroup(group) => {
+                log::debug!("PersistedState: CreateFriendGroup");
+                self.friend_groups.push(group);
+            }
+            Actions::DeleteFriendGroup(id) => {
+                log::debug!("PersistedState: DeleteFriendGroup");
+                self.friend_groups.retain(|group| group.id != id);
+            }
+            Actions::SetFriendGroupColor(id, color) => {
+                log::debug!("PersistedState: SetFriendGroupColor");
+                if let Some(group) = self.friend_group_mut(id) {
+                    group.color = color;
+                }
+            }
+            Actions::ToggleFriendGroupCollapsed(id) => {
+                log::debug!("PersistedState: ToggleFriendGroupCollapsed");
+                if let Some(group) = self.friend_group_mut(id) {
+                    group.collapsed = !group.collapsed;
+                }
+            }
+            Actions::AddToFriendGroup(id, did) => {
+                log::debug!("PersistedState: AddToFriendGroup");
+                if let Some(group) = self.friend_group_mut(id) {
+                    group.add(&did);
+                }
+            }
+            Actions::RemoveFromFriendGroup(id, did) => {
+                log::debug!("PersistedState: RemoveFromFriendGroup");
+                if let Some(group) = self.friend_group_mut(id) {
+                    group.remove(&did);
+                }
+            }
+            Actions::ImportFriendGroups(imported) => {
+                log::debug!("PersistedState: ImportFriendGroups");
+                groups::merge(&mut self.friend_groups, imported);
+            }
+            Actions::ImportContacts(imported) => {
+                log::debug!("PersistedState: ImportContacts");
+                for (did, contact) in imported {
+                    self.contacts.insert(did.clone(), contact);
+                    self.remove_empty_contact(&did);
+                }
+                contacts::sync(&self.contacts);
+            }
+            Actions::RecordIdentity(did, username) => {
+                log::debug!("PersistedState: RecordIdentity");
+                verification::record(&mut self.identities, did, username);
+            }
+            Actions::SetVerified(did, verified) => {
+                log::debug!("PersistedState: SetVerified");
+                self.identities.entry(did).or_default().verified_at =
+                    verified.then(|| DateTime::from(Local::now()));
+            }
+            Actions::AcknowledgeKeyChange(did) => {
+                log::debug!("PersistedState: AcknowledgeKeyChange");
+                if let Some(identity) = self.identities.get_mut(&did) {
+                    identity.previous_did = None;
+                }
+            }
+            Actions::RecordFriendRequest(did) => {
+                log::debug!("PersistedState: RecordFriendRequest");
+                self.requests.record(&did, Utc::now());
+            }
+            Actions::SetRequestsIgnored(did, ignored) => {
+                log::debug!("PersistedState: SetRequestsIgnored");
+                if ignored {
+                    self.requests.ignored.insert(did);
+                } else {
+                    self.requests.ignored.remove(&did);
+                }
+            }
+            Actions::SetIncomingRequests(incoming) => {
+                log::debug!("PersistedState: SetIncomingRequests");
+                self.requests.incoming = incoming;
+            }
+            Actions::CountFilteredRequest => {
+                log::debug!("PersistedState: CountFilteredRequest");
+                self.requests.filtered_count += 1;
+            }
+            Actions::ResetFilteredRequests => {
+                log::debug!("PersistedState: ResetFilteredRequests");
+                self.requests.filtered_count = 0;
+            }
+            Actions::SetPresence(presence) => {
+                log::debug!("PersistedState: SetPresence");
+                self.presence = presence;
+            }
+            Actions::SetIdle(idle) => {
+                log::debug!("PersistedState: SetIdle");
+                self.idle = idle;
+            }
+            Actions::SetIntroNote(did, note) => {
+                log::debug!("PersistedState: SetIntroNote");
+                match note.map(|note| note.trim().to_string()).filter(|note| !note.is_empty()) {
+                    Some(note) => {
+                        self.requests.intros.insert(did, note);
+                    }
+                    None => {
+                        self.requests.intros.remove(&did);
+                    }
+                }
+            }
             Actions::SetShowPrerelaseNotice(value) => {
                 log::debug!("PersistedState: SetShowPrerelaseNotice");
                 self.show_prerelease_notice = value;
+            }
+            Actions::SetSendTyping(value) => {
+                log::debug!("PersistedState: SetSendTyping");
+                self.send_typing = value;
             } // Actions::SendNotification(title, content, sound) => {
               //     let _ = PushNotification(title, content, sound);
               //     PersistedState {
@@ -0,0 +1,87 @@
+use chrono::{prelude::*, Duration};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use utils::extensions::BasicExtension;
+use warp::sync::RwLock;
+
+use crate::{Actions, PersistedState};
+
+// the commands of the registered native extensions, with the name of their extension
+static REGISTRY: Lazy<RwLock<Vec<(String, PaletteCommand)>>> = Lazy::new(|| RwLock::new(vec![]));
+
+/// an action contributed to the command palette by a native extension
+#[derive(Clone)]
+pub struct PaletteCommand {
+    /// shown in the palette and matched against the search text
+    pub name: String,
+    pub description: String,
+    /// the action dispatched when the command is chosen, so that the change is saved
+    pub run: fn(&PersistedState) -> Actions,
+}
+
+/// implemented by native extensions which add entries to the command palette
+pub trait PaletteCommands {
+    fn commands() -> Vec<PaletteCommand>;
+}
+
+/// adds the commands of the extension `E` to the palette. registering an extension again does nothing
+pub fn register<E: BasicExtension + PaletteCommands>() {
+    let extension = E::info().name;
+    let mut registry = REGISTRY.write();
+    if registry.iter().any(|(name, _)| *name == extension) {
+        return;
+    }
+    registry.extend(
+        E::commands()
+            .into_iter()
+            .map(|command| (extension.clone(), command)),
+    );
+}
+
+/// the registered commands, with the name of their extension
+pub fn registered() -> Vec<(String, PaletteCommand)> {
+    REGISTRY.read().clone()
+}
+
+/// how often and how recently a palette entry was chosen. used to rank the results
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq)]
+pub struct PaletteUsage {
+    pub count: u32,
+    pub last_used: DateTime<Utc>,
+}
+
+impl PaletteUsage {
+    pub fn new() -> Self {
+        Self {
+            count: 0,
+            last_used: Utc::now(),
+        }
+    }
+
+    pub fn record(&mut self) {
+        self.count += 1;
+        self.last_used = Utc::now();
+    }
+
+    /// added to the fuzzy match score of the entry
+    pub fn bonus(&self, now: DateTime<Utc>) -> i64 {
+        let frequency = i64::from(self.count.min(20)) * 5;
+        let elapsed = now - self.last_used;
+        let recency = if elapsed < Duration::hours(1) {
+            50
+        } else if elapsed < Duration::days(1) {
+            25
+        } else if elapsed < Duration::weeks(1) {
+            10
+        } else {
+            0
+        };
+        frequency + recency
+    }
+}
+
+impl Default for PaletteUsage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,36 @@
+//! the local user's presence. how it is shared with friends is in `utils::presence`
+
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::Lazy;
+
+pub use utils::presence::Presence;
+
+static LAST_INPUT: Lazy<Mutex<Instant>> = Lazy::new(|| Mutex::new(Instant::now()));
+
+/// records keyboard or mouse input, which ends auto away
+pub fn touch() {
+    if let Ok(mut last_input) = LAST_INPUT.lock() {
+        *last_input = Instant::now();
+    }
+}
+
+/// whether there was no input for `minutes`. 0 turns auto away off
+pub fn is_idle(minutes: u64) -> bool {
+    minutes > 0
+        && LAST_INPUT
+            .lock()
+            .map(|last_input| last_input.elapsed() >= Duration::from_secs(minutes * 60))
+            .unwrap_or(false)
+}
+
+/// the presence shown to friends. an idle user is away unless they chose another presence
+pub fn effective(chosen: Presence, idle: bool) -> Presence {
+    match chosen {
+        Presence::Online if idle => Presence::Away,
+        chosen => chosen,
+    }
+}
@@ -0,0 +1,57 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+/// what the local user knows about friend requests. DIDs are stored as strings
+#[derive(Serialize, Deserialize, Default, Clone, Eq, PartialEq)]
+pub struct RequestLog {
+    /// senders whose requests are hidden without declining them
+    pub ignored: HashSet<String>,
+    /// when each sender's requests arrived during the last day. used for rate limiting
+    pub history: HashMap<String, Vec<DateTime<Utc>>>,
+    /// the number of incoming requests which were ignored or declined by a filter
+    pub filtered_count: u32,
+    /// notes for outgoing requests, sent as the first message once the request is accepted since a request can't
+    /// carry a message
+    pub intros: HashMap<String, String>,
+    /// the senders of the pending incoming requests, including the ignored ones
+    #[serde(skip)]
+    pub incoming: HashSet<String>,
+}
+
+impl RequestLog {
+    pub fn is_ignored(&self, did: &str) -> bool {
+        self.ignored.contains(did)
+    }
+
+    /// the number of requests from `did` in the day before `now`
+    pub fn recent_count(&self, did: &str, now: DateTime<Utc>) -> usize {
+        self.history
+            .get(did)
+            .map(|times| {
+                times
+                    .iter()
+                    .filter(|time| now - **time < Duration::days(1))
+                    .count()
+            })
+            .unwrap_or(0)
+    }
+
+    /// the number of pending incoming requests which aren't ignored
+    pub fn pending_count(&self) -> usize {
+        self.incoming
+            .iter()
+            .filter(|did| !self.is_ignored(did))
+            .count()
+    }
+
+    /// records a request from `did` and forgets the ones older than a day
+    pub fn record(&mut self, did: &str, now: DateTime<Utc>) {
+        self.history
+            .values_mut()
+            .for_each(|times| times.retain(|time| now - *time < Duration::days(1)));
+        self.history.retain(|_, times| !times.is_empty());
+        self.history.entry(did.to_string()).or_default().push(now);
+    }
+}
@@ -0,0 +1,212 @@
+use chrono::{prelude::*, Duration};
+use serde::{Deserialize, Serialize};
+
+// messages starting with this prefix are used by Uplink to coordinate settings between participants.
+// they are never displayed as regular chat messages.
+// the prefix starts with an invisible separator so that it is unlikely to be typed by a user.
+pub const CONTROL_MESSAGE_PREFIX: &str = "\u{2063}uplink-control:";
+
+/// how long messages are kept before they disappear. set per conversation
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Retention {
+    Off,
+    FiveMinutes,
+    OneHour,
+    OneDay,
+    OneWeek,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Retention::Off
+    }
+}
+
+impl Retention {
+    pub fn all() -> Vec<Retention> {
+        vec![
+            Retention::Off,
+            Retention::FiveMinutes,
+            Retention::OneHour,
+            Retention::OneDay,
+            Retention::OneWeek,
+        ]
+    }
+
+    pub fn as_secs(&self) -> i64 {
+        match self {
+            Retention::Off => 0,
+            Retention::FiveMinutes => 5 * 60,
+            Retention::OneHour => 60 * 60,
+            Retention::OneDay => 24 * 60 * 60,
+            Retention::OneWeek => 7 * 24 * 60 * 60,
+        }
+    }
+
+    pub fn from_secs(secs: i64) -> Option<Retention> {
+        Retention::all().into_iter().find(|r| r.as_secs() == secs)
+    }
+
+    pub fn duration(&self) -> Option<Duration> {
+        match self {
+            Retention::Off => None,
+            _ => Some(Duration::seconds(self.as_secs())),
+        }
+    }
+
+    /// used for the settings menu
+    pub fn label(&self) -> String {
+        match self {
+            Retention::Off => "Off",
+            Retention::FiveMinutes => "5 minutes",
+            Retention::OneHour => "1 hour",
+            Retention::OneDay => "1 day",
+            Retention::OneWeek => "1 week",
+        }
+        .to_string()
+    }
+
+    /// used for the timer in the topbar
+    pub fn short_label(&self) -> String {
+        match self {
+            Retention::Off => "",
+            Retention::FiveMinutes => "5m",
+            Retention::OneHour => "1h",
+            Retention::OneDay => "1d",
+            Retention::OneWeek => "1w",
+        }
+        .to_string()
+    }
+
+    /// returns true if a message sent at `date` should no longer be displayed
+    pub fn is_expired(&self, date: DateTime<Utc>) -> bool {
+        match self.duration() {
+            Some(duration) => date + duration < Utc::now(),
+            None => false,
+        }
+    }
+
+    /// when the conversation has to be checked for expired messages again, given the dates of the messages it
+    /// still has. messages which arrive later are newer, so they expire a full period after `now` at the earliest
+    pub fn next_expiry<I>(&self, dates: I, now: DateTime<Utc>) -> Option<DateTime<Utc>>
+    where
+        I: IntoIterator<Item = DateTime<Utc>>,
+    {
+        let duration = self.duration()?;
+        Some(
+            dates
+                .into_iter()
+                .fold(now + duration, |next, date| next.min(date + duration)),
+        )
+    }
+}
+
+/// settings which are announced to the other participants of a conversation
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ControlMessage {
+    SetRetention(Retention),
+}
+
+impl ControlMessage {
+    pub fn encode(&self) -> Vec<String> {
+        let payload = match self {
+            ControlMessage::SetRetention(retention) => format!("retention={}", retention.as_secs()),
+        };
+        vec![format!("{}{}", CONTROL_MESSAGE_PREFIX, payload)]
+    }
+
+    pub fn decode(value: &[String]) -> Option<ControlMessage> {
+        let payload = value.first()?.strip_prefix(CONTROL_MESSAGE_PREFIX)?;
+        let (key, val) = payload.split_once('=')?;
+        match key {
+            "retention" => val
+                .parse::<i64>()
+                .ok()
+                .and_then(Retention::from_secs)
+                .map(ControlMessage::SetRetention),
+            _ => None,
+        }
+    }
+
+    pub fn is_control_message(value: &[String]) -> bool {
+        value
+            .first()
+            .map(|line| line.starts_with(CONTROL_MESSAGE_PREFIX))
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expiry() {
+        let now = Utc::now();
+        assert!(!Retention::Off.is_expired(now - Duration::weeks(52)));
+        assert!(Retention::FiveMinutes.is_expired(now - Duration::minutes(6)));
+        assert!(!Retention::FiveMinutes.is_expired(now - Duration::minutes(4)));
+        assert!(Retention::OneHour.is_expired(now - Duration::minutes(61)));
+        assert!(!Retention::OneDay.is_expired(now - Duration::hours(23)));
+        assert!(Retention::OneWeek.is_expired(now - Duration::days(8)));
+        // messages from a participant whose clock is ahead
+        assert!(!Retention::FiveMinutes.is_expired(now + Duration::minutes(10)));
+    }
+
+    #[test]
+    fn next_expiry_is_the_oldest_message() {
+        let now = Utc::now();
+        let dates = [now - Duration::minutes(30), now - Duration::minutes(10)];
+        assert_eq!(Retention::Off.next_expiry(dates, now), None);
+        assert_eq!(
+            Retention::OneHour.next_expiry(dates, now),
+            Some(now + Duration::minutes(30))
+        );
+        // without messages, only the ones which arrive later can expire
+        assert_eq!(
+            Retention::OneHour.next_expiry([], now),
+            Some(now + Duration::hours(1))
+        );
+    }
+
+    #[test]
+    fn seconds_round_trip() {
+        for retention in Retention::all() {
+            assert_eq!(Retention::from_secs(retention.as_secs()), Some(retention));
+        }
+        assert_eq!(Retention::from_secs(42), None);
+    }
+
+    #[test]
+    fn control_message_round_trip() {
+        for retention in Retention::all() {
+            let message = ControlMessage::SetRetention(retention);
+            let encoded = message.encode();
+            assert!(ControlMessage::is_control_message(&encoded));
+            assert_eq!(ControlMessage::decode(&encoded), Some(message));
+        }
+    }
+
+    #[test]
+    fn decode_rejects_other_messages() {
+        let decode = |line: &str| ControlMessage::decode(&[line.to_string()]);
+        assert_eq!(decode("retention=300"), None);
+        assert_eq!(
+            decode(&format!("{}retention=42", CONTROL_MESSAGE_PREFIX)),
+            None
+        );
+        assert_eq!(
+            decode(&format!("{}retention=soon", CONTROL_MESSAGE_PREFIX)),
+            None
+        );
+        assert_eq!(
+            decode(&format!("{}volume=300", CONTROL_MESSAGE_PREFIX)),
+            None
+        );
+        assert_eq!(decode(CONTROL_MESSAGE_PREFIX), None);
+        assert_eq!(ControlMessage::decode(&[]), None);
+        assert!(!ControlMessage::is_control_message(&[String::from(
+            "hello"
+        )]));
+    }
+}
@@ -0,0 +1,296 @@
+//! safety fingerprints, which let two users confirm in person or over another channel that they have each other's
+//! DID rather than one belonging to someone with a lookalike username.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+const NUMBER_GROUPS: usize = 12;
+const DIGITS_PER_GROUP: u64 = 100_000;
+const EMOJI_WORDS_COUNT: usize = 7;
+
+// 64 entries, so each byte of the digest picks one without bias
+const EMOJI_WORDS: [(&str, &str); 64] = [
+    ("🐶", "Dog"),
+    ("🐱", "Cat"),
+    ("🦁", "Lion"),
+    ("🐴", "Horse"),
+    ("🦄", "Unicorn"),
+    ("🐷", "Pig"),
+    ("🐘", "Elephant"),
+    ("🐰", "Rabbit"),
+    ("🐼", "Panda"),
+    ("🐓", "Rooster"),
+    ("🐧", "Penguin"),
+    ("🐢", "Turtle"),
+    ("🐟", "Fish"),
+    ("🐙", "Octopus"),
+    ("🦋", "Butterfly"),
+    ("🌷", "Flower"),
+    ("🌳", "Tree"),
+    ("🌵", "Cactus"),
+    ("🍄", "Mushroom"),
+    ("🌏", "Globe"),
+    ("🌙", "Moon"),
+    ("☁️", "Cloud"),
+    ("🔥", "Fire"),
+    ("🍌", "Banana"),
+    ("🍎", "Apple"),
+    ("🍓", "Strawberry"),
+    ("🌽", "Corn"),
+    ("🍕", "Pizza"),
+    ("🎂", "Cake"),
+    ("❤️", "Heart"),
+    ("🙂", "Smiley"),
+    ("🤖", "Robot"),
+    ("🎩", "Hat"),
+    ("👓", "Glasses"),
+    ("🔧", "Spanner"),
+    ("🎅", "Santa"),
+    ("👍", "Thumbs Up"),
+    ("☂️", "Umbrella"),
+    ("⌛", "Hourglass"),
+    ("⏰", "Clock"),
+    ("🎁", "Gift"),
+    ("💡", "Light Bulb"),
+    ("📕", "Book"),
+    ("✏️", "Pencil"),
+    ("📎", "Paperclip"),
+    ("✂️", "Scissors"),
+    ("🔒", "Lock"),
+    ("🔑", "Key"),
+    ("🔨", "Hammer"),
+    ("☎️", "Telephone"),
+    ("🏁", "Flag"),
+    ("🚂", "Train"),
+    ("🚲", "Bicycle"),
+    ("✈️", "Aeroplane"),
+    ("🚀", "Rocket"),
+    ("🏆", "Trophy"),
+    ("⚽", "Ball"),
+    ("🎸", "Guitar"),
+    ("🎺", "Trumpet"),
+    ("🔔", "Bell"),
+    ("⚓", "Anchor"),
+    ("🎧", "Headphones"),
+    ("📁", "Folder"),
+    ("📌", "Pin"),
+];
+
+/// derived from two DIDs. both users see the same fingerprint, whichever of them computes it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fingerprint {
+    /// groups of five digits
+    pub numbers: Vec<String>,
+    /// (emoji, word)
+    pub emojis: Vec<(&'static str, &'static str)>,
+}
+
+// the DIDs are sorted so that the result doesn't depend on who is local
+fn digest(did_a: &str, did_b: &str, round: u8) -> Vec<u8> {
+    let (first, second) = if did_a <= did_b {
+        (did_a, did_b)
+    } else {
+        (did_b, did_a)
+    };
+    let mut hasher = Sha256::new();
+    hasher.update(first.as_bytes());
+    hasher.update([0]);
+    hasher.update(second.as_bytes());
+    hasher.update([round]);
+    hasher.finalize().to_vec()
+}
+
+/// the DIDs are passed as strings
+pub fn fingerprint(did_a: &str, did_b: &str) -> Fingerprint {
+    // each group uses 5 bytes, which is more than two digests
+    let bytes: Vec<u8> = (0..2)
+        .flat_map(|round| digest(did_a, did_b, round))
+        .collect();
+    let numbers = bytes
+        .chunks(5)
+        .take(NUMBER_GROUPS)
+        .map(|chunk| {
+            let value = chunk
+                .iter()
+                .fold(0_u64, |acc, byte| (acc << 8) | *byte as u64);
+            format!("{:05}", value % DIGITS_PER_GROUP)
+        })
+        .collect();
+    let emojis = digest(did_a, did_b, 2)
+        .into_iter()
+        .take(EMOJI_WORDS_COUNT)
+        .map(|byte| EMOJI_WORDS[byte as usize % EMOJI_WORDS.len()])
+        .collect();
+    Fingerprint { numbers, emojis }
+}
+
+/// the username a DID was last seen with, and whether the user has compared fingerprints with its owner
+#[derive(Serialize, Deserialize, Default, Clone, Eq, PartialEq)]
+pub struct KnownIdentity {
+    pub username: String,
+    /// when the user marked the identity as verified
+    pub verified_at: Option<DateTime<Utc>>,
+    /// the verified DID which had this username when this one was first seen, until the user accepts the change
+    #[serde(default)]
+    pub previous_did: Option<String>,
+}
+
+impl KnownIdentity {
+    pub fn is_verified(&self) -> bool {
+        self.verified_at.is_some()
+    }
+}
+
+/// keyed by DID
+pub type KnownIdentities = HashMap<String, KnownIdentity>;
+
+pub fn is_verified(identities: &KnownIdentities, did: &str) -> bool {
+    identities
+        .get(did)
+        .map(KnownIdentity::is_verified)
+        .unwrap_or(false)
+}
+
+/// remembers the username of `did`. a DID seen for the first time with the username of a verified identity is linked
+/// to it, since either the friend's key changed or someone is impersonating them
+pub fn record(identities: &mut KnownIdentities, did: String, username: String) {
+    if !identities.contains_key(&did) && !username.is_empty() {
+        let lowercase = username.to_lowercase();
+        // usernames aren't unique, so the most recently verified one is picked, and then the lowest DID
+        let previous_did = identities
+            .iter()
+            .filter(|(_, identity)| {
+                identity.is_verified() && identity.username.to_lowercase() == lowercase
+            })
+            .max_by(|(did_a, a), (did_b, b)| {
+                a.verified_at
+                    .cmp(&b.verified_at)
+                    .then_with(|| did_b.cmp(did_a))
+            })
+            .map(|(did, _)| did.clone());
+        identities.insert(
+            did,
+            KnownIdentity {
+                username,
+                verified_at: None,
+                previous_did,
+            },
+        );
+        return;
+    }
+    identities.entry(did).or_default().username = username;
+}
+
+/// the verified DID which `did` replaced, while the previous DID still has the same username. the user should compare
+/// fingerprints again, until they verify the new DID or accept the change
+pub fn key_change<'a>(
+    identities: &'a KnownIdentities,
+    did: &str,
+    username: &str,
+) -> Option<&'a str> {
+    let identity = identities.get(did)?;
+    if username.is_empty() || identity.is_verified() {
+        return None;
+    }
+    let (previous_did, previous) = identities.get_key_value(identity.previous_did.as_deref()?)?;
+    (previous.username.to_lowercase() == username.to_lowercase()).then(|| previous_did.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALICE: &str = "did:key:z6MkAlice";
+    const BOB: &str = "did:key:z6MkBob";
+
+    fn verified(identities: &mut KnownIdentities, did: &str, username: &str) {
+        record(identities, did.to_string(), username.to_string());
+        identities.get_mut(did).unwrap().verified_at = Some(Utc::now());
+    }
+
+    #[test]
+    fn both_users_see_the_same_fingerprint() {
+        assert_eq!(fingerprint(ALICE, BOB), fingerprint(BOB, ALICE));
+    }
+
+    #[test]
+    fn fingerprints_are_deterministic() {
+        // a change here would make every user compare fingerprints again
+        let fingerprint_ab = fingerprint(ALICE, BOB);
+        assert_eq!(fingerprint_ab.numbers[0], "28002");
+        assert_eq!(fingerprint_ab.numbers[11], "95848");
+        let words: Vec<&str> = fingerprint_ab
+            .emojis
+            .iter()
+            .map(|(_, word)| *word)
+            .collect();
+        assert_eq!(
+            words,
+            [
+                "Dog",
+                "Globe",
+                "Light Bulb",
+                "Fish",
+                "Ball",
+                "Apple",
+                "Tree"
+            ]
+        );
+        assert_ne!(
+            fingerprint(ALICE, BOB),
+            fingerprint(ALICE, "did:key:z6MkMallory")
+        );
+    }
+
+    #[test]
+    fn fingerprints_have_twelve_groups_and_seven_emojis() {
+        let fingerprint = fingerprint(ALICE, BOB);
+        assert_eq!(fingerprint.numbers.len(), NUMBER_GROUPS);
+        assert!(fingerprint
+            .numbers
+            .iter()
+            .all(|group| group.len() == 5 && group.chars().all(|c| c.is_ascii_digit())));
+        assert_eq!(fingerprint.emojis.len(), EMOJI_WORDS_COUNT);
+    }
+
+    #[test]
+    fn a_new_did_with_a_verified_username_is_a_key_change() {
+        let mut identities = KnownIdentities::new();
+        verified(&mut identities, ALICE, "Alice");
+        record(&mut identities, BOB.to_string(), "alice".to_string());
+        assert_eq!(key_change(&identities, BOB, "alice"), Some(ALICE));
+        assert_eq!(key_change(&identities, ALICE, "Alice"), None);
+    }
+
+    #[test]
+    fn unverified_namesakes_are_not_key_changes() {
+        let mut identities = KnownIdentities::new();
+        record(&mut identities, ALICE.to_string(), "Sam".to_string());
+        record(&mut identities, BOB.to_string(), "Sam".to_string());
+        assert_eq!(key_change(&identities, BOB, "Sam"), None);
+        assert_eq!(key_change(&identities, ALICE, "Sam"), None);
+    }
+
+    #[test]
+    fn a_known_did_taking_a_verified_username_is_not_a_key_change() {
+        let mut identities = KnownIdentities::new();
+        verified(&mut identities, ALICE, "Alice");
+        record(&mut identities, BOB.to_string(), "Bob".to_string());
+        record(&mut identities, BOB.to_string(), "Alice".to_string());
+        assert_eq!(key_change(&identities, BOB, "Alice"), None);
+    }
+
+    #[test]
+    fn the_key_change_ends_when_either_username_changes_or_the_new_did_is_verified() {
+        let mut identities = KnownIdentities::new();
+        verified(&mut identities, ALICE, "Alice");
+        record(&mut identities, BOB.to_string(), "Alice".to_string());
+        assert_eq!(key_change(&identities, BOB, "Bob"), None);
+
+        identities.get_mut(BOB).unwrap().verified_at = Some(Utc::now());
+        assert_eq!(key_change(&identities, BOB, "Alice"), None);
+    }
+}
@@ -1,44 +1,54 @@
 use dioxus::prelude::*;
-use warp::{crypto::DID, multipass::identity::IdentityStatus};
+use warp::crypto::DID;
 
-use utils::Account;
+use utils::presence::{self, Presence};
 
 #[derive(PartialEq, Props)]
 pub struct Props {
     inline: bool,
     remote_did: DID,
-    account: Account,
 }
 
 #[allow(non_snake_case)]
 pub fn ActivityIndicator(cx: Scope<Props>) -> Element {
-    let status = use_state(&cx, || IdentityStatus::Offline);
+    // None while the friend is offline or invisible
+    let status = use_state(&cx, || None::<Presence>);
 
-    let account = cx.props.account.clone();
     let remote_did = cx.props.remote_did.clone();
 
-    use_future(&cx, (&account, status), |(account, status)| async move {
-        loop {
-            if let Ok(current_status) = account.identity_status(&remote_did).await {
-                if *status != current_status {
+    // the presence is refreshed by `presence::watch_presence`
+    use_future(
+        &cx,
+        (&remote_did, status),
+        |(remote_did, status)| async move {
+            let mut presences = presence::watch(&remote_did);
+            loop {
+                let current_status = presence::cached(&presences.borrow(), &remote_did);
+                if *status.current() != current_status {
                     status.set(current_status);
                 }
+                if presences.changed().await.is_err() {
+                    break;
+                }
             }
-            tokio::time::sleep(std::time::Duration::from_millis(300)).await;
-        }
-    });
+        },
+    );
 
     let main_class = match cx.props.inline {
         true => "inline",
         false => "icon-icon",
     };
+    let (status_class, label) = match *status.get() {
+        Some(presence) => (presence.class(), presence.label()),
+        None => ("offline", String::from("Offline")),
+    };
 
     cx.render(rsx! {
         div {
             class: "activity {main_class}",
-            div { class: "bubble {status}" },
+            div { class: "bubble {status_class}" },
             p {
-                "{status}"
+                "{label}"
             }
         }
     })
@@ -16,6 +16,8 @@ pub struct Props {
     size: usize,
     thumbnail: String,
     storage: Storage,
+    // set to the file name to open the preview
+    preview: UseState<Option<String>>,
 }
 
 #[allow(non_snake_case)]
@@ -34,10 +36,13 @@ pub fn File(cx: Scope<Props>) -> Element {
     let file_name_complete_ref = use_ref(&cx, || cx.props.name.clone());
 
     let file_size = files_functions::format_item_size(cx.props.size);
-    let file_thumb = &cx.props.thumbnail.clone();
+    // a regenerated thumbnail is shown before the file list is read again
+    let regenerated_thumb = use_state(&cx, || None::<String>);
+    let file_thumb = regenerated_thumb.get().clone().unwrap_or_else(|| cx.props.thumbnail.clone());
+    let can_regenerate_thumb = files_functions::has_thumbnail(&cx.props.name);
 
     let show_edit_name_script = include_str!("./show_edit_name.js").replace("file_id", &file_id);
-    let file_component = if cx.props.thumbnail.is_empty() {
+    let file_component = if file_thumb.is_empty() {
         rsx!(Icon {
             icon: Shape::Document
         })
@@ -63,10 +68,19 @@ pub fn File(cx: Scope<Props>) -> Element {
                 *DRAG_FILE_IN_APP_EVENT.write() = DragFileInApp::cancel();
             },
             id: "{file_id}-file",
+            ondblclick: move |_| cx.props.preview.set(Some(file_name_complete_ref.read().clone())),
                 ContextMenu {
                     parent: format!("{}-file", file_id.clone()),
                     items: cx.render(
                         rsx! {
+                                ContextItem {
+                                    icon: Shape::Eye,
+                                    onpressed: move |_| {
+                                        hide_edit_name_element(cx);
+                                        cx.props.preview.set(Some(file_name_complete_ref.read().clone()));
+                                    },
+                                    text: String::from("Preview")
+                                },
                                 ContextItem {
                                     icon: Shape::PencilSquare,
                                     onpressed: move |_| {
@@ -100,6 +114,26 @@ pub fn File(cx: Scope<Props>) -> Element {
                                     },
                                     text: String::from("Download")
                                 },
+                                can_regenerate_thumb.then(|| rsx!(
+                                    ContextItem {
+                                        icon: Shape::Photo,
+                                        onpressed: move |_| {
+                                            hide_edit_name_element(cx);
+                                            let file_storage = cx.props.storage.clone();
+                                            let file_name = &*file_name_complete_ref.read();
+                                            cx.spawn({
+                                                to_owned![file_storage, file_name, regenerated_thumb];
+                                                async move {
+                                                    match files_functions::set_thumbnail(file_storage, file_name.clone()).await {
+                                                        Ok(thumbnail) => regenerated_thumb.set(Some(thumbnail)),
+                                                        Err(error) => log::error!("Error regenerating the thumbnail of {}: {error}", &file_name),
+                                                    };
+                                                }
+                                            });
+                                        },
+                                        text: String::from("Regenerate Thumbnail")
+                                    }
+                                )),
                                 hr {},
                                 ContextItem {
                                     onpressed: move |_| {
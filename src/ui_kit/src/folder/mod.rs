@@ -3,7 +3,7 @@ use std::path::PathBuf;
 use dioxus::{prelude::*, core::to_owned, desktop::{use_window, wry::webview::FileDropEvent}};
 use dioxus_elements::KeyCode;
 use dioxus_heroicons::{outline::Shape, Icon};
-use utils::{Storage, DRAG_FILE_IN_APP_EVENT, DragFileInApp, DRAG_FILE_EVENT, files_functions};
+use utils::{Storage, DRAG_FILE_IN_APP_EVENT, DragFileInApp, DRAG_FILE_EVENT, files_functions, upload_queue};
 use crate::context_menu::{ContextItem, ContextMenu};
 
 #[derive(PartialEq, Eq, Copy, Clone)]
@@ -81,24 +81,8 @@ pub fn Folder(cx: Scope<Props>) -> Element {
                               
                                 if !files_local_path.is_empty() {
                                     *drag_over_folder.write_silent() = false;
-                                    match file_storage.select(&folder_name_complete_ref.read().clone()) {
-                                        Ok(_) => (),
-                                        Err(error) => log::error!("Error selecting new current directory folder: {error}"),
-                                    };
-                                    for file_path in &files_local_path {
-                                        files_functions::upload_file(
-                                            file_storage.clone(),
-                                            file_path.clone(),
-                                            eval_script.clone(), 
-                                        )
-                                        .await;
-                                        tokio::time::sleep(std::time::Duration::from_millis(150)).await;
-                                        log::info!("{} file uploaded!", file_path.to_string_lossy());
-                                       
-                                    }
-                                    if let Err(error) = file_storage.go_back() {
-                                        log::error!("Error on go back a directory: {error}");
-                                    };
+                                    let folder_path = file_storage.get_path().join(&*folder_name_complete_ref.read());
+                                    upload_queue::enqueue(files_local_path, folder_path);
                                     update_current_dir.set(());
                                     break;
                                 }
@@ -0,0 +1,113 @@
+//! a Constellation for tests. uploads don't store anything, they report the scripted progress
+
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use futures::stream::{self, BoxStream, StreamExt};
+use warp::{
+    constellation::{directory::Directory, Constellation, Progression},
+    error::Error,
+    module::Module,
+    Extension, SingleHandle,
+};
+
+use crate::Storage;
+
+#[derive(Clone)]
+pub struct FakeConstellation {
+    root: Directory,
+    path: PathBuf,
+    modified: DateTime<Utc>,
+    // what every upload reports
+    progress: Vec<Progression>,
+}
+
+impl FakeConstellation {
+    pub fn new(progress: Vec<Progression>) -> Self {
+        Self {
+            root: Directory::new("root"),
+            path: PathBuf::new(),
+            modified: Utc::now(),
+            progress,
+        }
+    }
+
+    pub fn storage(progress: Vec<Progression>) -> Storage {
+        Storage(Box::new(Self::new(progress)))
+    }
+}
+
+impl Extension for FakeConstellation {
+    fn id(&self) -> String {
+        String::from("fake-constellation")
+    }
+
+    fn name(&self) -> String {
+        String::from("Fake Constellation")
+    }
+
+    fn module(&self) -> Module {
+        Module::FileSystem
+    }
+}
+
+impl SingleHandle for FakeConstellation {}
+
+#[async_trait::async_trait]
+impl Constellation for FakeConstellation {
+    fn modified(&self) -> DateTime<Utc> {
+        self.modified
+    }
+
+    fn root_directory(&self) -> Directory {
+        self.root.clone()
+    }
+
+    fn set_path(&mut self, path: PathBuf) {
+        self.path = path;
+    }
+
+    fn get_path(&self) -> PathBuf {
+        self.path.clone()
+    }
+
+    async fn put_stream(
+        &mut self,
+        _: &str,
+        _: Option<usize>,
+        _: BoxStream<'static, Vec<u8>>,
+    ) -> Result<BoxStream<'static, Progression>, Error> {
+        Ok(stream::iter(self.progress.clone()).boxed())
+    }
+}
+
+pub fn current(name: &str, current: usize, total: usize) -> Progression {
+    Progression::CurrentProgress {
+        name: name.to_string(),
+        current,
+        total: Some(total),
+    }
+}
+
+pub fn complete(name: &str, total: usize) -> Progression {
+    Progression::ProgressComplete {
+        name: name.to_string(),
+        total: Some(total),
+    }
+}
+
+pub fn failed(name: &str, error: &str) -> Progression {
+    Progression::ProgressFailed {
+        name: name.to_string(),
+        last_size: None,
+        error: Some(error.to_string()),
+    }
+}
+
+/// the progress of an upload of `name` to the fake
+pub async fn upload(storage: &mut Storage, name: &str) -> BoxStream<'static, Progression> {
+    storage
+        .put_stream(name, None, stream::empty().boxed())
+        .await
+        .expect("the fake accepts every upload")
+}
@@ -1,118 +1,15 @@
-use std::{path::{PathBuf, Path}, io::Cursor, ffi::OsStr};
+use std::{path::{Path, PathBuf}, io::Cursor, ffi::OsStr};
 
-use dioxus::desktop::DesktopContext;
-use futures::StreamExt;
 use mime::*;
-use tokio_util::io::ReaderStream;
-use warp::{constellation::{Progression, directory::Directory}, error::Error};
-use image::io::Reader as ImageReader;
+use warp::{constellation::directory::Directory, error::Error};
+use image::{io::Reader as ImageReader, DynamicImage, ImageOutputFormat};
 
 use crate::Storage;
 
-pub async fn upload_file(mut file_storage: Storage, file_path: PathBuf, eval_script: DesktopContext) {
-    let mut filename = match file_path
-        .file_name()
-        .map(|file| file.to_string_lossy().to_string())
-    {
-        Some(file) => file,
-        None => return,
-    };
-
-    let local_path = Path::new(&file_path).to_string_lossy().to_string();
-    let original = filename.clone();
-    let file = PathBuf::from(&original);
-
-    let current_directory = match file_storage.current_directory() {
-        Ok(current_dir) => current_dir, 
-        _ => return
-    };
-
-    filename = verify_duplicate_name(current_directory, filename.clone(), file);
-
-    let tokio_file = match tokio::fs::File::open(&local_path).await {
-        Ok(file) => file,
-        Err(error) => {
-            log::error!("Error on get tokio file, cancelling upload action, error: {error}");
-            return;
-        }
-    };
-
-    let total_size_for_stream = match tokio_file.metadata().await {
-        Ok(data) => Some(data.len() as usize),
-        Err(error) => {
-            log::error!("Error getting metadata: {:?}", error);
-            None
-        }
-    };
-
-    let file_stream = ReaderStream::new(tokio_file)
-        .filter_map(|x| async { x.ok() })
-        .map(|x| x.into());
-
-    match file_storage
-        .put_stream(&filename, total_size_for_stream, file_stream.boxed())
-        .await
-    {
-        Ok(mut upload_progress) => {
-            while let Some(upload_progress) = upload_progress.next().await {
-                match upload_progress {
-                    Progression::CurrentProgress {
-                        name,
-                        current,
-                        total,
-                    } => {
-                        log::info!("Written {} MB for {name}", current / 1024 / 1024);
-                        if let Some(total) = total {
-                            let mut selector_without_percentage =
-                                "document.getElementById('dropzone').value = '".to_owned();
-
-                            let percentage =
-                                ((((current as f64) / (total as f64)) * 100.) as usize).to_string();
-                            selector_without_percentage.push_str(&percentage);
-
-                            let ending_string = "% uploaded'";
-                            selector_without_percentage.push_str(ending_string);
-
-                            eval_script.eval(&selector_without_percentage);
-
-                            log::info!(
-                                "{}% completed",
-                                (((current as f64) / (total as f64)) * 100.) as usize
-                            )
-                        }
-                    }
-                    Progression::ProgressComplete { name, total } => {
-                        log::info!(
-                            "{name} has been uploaded with {} MB",
-                            total.unwrap_or_default() / 1024 / 1024
-                        );
-                    }
-                    Progression::ProgressFailed {
-                        name,
-                        last_size,
-                        error,
-                    } => {
-                        log::info!(
-                            "{name} failed to upload at {} MB due to: {}",
-                            last_size.unwrap_or_default(),
-                            error.unwrap_or_default()
-                        );
-                    }
-                }
-            }
-            match set_thumbnail_if_file_is_image(file_storage.clone(), filename.clone()).await {
-                Ok(success) => log::info!("{:?}", success), 
-                Err(error) => log::error!("Error on update thumbnail: {:?}", error), 
-            }  
-            log::info!("{:?} file uploaded!", &filename);
-        }, 
-        Err(error) => log::error!("Error when upload file: {:?}", error)
-        
-    }
-
-
-    
-}
+// the largest width and height of a thumbnail, in pixels
+const THUMBNAIL_SIZE: u32 = 256;
+const THUMBNAIL_QUALITY: u8 = 80;
+const THUMBNAIL_EXTENSIONS: [&str; 8] = ["png", "jpg", "jpeg", "webp", "gif", "bmp", "tif", "tiff"];
 
 pub fn verify_duplicate_name(current_directory: Directory, filename: String, file_pathbuf: PathBuf) -> String {
     let mut count_index_for_duplicate_filename = 1;
@@ -138,44 +35,90 @@ pub fn verify_duplicate_name(current_directory: Directory, filename: String, fil
     new_file_name
 }
 
-async fn set_thumbnail_if_file_is_image(
+/// (re)generates the thumbnail of the file `filename` in the current directory. returns the thumbnail
+pub async fn set_thumbnail(
     file_storage: Storage,
-    filename_to_save: String,
-) -> Result<String, Box<dyn std::error::Error>> {
-    let item = file_storage.current_directory()?.get_item(&filename_to_save)?;
-    let parts_of_filename: Vec<&str> = filename_to_save.split('.').collect();
+    filename: String,
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    // checked first, so that other files aren't downloaded
+    if !has_thumbnail(&filename) {
+        return Err(Box::from(Error::InvalidItem));
+    }
+    let item = file_storage.current_directory()?.get_item(&filename)?;
+    let file = file_storage.get_buffer(&filename).await?;
+
+    // decoding and resizing a large photo takes a while
+    let thumbnail = tokio::task::spawn_blocking(move || make_thumbnail(&file)).await??;
+    item.set_thumbnail(&thumbnail);
+    log::info!("{} thumbnail updated with success!", item.name());
+    Ok(thumbnail)
+}
 
-    let file = file_storage.get_buffer(&filename_to_save).await?;
+/// makes the thumbnail of a local file, e.g. one being uploaded, without going through the storage
+pub async fn local_thumbnail(
+    path: PathBuf,
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let file = tokio::fs::read(path).await?;
+    let thumbnail = tokio::task::spawn_blocking(move || make_thumbnail(&file)).await??;
+    Ok(thumbnail)
+}
+
+/// whether a thumbnail can be made for the file, going by its extension
+pub fn has_thumbnail(filename: &str) -> bool {
+    Path::new(filename)
+        .extension()
+        .and_then(OsStr::to_str)
+        .map(|extension| THUMBNAIL_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
 
-    // Guarantee that is an image that has been uploaded
-    ImageReader::new(Cursor::new(&file))
+// a data URL of the image downscaled to fit the thumbnail size. images with transparency stay PNG, the others
+// become JPEG. GIFs use their first frame
+fn make_thumbnail(file: &[u8]) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let mut image = ImageReader::new(Cursor::new(file))
         .with_guessed_format()?
         .decode()?;
+    // downscaled first, so that only the thumbnail is rotated. the bounds are square, so the rotation keeps it in them
+    if image.width() > THUMBNAIL_SIZE || image.height() > THUMBNAIL_SIZE {
+        image = image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+    }
+    image = apply_orientation(image, exif_orientation(file));
 
-    // Since files selected are filtered to be jpg, jpeg, png or svg the last branch is not reachable
-    let mime = match parts_of_filename
-        .iter()
-        .map(|extension| extension.to_lowercase())
-        .last()
-    {
-        Some(m) => match m.as_str() {
-            "png" => IMAGE_PNG.to_string(),
-            "jpg" => IMAGE_JPEG.to_string(),
-            "jpeg" => IMAGE_JPEG.to_string(),
-            "svg" => IMAGE_SVG.to_string(),
-            &_ => "".to_string(),
-        },
-        None => "".to_string(),
+    let mut bytes = Vec::new();
+    let mime = if image.color().has_alpha() {
+        image.write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Png)?;
+        IMAGE_PNG
+    } else {
+        image
+            .to_rgb8()
+            .write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Jpeg(THUMBNAIL_QUALITY))?;
+        IMAGE_JPEG
     };
+    Ok(format!("data:{};base64,{}", mime, base64::encode(&bytes)))
+}
 
-    if !file.is_empty() || !mime.is_empty() {
-        let prefix = format!("data:{};base64,", mime);
-        let base64_image = base64::encode(&file);
-        let img = prefix + base64_image.as_str();
-        item.set_thumbnail(&img);
-        Ok(format_args!("{} thumbnail updated with success!", item.name()).to_string())
-    } else {
-        Err(Box::from(Error::InvalidItem))
+// the EXIF orientation, 1 to 8. 1, the default, is upright
+fn exif_orientation(file: &[u8]) -> u32 {
+    exif::Reader::new()
+        .read_from_container(&mut Cursor::new(file))
+        .ok()
+        .and_then(|exif| {
+            exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY)
+                .and_then(|field| field.value.get_uint(0))
+        })
+        .unwrap_or(1)
+}
+
+fn apply_orientation(image: DynamicImage, orientation: u32) -> DynamicImage {
+    match orientation {
+        2 => image.fliph(),
+        3 => image.rotate180(),
+        4 => image.flipv(),
+        5 => image.rotate90().fliph(),
+        6 => image.rotate90(),
+        7 => image.rotate270().fliph(),
+        8 => image.rotate270(),
+        _ => image,
     }
 }
 
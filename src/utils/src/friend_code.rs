@@ -0,0 +1,129 @@
+//! friend codes as QR codes. a friend code is a DID without the `did:key:` prefix.
+
+use std::{io::Cursor, path::Path};
+
+use image::{DynamicImage, ImageOutputFormat, Luma, RgbaImage};
+use qrcode::QrCode;
+
+pub const DID_PREFIX: &str = "did:key:";
+// the rendered QR code is at least this many pixels wide, so that it can be scanned from a screen
+const QR_SIZE: u32 = 256;
+
+/// the friend code for a DID, which is passed as a string
+pub fn from_did(did: &str) -> String {
+    did.trim().trim_start_matches(DID_PREFIX).to_string()
+}
+
+pub fn render_qr(code: &str) -> Result<DynamicImage, String> {
+    let qr = QrCode::new(code.as_bytes()).map_err(|e| e.to_string())?;
+    let image = qr
+        .render::<Luma<u8>>()
+        .min_dimensions(QR_SIZE, QR_SIZE)
+        .build();
+    Ok(DynamicImage::ImageLuma8(image))
+}
+
+pub fn qr_png(code: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = vec![];
+    render_qr(code)?
+        .write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Png)
+        .map_err(|e| e.to_string())?;
+    Ok(bytes)
+}
+
+/// for displaying the QR code in an `img`
+pub fn qr_data_uri(code: &str) -> Result<String, String> {
+    Ok(format!(
+        "data:image/png;base64,{}",
+        base64::encode(qr_png(code)?)
+    ))
+}
+
+pub fn save_qr_png(code: &str, path: &Path) -> Result<(), String> {
+    std::fs::write(path, qr_png(code)?).map_err(|e| e.to_string())
+}
+
+/// the friend code in the first QR code found in the image. QR codes containing a full DID are accepted too
+pub fn decode_qr(image: &DynamicImage) -> Option<String> {
+    let mut prepared = rqrr::PreparedImage::prepare(image.to_luma8());
+    prepared
+        .detect_grids()
+        .into_iter()
+        .filter_map(|grid| match grid.decode() {
+            Ok((_, content)) => Some(content),
+            Err(e) => {
+                log::debug!("failed to decode a QR code: {}", e);
+                None
+            }
+        })
+        .map(|content| from_did(&content))
+        .find(|code| !code.is_empty())
+}
+
+pub fn decode_qr_file(path: &Path) -> Result<String, String> {
+    let image = image::open(path).map_err(|e| e.to_string())?;
+    decode_qr(&image).ok_or_else(|| String::from("no QR code found"))
+}
+
+/// decodes an image from the clipboard, which is RGBA with 8 bits per channel
+pub fn decode_qr_rgba(width: u32, height: u32, bytes: Vec<u8>) -> Result<String, String> {
+    let image = RgbaImage::from_raw(width, height, bytes)
+        .ok_or_else(|| String::from("the image data doesn't match its size"))?;
+    decode_qr(&DynamicImage::ImageRgba8(image)).ok_or_else(|| String::from("no QR code found"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CODE: &str = "z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK";
+
+    #[test]
+    fn round_trip() {
+        let image = render_qr(CODE).unwrap();
+        assert_eq!(decode_qr(&image).as_deref(), Some(CODE));
+    }
+
+    #[test]
+    fn round_trip_with_did_prefix() {
+        let did = format!("{}{}", DID_PREFIX, CODE);
+        assert_eq!(from_did(&did), CODE);
+        let image = render_qr(&did).unwrap();
+        assert_eq!(decode_qr(&image).as_deref(), Some(CODE));
+    }
+
+    #[test]
+    fn round_trip_through_png() {
+        let image = image::load_from_memory(&qr_png(CODE).unwrap()).unwrap();
+        assert_eq!(decode_qr(&image).as_deref(), Some(CODE));
+    }
+
+    #[test]
+    fn decode_rgba() {
+        let image = render_qr(CODE).unwrap().to_rgba8();
+        let (width, height) = image.dimensions();
+        assert_eq!(
+            decode_qr_rgba(width, height, image.into_raw()),
+            Ok(CODE.to_string())
+        );
+    }
+
+    #[test]
+    fn decode_rgba_size_mismatch() {
+        let image = render_qr(CODE).unwrap().to_rgba8();
+        let (width, height) = image.dimensions();
+        assert_eq!(
+            decode_qr_rgba(width + 1, height, image.into_raw()),
+            Err(String::from("the image data doesn't match its size"))
+        );
+    }
+
+    #[test]
+    fn decode_rgba_without_qr_code() {
+        let blank = RgbaImage::from_pixel(64, 64, image::Rgba([255, 255, 255, 255]));
+        assert_eq!(
+            decode_qr_rgba(64, 64, blank.into_raw()),
+            Err(String::from("no QR code found"))
+        );
+    }
+}
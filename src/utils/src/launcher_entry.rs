@@ -0,0 +1,142 @@
+//! the unread badge on Linux docks and launchers which implement Unity's `com.canonical.Unity.LauncherEntry`
+//! interface, such as Plank, Dash to Dock and KDE's task manager.
+
+use std::collections::HashMap;
+
+use once_cell::sync::Lazy;
+use tokio::sync::Mutex;
+use zbus::{zvariant::Value, Connection};
+
+pub const INTERFACE: &str = "com.canonical.Unity.LauncherEntry";
+/// identifies the application to the launcher. must match the name of the installed desktop file
+pub const APP_URI: &str = "application://im.satellite.uplink.desktop";
+const OBJECT_PATH: &str = "/im/satellite/uplink/LauncherEntry";
+
+// connecting is slow compared to emitting a signal, so the session bus connection is kept
+static SESSION: Lazy<Mutex<Option<Connection>>> = Lazy::new(|| Mutex::new(None));
+
+/// emits an `Update` signal with the count and urgency on `connection`. a count of 0 hides the badge.
+pub async fn emit_update(connection: &Connection, count: u32) -> zbus::Result<()> {
+    let mut properties: HashMap<&str, Value> = HashMap::new();
+    properties.insert("count", Value::I64(count as i64));
+    properties.insert("count-visible", Value::Bool(count > 0));
+    properties.insert("urgent", Value::Bool(count > 0));
+    connection
+        .emit_signal(
+            None::<&str>,
+            OBJECT_PATH,
+            INTERFACE,
+            "Update",
+            &(APP_URI, properties),
+        )
+        .await
+}
+
+/// updates the badge using the session bus from `DBUS_SESSION_BUS_ADDRESS`
+pub async fn set_count(count: u32) -> Result<(), String> {
+    let mut session = SESSION.lock().await;
+    if session.is_none() {
+        *session = Some(Connection::session().await.map_err(|e| e.to_string())?);
+    }
+    let result = match session.as_ref() {
+        Some(connection) => emit_update(connection, count).await,
+        None => return Ok(()),
+    };
+    // the bus may have restarted. reconnect next time
+    if result.is_err() {
+        *session = None;
+    }
+    result.map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader},
+        process::{Child, Command, Stdio},
+    };
+
+    use futures::StreamExt;
+    use zbus::{fdo::DBusProxy, ConnectionBuilder, MatchRule, MessageStream, MessageType};
+
+    use super::*;
+
+    // a bus of its own, so the test neither needs nor disturbs the user's session
+    struct PrivateBus {
+        daemon: Child,
+        address: String,
+    }
+
+    impl PrivateBus {
+        fn start() -> Self {
+            let mut daemon = Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .spawn()
+                .expect("dbus-daemon must be installed");
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.take().expect("stdout is piped"))
+                .read_line(&mut address)
+                .expect("dbus-daemon prints its address");
+            Self {
+                daemon,
+                address: address.trim().to_string(),
+            }
+        }
+
+        async fn connect(&self) -> Connection {
+            ConnectionBuilder::address(self.address.as_str())
+                .expect("the address is valid")
+                .build()
+                .await
+                .expect("the bus accepts connections")
+        }
+    }
+
+    impl Drop for PrivateBus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "starts a dbus-daemon"]
+    async fn update_reaches_the_launcher() {
+        let bus = PrivateBus::start();
+        let launcher = bus.connect().await;
+        let rule = MatchRule::builder()
+            .msg_type(MessageType::Signal)
+            .interface(INTERFACE)
+            .unwrap()
+            .member("Update")
+            .unwrap()
+            .build();
+        DBusProxy::new(&launcher)
+            .await
+            .unwrap()
+            .add_match_rule(rule)
+            .await
+            .unwrap();
+        let mut signals = MessageStream::from(&launcher);
+
+        let app = bus.connect().await;
+        emit_update(&app, 3).await.unwrap();
+        emit_update(&app, 0).await.unwrap();
+
+        let mut updates = vec![];
+        while updates.len() < 2 {
+            let message = signals.next().await.unwrap().unwrap();
+            if message.member().as_deref() != Some("Update") {
+                continue;
+            }
+            let (uri, properties): (String, HashMap<String, zbus::zvariant::OwnedValue>) =
+                message.body().unwrap();
+            assert_eq!(uri, APP_URI);
+            let count = i64::try_from(properties["count"].clone()).unwrap();
+            let visible = bool::try_from(properties["count-visible"].clone()).unwrap();
+            updates.push((count, visible));
+        }
+        assert_eq!(updates, vec![(3, true), (0, false)]);
+    }
+}
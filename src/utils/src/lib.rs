@@ -1,7 +1,17 @@
 pub mod extensions;
+#[cfg(test)]
+mod fake_constellation;
+pub mod friend_code;
+#[cfg(target_os = "linux")]
+pub mod launcher_entry;
+#[cfg(target_os = "linux")]
+pub mod notification_actions;
 pub mod notifications;
+pub mod presence;
 pub mod sounds;
 pub mod files_functions;
+pub mod transfers;
+pub mod upload_queue;
 
 use clap::Parser;
 use dioxus::desktop::wry::webview::FileDropEvent;
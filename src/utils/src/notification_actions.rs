@@ -0,0 +1,155 @@
+//! clicks on notifications, which the freedesktop notification server reports as `ActionInvoked` signals. one task
+//! listens for the signals of every notification, instead of a thread waiting on each notification.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, MutexGuard,
+    },
+};
+
+use futures::StreamExt;
+use once_cell::sync::Lazy;
+use zbus::{fdo::DBusProxy, Connection, MatchRule, MessageStream, MessageType};
+
+const INTERFACE: &str = "org.freedesktop.Notifications";
+/// the action invoked by clicking the notification itself
+pub const DEFAULT_ACTION: &str = "default";
+
+type Handler = Box<dyn FnOnce() + Send>;
+
+// the click handlers of the notifications which are still open, by notification id
+static HANDLERS: Lazy<Mutex<HashMap<u32, Handler>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+static LISTENING: AtomicBool = AtomicBool::new(false);
+
+// a panicking handler doesn't stop the others
+fn handlers() -> MutexGuard<'static, HashMap<u32, Handler>> {
+    HANDLERS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+enum Signal {
+    ActionInvoked(u32, String),
+    NotificationClosed(u32),
+}
+
+/// calls `on_click` when the notification with `id` is clicked. the handler is dropped when the notification closes
+pub fn on_click<F>(id: u32, on_click: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    handlers().insert(id, Box::new(on_click));
+    if LISTENING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    match tokio::runtime::Handle::try_current() {
+        Ok(runtime) => {
+            runtime.spawn(async {
+                if let Err(e) = listen().await {
+                    log::error!("stopped listening for notification clicks: {}", e);
+                }
+                // the next notification tries again
+                LISTENING.store(false, Ordering::SeqCst);
+            });
+        }
+        Err(e) => {
+            log::error!("can't listen for notification clicks: {}", e);
+            LISTENING.store(false, Ordering::SeqCst);
+        }
+    }
+}
+
+async fn listen() -> zbus::Result<()> {
+    let connection = Connection::session().await?;
+    let dbus = DBusProxy::new(&connection).await?;
+    for member in ["ActionInvoked", "NotificationClosed"] {
+        let rule = MatchRule::builder()
+            .msg_type(MessageType::Signal)
+            .interface(INTERFACE)?
+            .member(member)?
+            .build();
+        dbus.add_match_rule(rule).await?;
+    }
+    let mut messages = MessageStream::from(&connection);
+    while let Some(message) = messages.next().await {
+        let message = message?;
+        if message.interface().as_deref() != Some(INTERFACE) {
+            continue;
+        }
+        let signal = match message.member().as_deref() {
+            Some("ActionInvoked") => message
+                .body::<(u32, String)>()
+                .map(|(id, action)| Signal::ActionInvoked(id, action)),
+            Some("NotificationClosed") => message
+                .body::<(u32, u32)>()
+                .map(|(id, _reason)| Signal::NotificationClosed(id)),
+            _ => continue,
+        };
+        match signal {
+            Ok(signal) => handle(signal),
+            Err(e) => log::error!("unexpected notification signal: {}", e),
+        }
+    }
+    Ok(())
+}
+
+fn handle(signal: Signal) {
+    match signal {
+        Signal::ActionInvoked(id, action) if action == DEFAULT_ACTION => {
+            // taken out first, so that the handler can show another notification
+            let handler = handlers().remove(&id);
+            if let Some(handler) = handler {
+                handler();
+            }
+        }
+        Signal::ActionInvoked(..) => {}
+        Signal::NotificationClosed(id) => {
+            handlers().remove(&id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{atomic::AtomicUsize, Arc};
+
+    use super::*;
+
+    fn counting(id: u32) -> Arc<AtomicUsize> {
+        let clicks = Arc::new(AtomicUsize::new(0));
+        let counter = clicks.clone();
+        handlers().insert(
+            id,
+            Box::new(move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }),
+        );
+        clicks
+    }
+
+    #[test]
+    fn a_click_calls_the_handler_once() {
+        let clicks = counting(1001);
+        handle(Signal::ActionInvoked(1001, String::from(DEFAULT_ACTION)));
+        handle(Signal::ActionInvoked(1001, String::from(DEFAULT_ACTION)));
+        assert_eq!(clicks.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn other_actions_and_notifications_are_ignored() {
+        let clicks = counting(1002);
+        handle(Signal::ActionInvoked(1002, String::from("reply")));
+        handle(Signal::ActionInvoked(1003, String::from(DEFAULT_ACTION)));
+        assert_eq!(clicks.load(Ordering::SeqCst), 0);
+        assert!(handlers().contains_key(&1002));
+    }
+
+    #[test]
+    fn closing_drops_the_handler() {
+        let clicks = counting(1004);
+        handle(Signal::NotificationClosed(1004));
+        assert!(!handlers().contains_key(&1004));
+        handle(Signal::ActionInvoked(1004, String::from(DEFAULT_ACTION)));
+        assert_eq!(clicks.load(Ordering::SeqCst), 0);
+    }
+}
@@ -16,11 +16,43 @@ pub fn PushNotification(title: String, content: String, notification_sound: Soun
     Play(notification_sound);
 }
 
-pub fn set_badge(count: u32) -> Result<(), String> {
-    #[cfg(not(target_os = "macos"))]
+/// shows a notification and calls `on_click` if it is clicked. clicks are only reported by the freedesktop
+/// notification server, so on other platforms this behaves like `PushNotification`.
+pub fn push_notification_with_action<F>(
+    title: String,
+    content: String,
+    notification_sound: Sounds,
+    on_click: F,
+) where
+    F: FnOnce() + Send + 'static,
+{
+    let summary = format!("Uplink - {}", title);
+    let handle = Notification::new()
+        .summary(summary.as_ref())
+        .body(&content)
+        .action("default", "Open")
+        .show();
+    match handle {
+        #[cfg(target_os = "linux")]
+        Ok(handle) => crate::notification_actions::on_click(handle.id(), on_click),
+        #[cfg(not(target_os = "linux"))]
+        Ok(_) => {
+            let _ = on_click;
+        }
+        Err(e) => log::error!("failed to show notification: {}", e),
+    }
+    Play(notification_sound);
+}
+
+/// shows the number of unread messages on the dock icon. on Linux this goes through the Unity LauncherEntry API
+pub async fn set_badge(count: u32) -> Result<(), String> {
+    #[cfg(target_os = "linux")]
+    crate::launcher_entry::set_count(count).await?;
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
     let _ = count;
+    // AppKit may only be used from the main thread
     #[cfg(target_os = "macos")]
-    unsafe {
+    dispatch::Queue::main().exec_async(move || unsafe {
         use cocoa::{appkit::NSApp, base::nil, foundation::NSString};
 
         let label = if count == 0 {
@@ -30,6 +62,6 @@ pub fn set_badge(count: u32) -> Result<(), String> {
         };
         let dock_tile: cocoa::base::id = msg_send![NSApp(), dockTile];
         let _: cocoa::base::id = msg_send![dock_tile, setBadgeLabel: label];
-    }
+    });
     Ok(())
 }
@@ -0,0 +1,174 @@
+//! presence is shared with friends through the status message, since identities have no presence field. a
+//! presence other than online is a tag such as `[away]` in front of the message.
+
+use std::{collections::HashMap, time::Duration};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use warp::{
+    crypto::DID,
+    error::Error,
+    multipass::identity::{IdentityStatus, IdentityUpdate},
+};
+
+use crate::Account;
+
+/// how often `watch_presence` asks for the presence of the watched users
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+
+// the users whose presence is shown, keyed by DID. friends are always watched
+static WATCHED: Lazy<warp::sync::RwLock<HashMap<String, DID>>> =
+    Lazy::new(|| warp::sync::RwLock::new(HashMap::new()));
+// the last known presence of the watched users, keyed by DID. offline and invisible users are missing
+static PRESENCES: Lazy<watch::Sender<HashMap<String, Presence>>> =
+    Lazy::new(|| watch::channel(HashMap::new()).0);
+
+/// the availability shown to friends
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Presence {
+    Online,
+    Away,
+    Busy,
+    Invisible,
+}
+
+impl Default for Presence {
+    fn default() -> Self {
+        Presence::Online
+    }
+}
+
+impl Presence {
+    pub fn all() -> Vec<Presence> {
+        vec![
+            Presence::Online,
+            Presence::Away,
+            Presence::Busy,
+            Presence::Invisible,
+        ]
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Presence::Online => "Online",
+            Presence::Away => "Away",
+            Presence::Busy => "Busy",
+            Presence::Invisible => "Invisible",
+        }
+        .to_string()
+    }
+
+    /// the css class of the presence bubble
+    pub fn class(&self) -> &'static str {
+        match self {
+            Presence::Online => "online",
+            Presence::Away => "away",
+            Presence::Busy => "busy",
+            Presence::Invisible => "offline",
+        }
+    }
+
+    // invisible users are shown as offline, so the tag doesn't reveal them
+    fn tag(&self) -> Option<&'static str> {
+        match self {
+            Presence::Online => None,
+            Presence::Away => Some("[away]"),
+            Presence::Busy => Some("[busy]"),
+            Presence::Invisible => Some("[offline]"),
+        }
+    }
+}
+
+/// splits a status message into the presence tag and the message the user wrote
+pub fn parse_status(status: &str) -> (Presence, &str) {
+    Presence::all()
+        .into_iter()
+        .find_map(|presence| {
+            presence
+                .tag()
+                .and_then(|tag| status.strip_prefix(tag))
+                .map(|message| (presence, message.trim_start()))
+        })
+        .unwrap_or((Presence::Online, status))
+}
+
+/// the status message published for `presence`. `message` may already carry a tag
+pub fn tag_status(presence: Presence, message: &str) -> String {
+    let (_, message) = parse_status(message);
+    match presence.tag() {
+        Some(tag) if message.is_empty() => tag.to_string(),
+        Some(tag) => format!("{} {}", tag, message),
+        None => message.to_string(),
+    }
+}
+
+/// what the friend with `did` shows to us. None means they are offline or invisible
+pub async fn friend_presence(account: &Account, did: &DID) -> Option<Presence> {
+    match account.identity_status(did).await {
+        Ok(IdentityStatus::Online) => {}
+        _ => return None,
+    }
+    let status_message = account
+        .get_identity(did.clone().into())
+        .await
+        .ok()
+        .and_then(|identities| identities.first().cloned())
+        .and_then(|identity| identity.status_message())
+        .unwrap_or_default();
+    match parse_status(&status_message).0 {
+        Presence::Invisible => None,
+        presence => Some(presence),
+    }
+}
+
+/// tags our status message with `presence`. nothing is sent if the status message already has the tag
+pub async fn publish(account: &mut Account, presence: Presence) -> Result<(), Error> {
+    let current = account
+        .get_own_identity()
+        .await?
+        .status_message()
+        .unwrap_or_default();
+    let status = tag_status(presence, &current);
+    if status == current {
+        return Ok(());
+    }
+    let status = (!status.is_empty()).then_some(status);
+    account
+        .update_identity(IdentityUpdate::set_status_message(status))
+        .await
+}
+
+/// adds `did` to the users checked by `watch_presence` and returns the receiver of their presence changes
+pub fn watch(did: &DID) -> watch::Receiver<HashMap<String, Presence>> {
+    WATCHED.write().insert(did.to_string(), did.clone());
+    PRESENCES.subscribe()
+}
+
+/// the last known presence of `did`. None means they are offline, invisible or not watched
+pub fn cached(presences: &HashMap<String, Presence>, did: &DID) -> Option<Presence> {
+    presences.get(&did.to_string()).copied()
+}
+
+/// keeps the presence of friends and watched users up to date. one instance runs for the whole app, so the
+/// indicators don't ask for identities themselves
+pub async fn watch_presence(account: Account) {
+    loop {
+        let mut users = WATCHED.read().clone();
+        for did in account.list_friends().await.unwrap_or_default() {
+            users.insert(did.to_string(), did);
+        }
+        let mut presences = HashMap::new();
+        for (key, did) in users {
+            if let Some(presence) = friend_presence(&account, &did).await {
+                presences.insert(key, presence);
+            }
+        }
+        PRESENCES.send_if_modified(|current| {
+            let changed = *current != presences;
+            *current = presences;
+            changed
+        });
+        tokio::time::sleep(REFRESH_INTERVAL).await;
+    }
+}
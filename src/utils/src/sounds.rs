@@ -1,32 +1,473 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
 use soloud::*;
 
+use crate::DEFAULT_PATH;
+
+// how often the audio thread checks whether the current sound has finished while others are queued
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+// sounds requested while this many are waiting are dropped, so a burst of events doesn't play for minutes
+const MAX_QUEUED: usize = 3;
+// the file extensions looked for in a sound pack, in order of preference
+const PACK_EXTENSIONS: [&str; 4] = ["ogg", "wav", "mp3", "flac"];
+
+static AUDIO: Lazy<AudioService> = Lazy::new(|| AudioService::new(default_output));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Sounds {
     Notification,
     FriendReq,
     General,
 }
 
+impl Sounds {
+    pub fn all() -> Vec<Sounds> {
+        vec![Sounds::Notification, Sounds::FriendReq, Sounds::General]
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Sounds::Notification => "Notifications",
+            Sounds::FriendReq => "Friend Requests",
+            Sounds::General => "General",
+        }
+        .to_string()
+    }
+
+    /// the name of the file, without an extension, which replaces this sound in a sound pack
+    pub fn file_stem(&self) -> &'static str {
+        match self {
+            Sounds::Notification => "notification",
+            Sounds::FriendReq => "friend_request",
+            Sounds::General => "general",
+        }
+    }
+
+    fn bundled(&self) -> &'static [u8] {
+        match self {
+            Sounds::Notification => include_bytes!("../../../extra/assets/sounds/Ponderous.ogg"),
+            Sounds::FriendReq | Sounds::General => {
+                include_bytes!("../../../extra/assets/sounds/Success.ogg")
+            }
+        }
+    }
+}
+
+/// stored in the config and passed to the audio service with `configure`
+// missing fields fall back to their default so that new settings don't invalidate existing configs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoundSettings {
+    /// from 0.0 to 1.0
+    pub volume: f32,
+    pub notification: bool,
+    pub friend_request: bool,
+    pub general: bool,
+    /// the name of a directory in `DEFAULT_PATH/sounds`. sounds missing from the pack use the bundled ones
+    pub pack: Option<String>,
+}
+
+impl Default for SoundSettings {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            notification: true,
+            friend_request: true,
+            general: true,
+            pack: None,
+        }
+    }
+}
+
+impl SoundSettings {
+    pub fn is_enabled(&self, sound: Sounds) -> bool {
+        match sound {
+            Sounds::Notification => self.notification,
+            Sounds::FriendReq => self.friend_request,
+            Sounds::General => self.general,
+        }
+    }
+
+    pub fn set_enabled(&mut self, sound: Sounds, enabled: bool) {
+        match sound {
+            Sounds::Notification => self.notification = enabled,
+            Sounds::FriendReq => self.friend_request = enabled,
+            Sounds::General => self.general = enabled,
+        }
+    }
+}
+
+pub fn packs_dir() -> PathBuf {
+    DEFAULT_PATH.read().join("sounds")
+}
+
+/// the names of the installed sound packs
+pub fn list_packs() -> Vec<String> {
+    let mut packs: Vec<String> = fs::read_dir(packs_dir())
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().is_dir())
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    packs.sort();
+    packs
+}
+
+// reads the sound from the pack in `packs`, or in `packs_dir()` when it is None, falling back to the bundled sound
+fn load_sound(sound: Sounds, pack: Option<&str>, packs: Option<&Path>) -> Vec<u8> {
+    let from_pack = pack.and_then(|pack| {
+        let dir = packs
+            .map(Path::to_path_buf)
+            .unwrap_or_else(packs_dir)
+            .join(pack);
+        PACK_EXTENSIONS
+            .iter()
+            .map(|ext| dir.join(format!("{}.{}", sound.file_stem(), ext)))
+            .find(|path| path.is_file())
+            .and_then(|path| match fs::read(&path) {
+                Ok(bytes) => Some(bytes),
+                Err(e) => {
+                    log::error!("failed to read {}: {}", path.to_string_lossy(), e);
+                    None
+                }
+            })
+    });
+    from_pack.unwrap_or_else(|| sound.bundled().to_vec())
+}
+
+/// where the audio service sends sounds
+pub trait AudioOutput {
+    fn play(&mut self, data: &[u8], volume: f32) -> Result<(), String>;
+    fn is_playing(&self) -> bool;
+}
+
+pub struct SoloudOutput {
+    sl: Soloud,
+    // dropping the Wav stops it, so the sound being played is kept here
+    wav: Option<audio::Wav>,
+}
+
+impl SoloudOutput {
+    pub fn new(sl: Soloud) -> Self {
+        Self { sl, wav: None }
+    }
+}
+
+impl AudioOutput for SoloudOutput {
+    fn play(&mut self, data: &[u8], volume: f32) -> Result<(), String> {
+        let mut wav = audio::Wav::default();
+        wav.load_mem(data).map_err(|e| e.to_string())?;
+        self.sl.set_global_volume(volume);
+        self.sl.play(&wav);
+        self.wav = Some(wav);
+        Ok(())
+    }
+
+    fn is_playing(&self) -> bool {
+        self.sl.voice_count() > 0
+    }
+}
+
+/// discards every sound. used when there is no audio device, such as in headless tests
+#[derive(Default, Clone)]
+pub struct NullOutput {
+    plays: Arc<AtomicUsize>,
+}
+
+impl NullOutput {
+    /// the number of sounds played by this output and its clones
+    pub fn play_count(&self) -> usize {
+        self.plays.load(Ordering::SeqCst)
+    }
+}
+
+impl AudioOutput for NullOutput {
+    fn play(&mut self, _data: &[u8], _volume: f32) -> Result<(), String> {
+        self.plays.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn is_playing(&self) -> bool {
+        false
+    }
+}
+
+fn default_output() -> Box<dyn AudioOutput> {
+    match Soloud::default() {
+        Ok(sl) => Box::new(SoloudOutput::new(sl)),
+        Err(e) => {
+            log::error!(
+                "failed to open the audio device, sounds are disabled: {}",
+                e
+            );
+            Box::new(NullOutput::default())
+        }
+    }
+}
+
+enum Command {
+    Play(Sounds),
+    Configure(SoundSettings),
+}
+
+/// plays sounds one after another on a background thread, so callers never wait for playback
+pub struct AudioService {
+    // the Sender is not Sync, and the service is shared through a static
+    tx: Mutex<mpsc::Sender<Command>>,
+}
+
+impl AudioService {
+    /// the output is created on the audio thread since the Soloud handle can't be moved between threads
+    pub fn new<F>(make_output: F) -> Self
+    where
+        F: FnOnce() -> Box<dyn AudioOutput> + Send + 'static,
+    {
+        Self::spawn(make_output, None)
+    }
+
+    /// looks for sound packs in `packs` instead of `packs_dir()`
+    pub fn with_packs_dir<F>(make_output: F, packs: PathBuf) -> Self
+    where
+        F: FnOnce() -> Box<dyn AudioOutput> + Send + 'static,
+    {
+        Self::spawn(make_output, Some(packs))
+    }
+
+    fn spawn<F>(make_output: F, packs: Option<PathBuf>) -> Self
+    where
+        F: FnOnce() -> Box<dyn AudioOutput> + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || run(make_output(), rx, packs));
+        Self { tx: Mutex::new(tx) }
+    }
+
+    pub fn play(&self, sound: Sounds) {
+        self.send(Command::Play(sound));
+    }
+
+    pub fn configure(&self, settings: SoundSettings) {
+        self.send(Command::Configure(settings));
+    }
+
+    fn send(&self, command: Command) {
+        let result = match self.tx.lock() {
+            Ok(tx) => tx.send(command).map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = result {
+            log::error!("failed to reach the audio thread: {}", e);
+        }
+    }
+}
+
+fn run(mut output: Box<dyn AudioOutput>, rx: mpsc::Receiver<Command>, packs: Option<PathBuf>) {
+    let mut settings = SoundSettings::default();
+    let mut cache: HashMap<Sounds, Vec<u8>> = HashMap::new();
+    let mut queue: VecDeque<Sounds> = VecDeque::new();
+    loop {
+        // there is nothing to check until a command arrives when the queue is empty
+        let command = if queue.is_empty() {
+            rx.recv().map_err(|_| RecvTimeoutError::Disconnected)
+        } else {
+            rx.recv_timeout(POLL_INTERVAL)
+        };
+        match command {
+            Ok(Command::Play(sound)) => {
+                if settings.is_enabled(sound) && queue.len() < MAX_QUEUED {
+                    queue.push_back(sound);
+                }
+            }
+            Ok(Command::Configure(new_settings)) => {
+                if new_settings.pack != settings.pack {
+                    cache.clear();
+                }
+                settings = new_settings;
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+
+        if output.is_playing() {
+            continue;
+        }
+        if let Some(sound) = queue.pop_front() {
+            let pack = settings.pack.clone();
+            let data = cache
+                .entry(sound)
+                .or_insert_with(|| load_sound(sound, pack.as_deref(), packs.as_deref()));
+            if let Err(e) = output.play(data, settings.volume.clamp(0.0, 1.0)) {
+                log::error!("failed to play {:?}: {}", sound, e);
+            }
+        }
+    }
+}
+
+/// queues a sound on the shared audio service and returns immediately
 #[allow(non_snake_case)]
 pub fn Play(sound: Sounds) {
-    // Create a Soloud instance
-    let sl = Soloud::default().unwrap();
-    // Create a Wav instance
-    let mut wav = audio::Wav::default();
-    // Load the appropriate sound file based on the `sound` argument
-    match sound {
-        Sounds::Notification => wav
-            .load_mem(include_bytes!("../../../extra/assets/sounds/Ponderous.ogg"))
-            .unwrap(),
-        Sounds::FriendReq => wav
-            .load_mem(include_bytes!("../../../extra/assets/sounds/Success.ogg"))
-            .unwrap(),
-        // The `General` case is not handled
-        Sounds::General => {}
-    };
-    // Play the sound
-    sl.play(&wav);
-    // Wait until the sound finishes playing
-    while sl.voice_count() > 0 {
-        std::thread::sleep(std::time::Duration::from_millis(100));
+    AUDIO.play(sound);
+}
+
+/// applies new volume, pack and per-event settings to the shared audio service
+pub fn configure(settings: SoundSettings) {
+    AUDIO.configure(settings);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    // long enough for the audio thread, short enough to keep the tests quick
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    // a NullOutput which also remembers what it was given
+    #[derive(Default, Clone)]
+    struct Recorder {
+        output: NullOutput,
+        played: Arc<Mutex<Vec<Vec<u8>>>>,
+    }
+
+    impl AudioOutput for Recorder {
+        fn play(&mut self, data: &[u8], volume: f32) -> Result<(), String> {
+            self.played.lock().unwrap().push(data.to_vec());
+            self.output.play(data, volume)
+        }
+
+        fn is_playing(&self) -> bool {
+            false
+        }
+    }
+
+    impl Recorder {
+        fn wait_for(&self, count: usize) -> Vec<Vec<u8>> {
+            let start = Instant::now();
+            while self.output.play_count() < count && start.elapsed() < TIMEOUT {
+                thread::sleep(Duration::from_millis(5));
+            }
+            self.played.lock().unwrap().clone()
+        }
+    }
+
+    // a directory holding one pack with only the notification sound
+    fn packs(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("uplink-sounds-{}", std::process::id()))
+            .join(name);
+        fs::create_dir_all(dir.join("pack")).unwrap();
+        fs::write(dir.join("pack").join("notification.wav"), b"from the pack").unwrap();
+        dir
+    }
+
+    fn service(recorder: &Recorder, packs: PathBuf) -> AudioService {
+        let output = recorder.clone();
+        AudioService::with_packs_dir(move || Box::new(output), packs)
+    }
+
+    #[test]
+    fn bundled_sounds_without_a_pack() {
+        let recorder = Recorder::default();
+        let audio = service(&recorder, packs("bundled"));
+        audio.play(Sounds::Notification);
+        assert_eq!(
+            recorder.wait_for(1),
+            vec![Sounds::Notification.bundled().to_vec()]
+        );
+    }
+
+    #[test]
+    fn pack_sounds_with_fallback() {
+        let recorder = Recorder::default();
+        let audio = service(&recorder, packs("pack"));
+        audio.configure(SoundSettings {
+            pack: Some(String::from("pack")),
+            ..SoundSettings::default()
+        });
+        audio.play(Sounds::Notification);
+        // the pack has no friend request sound
+        audio.play(Sounds::FriendReq);
+        assert_eq!(
+            recorder.wait_for(2),
+            vec![
+                b"from the pack".to_vec(),
+                Sounds::FriendReq.bundled().to_vec()
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_pack_uses_bundled_sounds() {
+        let recorder = Recorder::default();
+        let audio = service(&recorder, packs("missing"));
+        audio.configure(SoundSettings {
+            pack: Some(String::from("not installed")),
+            ..SoundSettings::default()
+        });
+        audio.play(Sounds::Notification);
+        assert_eq!(
+            recorder.wait_for(1),
+            vec![Sounds::Notification.bundled().to_vec()]
+        );
+    }
+
+    #[test]
+    fn disabled_sounds_are_skipped() {
+        let recorder = Recorder::default();
+        let audio = service(&recorder, packs("disabled"));
+        let mut settings = SoundSettings::default();
+        settings.set_enabled(Sounds::FriendReq, false);
+        audio.configure(settings);
+        audio.play(Sounds::FriendReq);
+        audio.play(Sounds::General);
+        assert_eq!(
+            recorder.wait_for(1),
+            vec![Sounds::General.bundled().to_vec()]
+        );
+    }
+
+    // an output which is busy until the test ends
+    struct Busy(NullOutput);
+
+    impl AudioOutput for Busy {
+        fn play(&mut self, data: &[u8], volume: f32) -> Result<(), String> {
+            self.0.play(data, volume)
+        }
+
+        fn is_playing(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn play_does_not_wait_for_playback() {
+        let output = NullOutput::default();
+        let busy = Busy(output.clone());
+        let audio = AudioService::new(move || Box::new(busy));
+        let start = Instant::now();
+        for _ in 0..MAX_QUEUED * 10 {
+            audio.play(Sounds::General);
+        }
+        assert!(start.elapsed() < Duration::from_secs(1));
+        // nothing is played while the output is busy
+        thread::sleep(POLL_INTERVAL * 2);
+        assert_eq!(output.play_count(), 0);
     }
 }
@@ -0,0 +1,227 @@
+//! typed progress of file transfers. Constellation reports progress as a stream of `Progression`, which is turned
+//! into events that any component can subscribe to, whether or not the files page is mounted.
+
+use futures::{Stream, StreamExt};
+use once_cell::sync::Lazy;
+use tokio::sync::broadcast;
+use warp::constellation::Progression;
+
+// events a slow subscriber may fall behind by before it misses some
+const CHANNEL_CAPACITY: usize = 256;
+
+pub static TRANSFERS: Lazy<TransferManager> = Lazy::new(TransferManager::new);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransferEvent {
+    Queued {
+        id: u64,
+        name: String,
+    },
+    Progress {
+        id: u64,
+        current: usize,
+        total: Option<usize>,
+    },
+    Completed {
+        id: u64,
+    },
+    Failed {
+        id: u64,
+        error: String,
+    },
+    /// stopped by the user
+    Cancelled {
+        id: u64,
+    },
+}
+
+impl TransferEvent {
+    pub fn id(&self) -> u64 {
+        match self {
+            TransferEvent::Queued { id, .. }
+            | TransferEvent::Progress { id, .. }
+            | TransferEvent::Completed { id }
+            | TransferEvent::Failed { id, .. }
+            | TransferEvent::Cancelled { id } => *id,
+        }
+    }
+
+    /// the event for a progress report of transfer `id`
+    pub fn from_progression(id: u64, progression: Progression) -> Self {
+        match progression {
+            Progression::CurrentProgress { current, total, .. } => {
+                TransferEvent::Progress { id, current, total }
+            }
+            Progression::ProgressComplete { .. } => TransferEvent::Completed { id },
+            Progression::ProgressFailed { error, .. } => TransferEvent::Failed {
+                id,
+                error: error.unwrap_or_else(|| String::from("the transfer failed")),
+            },
+        }
+    }
+
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            TransferEvent::Completed { .. }
+                | TransferEvent::Failed { .. }
+                | TransferEvent::Cancelled { .. }
+        )
+    }
+}
+
+/// broadcasts transfer events. events sent without subscribers are dropped
+pub struct TransferManager {
+    sender: broadcast::Sender<TransferEvent>,
+}
+
+impl TransferManager {
+    fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self { sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<TransferEvent> {
+        self.sender.subscribe()
+    }
+
+    pub fn emit(&self, event: TransferEvent) {
+        // an error only means that nobody is listening
+        let _ = self.sender.send(event);
+    }
+}
+
+/// the events of one transfer, ending with `Completed` or `Failed`
+pub struct TransferProgress<S> {
+    id: u64,
+    progress: S,
+    finished: bool,
+}
+
+impl<S> TransferProgress<S>
+where
+    S: Stream<Item = Progression> + Unpin,
+{
+    pub fn new(id: u64, progress: S) -> Self {
+        Self {
+            id,
+            progress,
+            finished: false,
+        }
+    }
+
+    /// a stream which ends without reporting the result counts as completed, since Constellation doesn't always
+    /// report it
+    pub async fn next(&mut self) -> Option<TransferEvent> {
+        if self.finished {
+            return None;
+        }
+        let event = match self.progress.next().await {
+            Some(progression) => TransferEvent::from_progression(self.id, progression),
+            None => TransferEvent::Completed { id: self.id },
+        };
+        self.finished = event.is_final();
+        Some(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake_constellation::{self, complete, current, failed, FakeConstellation};
+
+    async fn events_of(progress: Vec<Progression>) -> Vec<Option<TransferEvent>> {
+        let mut storage = FakeConstellation::storage(progress);
+        let stream = fake_constellation::upload(&mut storage, "file.txt").await;
+        let mut progress = TransferProgress::new(7, stream);
+        let mut events = vec![];
+        for _ in 0..5 {
+            events.push(progress.next().await);
+        }
+        events
+    }
+
+    #[tokio::test]
+    async fn progress_then_completed() {
+        let events = events_of(vec![
+            current("file.txt", 5, 10),
+            current("file.txt", 10, 10),
+            complete("file.txt", 10),
+        ])
+        .await;
+        assert_eq!(
+            events,
+            vec![
+                Some(TransferEvent::Progress {
+                    id: 7,
+                    current: 5,
+                    total: Some(10)
+                }),
+                Some(TransferEvent::Progress {
+                    id: 7,
+                    current: 10,
+                    total: Some(10)
+                }),
+                Some(TransferEvent::Completed { id: 7 }),
+                None,
+                None,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn progress_failed_is_failed() {
+        let events = events_of(vec![
+            current("file.txt", 5, 10),
+            failed("file.txt", "disk full"),
+            // nothing after the failure is reported
+            complete("file.txt", 10),
+        ])
+        .await;
+        assert_eq!(
+            events[1],
+            Some(TransferEvent::Failed {
+                id: 7,
+                error: String::from("disk full")
+            })
+        );
+        assert_eq!(events[2], None);
+    }
+
+    #[tokio::test]
+    async fn ending_early_is_completed() {
+        let events = events_of(vec![current("file.txt", 5, 10)]).await;
+        assert_eq!(events[1], Some(TransferEvent::Completed { id: 7 }));
+        assert_eq!(events[2], None);
+    }
+
+    #[tokio::test]
+    async fn subscribers_receive_events() {
+        let mut events = TRANSFERS.subscribe();
+        TRANSFERS.emit(TransferEvent::Progress {
+            id: 1_000,
+            current: 1,
+            total: None,
+        });
+        TRANSFERS.emit(TransferEvent::Completed { id: 1_000 });
+        let mut received = vec![];
+        // other tests share the manager
+        while received.len() < 2 {
+            let event = events.recv().await.unwrap();
+            if event.id() == 1_000 {
+                received.push(event);
+            }
+        }
+        assert_eq!(
+            received,
+            vec![
+                TransferEvent::Progress {
+                    id: 1_000,
+                    current: 1,
+                    total: None
+                },
+                TransferEvent::Completed { id: 1_000 }
+            ]
+        );
+    }
+}
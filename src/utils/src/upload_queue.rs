@@ -0,0 +1,585 @@
+//! the files waiting to be uploaded to Constellation. the queue is global so that uploads continue, and can be
+//! followed, after leaving the files page. directories are expanded so that their tree is recreated.
+
+use std::{
+    fs,
+    future::Future,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use futures::{
+    stream::{BoxStream, FuturesUnordered},
+    Stream, StreamExt,
+};
+use once_cell::sync::Lazy;
+use tokio_util::io::ReaderStream;
+use warp::{constellation::Progression, sync::RwLock};
+
+use crate::{
+    files_functions,
+    transfers::{TransferEvent, TransferProgress, TRANSFERS},
+    Storage,
+};
+
+/// the Constellation directory where the app keeps its own files. hidden from the file browser
+pub const APP_DIRECTORY: &str = ".uplink";
+
+/// the number of files uploaded at the same time
+pub const MAX_CONCURRENT_UPLOADS: usize = 3;
+// how often the queue is checked for new uploads, pauses and cancellations
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+pub static UPLOAD_QUEUE: Lazy<RwLock<UploadQueue>> =
+    Lazy::new(|| RwLock::new(UploadQueue::default()));
+
+// uploading selects the target directory, which is shared with the file browser. only one upload may change it at
+// a time
+static DIRECTORY_LOCK: Lazy<tokio::sync::Mutex<()>> = Lazy::new(|| tokio::sync::Mutex::new(()));
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UploadStatus {
+    Queued,
+    Uploading {
+        current: usize,
+        total: Option<usize>,
+    },
+    Done,
+    Failed(String),
+    Cancelled,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Upload {
+    pub id: u64,
+    /// a file, or an empty directory which only has to be created
+    pub local_path: PathBuf,
+    /// the Constellation directory the file is uploaded to
+    pub remote_dir: PathBuf,
+    pub status: UploadStatus,
+    pub paused: bool,
+}
+
+impl Upload {
+    pub fn name(&self) -> String {
+        self.local_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+
+    /// the upload can't change anymore, unless it is retried
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self.status,
+            UploadStatus::Done | UploadStatus::Failed(_) | UploadStatus::Cancelled
+        )
+    }
+
+    /// 0 to 100
+    pub fn percentage(&self) -> usize {
+        match self.status {
+            UploadStatus::Uploading {
+                current,
+                total: Some(total),
+            } if total > 0 => ((current as f64 / total as f64) * 100.) as usize,
+            UploadStatus::Done => 100,
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct UploadQueue {
+    next_id: u64,
+    pub uploads: Vec<Upload>,
+}
+
+impl UploadQueue {
+    fn push(&mut self, local_path: PathBuf, remote_dir: PathBuf) -> TransferEvent {
+        self.next_id += 1;
+        let upload = Upload {
+            id: self.next_id,
+            local_path,
+            remote_dir,
+            status: UploadStatus::Queued,
+            paused: false,
+        };
+        let event = TransferEvent::Queued {
+            id: upload.id,
+            name: upload.name(),
+        };
+        self.uploads.push(upload);
+        event
+    }
+
+    fn get_mut(&mut self, id: u64) -> Option<&mut Upload> {
+        self.uploads.iter_mut().find(|upload| upload.id == id)
+    }
+
+    fn get(&self, id: u64) -> Option<&Upload> {
+        self.uploads.iter().find(|upload| upload.id == id)
+    }
+
+    /// updates the status of the upload the event is about
+    pub fn apply(&mut self, event: &TransferEvent) {
+        let upload = match self.get_mut(event.id()) {
+            Some(upload) => upload,
+            None => return,
+        };
+        // a cancelled upload stays cancelled, even if its last chunk arrives. retrying queues it again
+        if upload.status == UploadStatus::Cancelled
+            && !matches!(event, TransferEvent::Queued { .. })
+        {
+            return;
+        }
+        upload.status = match event {
+            TransferEvent::Queued { .. } => UploadStatus::Queued,
+            TransferEvent::Progress { current, total, .. } => UploadStatus::Uploading {
+                current: *current,
+                total: *total,
+            },
+            TransferEvent::Completed { .. } => UploadStatus::Done,
+            TransferEvent::Failed { error, .. } => UploadStatus::Failed(error.clone()),
+            TransferEvent::Cancelled { .. } => UploadStatus::Cancelled,
+        };
+    }
+
+    pub fn pending(&self) -> usize {
+        self.uploads
+            .iter()
+            .filter(|upload| !upload.is_finished())
+            .count()
+    }
+
+    // the uploads which are sending data. paused ones give up their slot, so that the queue keeps moving
+    fn uploading(&self) -> usize {
+        self.uploads
+            .iter()
+            .filter(|upload| {
+                matches!(upload.status, UploadStatus::Uploading { .. }) && !upload.paused
+            })
+            .count()
+    }
+
+    // marks the oldest queued upload which isn't paused as started
+    fn start_next(&mut self) -> Option<Upload> {
+        let upload = self
+            .uploads
+            .iter_mut()
+            .find(|upload| upload.status == UploadStatus::Queued && !upload.paused)?;
+        upload.status = UploadStatus::Uploading {
+            current: 0,
+            total: None,
+        };
+        Some(upload.clone())
+    }
+}
+
+/// queues the files and directories at `paths` to be uploaded into `remote_dir`
+pub fn enqueue(paths: Vec<PathBuf>, remote_dir: PathBuf) {
+    let mut files = vec![];
+    for path in paths {
+        expand(&path, &remote_dir, &mut files);
+    }
+    let events: Vec<TransferEvent> = {
+        let mut queue = UPLOAD_QUEUE.write();
+        files
+            .into_iter()
+            .map(|(local_path, remote_dir)| queue.push(local_path, remote_dir))
+            .collect()
+    };
+    for event in events {
+        TRANSFERS.emit(event);
+    }
+}
+
+/// paused uploads aren't started, and started ones stop sending data
+pub fn set_paused(id: u64, paused: bool) {
+    if let Some(upload) = UPLOAD_QUEUE.write().get_mut(id) {
+        upload.paused = paused;
+    }
+}
+
+pub fn cancel(id: u64) {
+    let unfinished = UPLOAD_QUEUE
+        .read()
+        .get(id)
+        .map(|upload| !upload.is_finished())
+        .unwrap_or(false);
+    if unfinished {
+        report(TransferEvent::Cancelled { id });
+    }
+}
+
+/// queues a failed or cancelled upload again
+pub fn retry(id: u64) {
+    let name = match UPLOAD_QUEUE.write().get_mut(id) {
+        Some(upload)
+            if matches!(
+                upload.status,
+                UploadStatus::Failed(_) | UploadStatus::Cancelled
+            ) =>
+        {
+            upload.paused = false;
+            upload.name()
+        }
+        _ => return,
+    };
+    report(TransferEvent::Queued { id, name });
+}
+
+/// removes the finished uploads from the list
+pub fn clear_finished() {
+    UPLOAD_QUEUE
+        .write()
+        .uploads
+        .retain(|upload| !upload.is_finished());
+}
+
+// updates the queue and tells the subscribers
+fn report(event: TransferEvent) {
+    UPLOAD_QUEUE.write().apply(&event);
+    TRANSFERS.emit(event);
+}
+
+// the files in the tree at `path`, with the remote directories which recreate the tree. empty directories are kept
+// so that they are created too
+fn expand(path: &Path, remote_dir: &Path, files: &mut Vec<(PathBuf, PathBuf)>) {
+    if !path.is_dir() {
+        files.push((path.to_path_buf(), remote_dir.to_path_buf()));
+        return;
+    }
+    let name = match path.file_name() {
+        Some(name) => name,
+        None => return,
+    };
+    let sub_dir = remote_dir.join(name);
+    let mut entries: Vec<PathBuf> = match fs::read_dir(path) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .collect(),
+        Err(e) => {
+            log::error!("failed to read the directory {}: {}", path.display(), e);
+            return;
+        }
+    };
+    if entries.is_empty() {
+        files.push((path.to_path_buf(), remote_dir.to_path_buf()));
+        return;
+    }
+    entries.sort();
+    for entry in entries {
+        expand(&entry, &sub_dir, files);
+    }
+}
+
+/// uploads the queued files, a few at a time. runs for as long as the app
+pub async fn run(storage: Storage) {
+    let mut running = FuturesUnordered::new();
+    loop {
+        while UPLOAD_QUEUE.read().uploading() < MAX_CONCURRENT_UPLOADS {
+            let upload = match UPLOAD_QUEUE.write().start_next() {
+                Some(upload) => upload,
+                None => break,
+            };
+            running.push(upload_one(storage.clone(), upload));
+        }
+        tokio::select! {
+            Some(_) = running.next(), if !running.is_empty() => {}
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+        }
+    }
+}
+
+async fn upload_one(mut storage: Storage, upload: Upload) {
+    if let Err(error) = upload_to_directory(&mut storage, &upload).await {
+        log::error!(
+            "failed to upload {}: {}",
+            upload.local_path.display(),
+            error
+        );
+        report(TransferEvent::Failed {
+            id: upload.id,
+            error,
+        });
+    }
+}
+
+async fn upload_to_directory(storage: &mut Storage, upload: &Upload) -> Result<(), String> {
+    let is_dir = upload.local_path.is_dir();
+    let name = upload.name();
+    let started = {
+        let _lock = DIRECTORY_LOCK.lock().await;
+        let previous_path = storage.get_path();
+        let result = start_upload(storage, upload, is_dir, &name).await;
+        storage.set_path(previous_path);
+        result?
+    };
+    // the name can differ from the local one when a file with that name exists
+    let (filename, mut progress) = match started {
+        Some((filename, progress)) => (filename, TransferProgress::new(upload.id, progress)),
+        // an empty directory, which is done once it exists
+        None => {
+            report(TransferEvent::Completed { id: upload.id });
+            return Ok(());
+        }
+    };
+
+    if !forward_progress(upload.id, &mut progress).await? {
+        return Ok(());
+    }
+
+    if !is_dir && files_functions::has_thumbnail(&filename) {
+        // made from the local copy, so that the file isn't downloaded again and the lock isn't held meanwhile
+        match files_functions::local_thumbnail(upload.local_path.clone()).await {
+            Ok(thumbnail) => {
+                let _lock = DIRECTORY_LOCK.lock().await;
+                let previous_path = storage.get_path();
+                storage.set_path(upload.remote_dir.clone());
+                match storage
+                    .current_directory()
+                    .and_then(|directory| directory.get_item(&filename))
+                {
+                    Ok(item) => item.set_thumbnail(&thumbnail),
+                    Err(e) => log::error!("failed to find the uploaded file {}: {}", filename, e),
+                }
+                storage.set_path(previous_path);
+            }
+            Err(e) => log::error!(
+                "failed to make the thumbnail of {}: {}",
+                upload.local_path.display(),
+                e
+            ),
+        }
+    }
+    report(TransferEvent::Completed { id: upload.id });
+    Ok(())
+}
+
+// reports the progress until the upload ends. false when it was cancelled
+async fn forward_progress<S>(id: u64, progress: &mut TransferProgress<S>) -> Result<bool, String>
+where
+    S: Stream<Item = Progression> + Unpin,
+{
+    loop {
+        if is_cancelled(id) {
+            // dropping the progress stream stops the upload before the file is added
+            return Ok(false);
+        }
+        let event = tokio::select! {
+            event = progress.next() => event,
+            _ = tokio::time::sleep(POLL_INTERVAL) => continue,
+        };
+        match event {
+            Some(TransferEvent::Failed { error, .. }) => return Err(error),
+            Some(TransferEvent::Completed { .. }) | None => return Ok(true),
+            Some(event) => report(event),
+        }
+    }
+}
+
+// creates the remote directory and starts the upload inside it. must be called with the directory lock held
+async fn start_upload(
+    storage: &mut Storage,
+    upload: &Upload,
+    is_dir: bool,
+    name: &str,
+) -> Result<Option<(String, BoxStream<'static, Progression>)>, String> {
+    let remote_dir = if is_dir {
+        upload.remote_dir.join(name)
+    } else {
+        upload.remote_dir.clone()
+    };
+    create_directories(storage, &remote_dir).await?;
+    if is_dir {
+        return Ok(None);
+    }
+
+    storage.set_path(upload.remote_dir.clone());
+    let current_directory = storage.current_directory().map_err(|e| e.to_string())?;
+    let filename = files_functions::verify_duplicate_name(
+        current_directory,
+        name.to_string(),
+        PathBuf::from(name),
+    );
+    let file = tokio::fs::File::open(&upload.local_path)
+        .await
+        .map_err(|e| e.to_string())?;
+    let total_size = file.metadata().await.ok().map(|data| data.len() as usize);
+    let id = upload.id;
+    let file_stream = ReaderStream::new(file)
+        .filter_map(|chunk| async { chunk.ok() })
+        .then(move |chunk| async move {
+            // holding the chunk back pauses the upload
+            while is_paused(id) && !is_cancelled(id) {
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+            chunk.into()
+        });
+    let progress = storage
+        .put_stream(&filename, total_size, file_stream.boxed())
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(Some((filename, progress)))
+}
+
+/// runs `f` with `path` as the current directory of `storage`, creating it when it is missing. the previous
+/// directory is restored afterwards, and other directory changes of the app wait until then.
+pub async fn in_directory<F, Fut, T>(storage: &mut Storage, path: &Path, f: F) -> Result<T, String>
+where
+    F: FnOnce(Storage) -> Fut,
+    Fut: Future<Output = T>,
+{
+    let _lock = DIRECTORY_LOCK.lock().await;
+    let previous_path = storage.get_path();
+    let result = match create_directories(storage, path).await {
+        Ok(()) => {
+            storage.set_path(path.to_path_buf());
+            Ok(f(storage.clone()).await)
+        }
+        Err(e) => Err(e),
+    };
+    storage.set_path(previous_path);
+    result
+}
+
+// creates every missing directory on the way to `path`, starting at the root
+async fn create_directories(storage: &mut Storage, path: &Path) -> Result<(), String> {
+    let mut current = PathBuf::new();
+    for component in path.iter() {
+        let name = component.to_string_lossy().to_string();
+        storage.set_path(current.clone());
+        let exists = storage
+            .current_directory()
+            .map(|dir| dir.has_item(&name))
+            .map_err(|e| e.to_string())?;
+        if !exists {
+            storage
+                .create_directory(&name, true)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        current.push(&name);
+    }
+    Ok(())
+}
+
+fn is_paused(id: u64) -> bool {
+    UPLOAD_QUEUE
+        .read()
+        .get(id)
+        .map(|upload| upload.paused)
+        .unwrap_or(false)
+}
+
+fn is_cancelled(id: u64) -> bool {
+    UPLOAD_QUEUE
+        .read()
+        .get(id)
+        .map(|upload| upload.status == UploadStatus::Cancelled)
+        .unwrap_or(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake_constellation::{self, complete, current, failed, FakeConstellation};
+
+    fn queued() -> u64 {
+        let event = UPLOAD_QUEUE
+            .write()
+            .push(PathBuf::from("/tmp/file.txt"), PathBuf::new());
+        event.id()
+    }
+
+    // the events about `id`, as they are received by a subscriber
+    async fn forwarded(
+        progress: Vec<Progression>,
+    ) -> (u64, Result<bool, String>, Vec<TransferEvent>) {
+        let id = queued();
+        let mut events = TRANSFERS.subscribe();
+        let mut storage = FakeConstellation::storage(progress);
+        let stream = fake_constellation::upload(&mut storage, "file.txt").await;
+        let result = forward_progress(id, &mut TransferProgress::new(id, stream)).await;
+        let mut received = vec![];
+        while let Ok(event) = events.try_recv() {
+            if event.id() == id {
+                received.push(event);
+            }
+        }
+        (id, result, received)
+    }
+
+    #[tokio::test]
+    async fn upload_progress_reaches_subscribers() {
+        let (id, result, received) = forwarded(vec![
+            current("file.txt", 4, 8),
+            current("file.txt", 8, 8),
+            complete("file.txt", 8),
+        ])
+        .await;
+        assert_eq!(result, Ok(true));
+        assert_eq!(
+            received,
+            vec![
+                TransferEvent::Progress {
+                    id,
+                    current: 4,
+                    total: Some(8)
+                },
+                TransferEvent::Progress {
+                    id,
+                    current: 8,
+                    total: Some(8)
+                },
+            ]
+        );
+        assert_eq!(
+            UPLOAD_QUEUE.read().get(id).unwrap().status,
+            UploadStatus::Uploading {
+                current: 8,
+                total: Some(8)
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_upload_is_an_error() {
+        let (_, result, _) = forwarded(vec![failed("file.txt", "no space")]).await;
+        assert_eq!(result, Err(String::from("no space")));
+    }
+
+    #[test]
+    fn paused_uploads_free_their_slot() {
+        let mut queue = UploadQueue::default();
+        for _ in 0..2 {
+            queue.push(PathBuf::from("/tmp/file.txt"), PathBuf::new());
+        }
+        let first = queue.start_next().unwrap().id;
+        assert_eq!(queue.uploading(), 1);
+        queue.get_mut(first).unwrap().paused = true;
+        assert_eq!(queue.uploading(), 0);
+        assert_ne!(queue.start_next().unwrap().id, first);
+        assert_eq!(queue.uploading(), 1);
+    }
+
+    #[tokio::test]
+    async fn cancel_is_broadcast() {
+        let id = queued();
+        let mut events = TRANSFERS.subscribe();
+        cancel(id);
+        let mut received = vec![];
+        while let Ok(event) = events.try_recv() {
+            if event.id() == id {
+                received.push(event);
+            }
+        }
+        assert_eq!(received, vec![TransferEvent::Cancelled { id }]);
+        assert_eq!(
+            UPLOAD_QUEUE.read().get(id).unwrap().status,
+            UploadStatus::Cancelled
+        );
+    }
+}